@@ -0,0 +1,1572 @@
+//! `har`: inspect, validate, convert, redact and replay HAR files from the
+//! command line, using the same engine as the desktop app.
+
+mod output;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use har_core::batch::{self, BatchItem, BatchOptions, Outcome};
+use har_core::ca::CertificateAuthority;
+use har_core::client::{Client, ClientConfig, HttpVersion};
+use har_core::compression::Compression;
+use har_core::cookies::CookieJar;
+use har_core::diff::{self, BodyDiff, DiffOptions, LineOp, ResponseDiff};
+use har_core::environment::{Environment, Retarget};
+use har_core::flow::{self, Extraction, FlowOptions, Source, StepLog};
+use har_core::group::{self, GroupBy};
+use har_core::mock::{MockOptions, MockServer, Strategy};
+use har_core::pages;
+use har_core::proxy::RecordingProxy;
+use har_core::redact::Redaction;
+use har_core::replay;
+use har_core::schedule::{self, ScheduleOptions, ScheduledItem};
+use har_core::summary::{self, Distribution};
+use har_core::{
+    validate, Diagnostic, Error, HarEntry, HarFile, HarSession, LoadOptions, ParseMode, Severity,
+};
+use output::Format;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+
+#[derive(Parser)]
+#[command(
+    name = "har",
+    version,
+    about = "Inspect, validate and replay HAR files"
+)]
+struct Cli {
+    /// Output format.
+    #[arg(long, short, value_enum, global = true, default_value_t = Format::Table)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Counts, sizes, status classes, MIME types, methods and timing
+    /// percentiles for the whole archive.
+    Summary {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// One line per entry, optionally filtered.
+    List {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Latency, errors and sizes per endpoint, with IDs in paths collapsed
+    /// into `{id}`, or per host; slowest first.
+    Endpoints {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        filter: Filter,
+        /// Group by host instead of by endpoint.
+        #[arg(long)]
+        by_host: bool,
+        /// Show only the slowest N groups.
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+    },
+    /// Load metrics for each page: requests, bytes, document time to first
+    /// byte, `pageTimings` and the longest chain of dependent requests.
+    Pages {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// The request and response of one entry.
+    Show {
+        #[command(flatten)]
+        input: Input,
+        /// Position of the entry, as printed by `list`.
+        index: usize,
+    },
+    /// Sends the request of one entry again.
+    Replay {
+        #[command(flatten)]
+        input: Input,
+        /// Position of the entry, as printed by `list`.
+        index: usize,
+        /// Upload a local file for a file param, as NAME=PATH; may be repeated.
+        #[arg(long = "attach", value_name = "NAME=PATH", value_parser = parse_attachment)]
+        attachments: Vec<(String, PathBuf)>,
+        /// Send the cookies the archive ends with, from every request and
+        /// Set-Cookie, instead of the entry's recorded Cookie header.
+        #[arg(long)]
+        cookie_jar: bool,
+        /// Print how the response differs from the recorded one instead of
+        /// the entry. Exits with status 1 if they differ.
+        #[arg(long)]
+        diff: bool,
+        /// With --diff, leave out JSON body values matching this JSONPath,
+        /// e.g. '$.meta.requestId'; may be repeated.
+        #[arg(long = "ignore-path", value_name = "JSONPATH", requires = "diff")]
+        ignore_paths: Vec<String>,
+        /// With --diff, also leave out this header; may be repeated. Date,
+        /// ETag, Set-Cookie and other volatile headers are always left out.
+        #[arg(long = "ignore-header", value_name = "NAME", requires = "diff")]
+        ignore_headers: Vec<String>,
+        #[command(flatten)]
+        environment: EnvironmentArgs,
+        #[command(flatten)]
+        client: ClientArgs,
+    },
+    /// Replays a range of entries in order, carrying extracted values from
+    /// one response into later requests. Exits with status 1 if a step fails.
+    Flow {
+        #[command(flatten)]
+        input: Input,
+        /// Position of the first entry, as printed by `list`.
+        first: usize,
+        /// Position of the last entry.
+        last: usize,
+        /// Capture a value for `{{NAME}}` from the response of a step,
+        /// counted from 0, as NAME@STEP=json:PATH, NAME@STEP=regex:RE or
+        /// NAME@STEP=header:NAME; may be repeated.
+        #[arg(long = "extract", value_name = "RULE", value_parser = parse_extraction)]
+        extractions: Vec<Extraction>,
+        /// Also replace each value the rules find in the recorded responses
+        /// wherever it appears in later recorded requests.
+        #[arg(long)]
+        replace_recorded: bool,
+        /// Send every step even after one fails.
+        #[arg(long)]
+        keep_going: bool,
+        /// Upload a local file for a file param, as NAME=PATH; may be repeated.
+        #[arg(long = "attach", value_name = "NAME=PATH", value_parser = parse_attachment)]
+        attachments: Vec<(String, PathBuf)>,
+        /// Start from the cookies the archive ends with instead of the
+        /// recorded Cookie headers, keeping those the flow receives.
+        #[arg(long)]
+        cookie_jar: bool,
+        #[command(flatten)]
+        environment: EnvironmentArgs,
+        #[command(flatten)]
+        client: ClientArgs,
+    },
+    /// Replays every entry matching the filter, several at a time, and
+    /// compares each status with the recorded one. Exits with status 1 if
+    /// any differs or fails.
+    Batch {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        filter: Filter,
+        /// Requests in flight at once.
+        #[arg(long, value_name = "N", default_value_t = 4)]
+        concurrency: usize,
+        /// Start at most this many requests per second.
+        #[arg(long, value_name = "N")]
+        rps: Option<f64>,
+        /// Attempts after a failure to send or a 429, 502, 503 or 504.
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        retries: u32,
+        /// Wait before the first retry in milliseconds, doubled after each.
+        #[arg(long, value_name = "MS", default_value_t = 500)]
+        retry_delay: u64,
+        /// Upload a local file for a file param, as NAME=PATH; may be repeated.
+        #[arg(long = "attach", value_name = "NAME=PATH", value_parser = parse_attachment)]
+        attachments: Vec<(String, PathBuf)>,
+        #[command(flatten)]
+        environment: EnvironmentArgs,
+        #[command(flatten)]
+        client: ClientArgs,
+    },
+    /// Replays the entries matching the filter with the gaps between them
+    /// as recorded, so requests that overlapped overlap again, and reports
+    /// how closely the schedule was kept.
+    Schedule {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        filter: Filter,
+        /// How much faster than recorded to replay, e.g. 2 or 0.5.
+        #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+        speed: f64,
+        /// Upload a local file for a file param, as NAME=PATH; may be repeated.
+        #[arg(long = "attach", value_name = "NAME=PATH", value_parser = parse_attachment)]
+        attachments: Vec<(String, PathBuf)>,
+        #[command(flatten)]
+        environment: EnvironmentArgs,
+        #[command(flatten)]
+        client: ClientArgs,
+    },
+    /// Serves the recorded responses of the entries matching the filter
+    /// over HTTP until interrupted, then lists the requests it had no
+    /// response for.
+    Mock {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        filter: Filter,
+        /// Port to listen on; 0 picks a free one.
+        #[arg(long, short, default_value_t = 8080)]
+        port: u16,
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+        /// Which response answers a request recorded several times.
+        #[arg(long, value_enum, default_value_t = StrategyArg::Sequential)]
+        strategy: StrategyArg,
+        /// Also match requests on their body.
+        #[arg(long)]
+        match_body: bool,
+        /// Allow cross-origin calls from any origin.
+        #[arg(long)]
+        cors: bool,
+    },
+    /// Runs a forward proxy recording the traffic sent through it until
+    /// interrupted, then writes it as a new HAR file. HTTPS is recorded for
+    /// clients that trust the proxy's certificate authority.
+    Record {
+        /// Output file; its extension picks the compression.
+        output: PathBuf,
+        /// Port to listen on; 0 picks a free one.
+        #[arg(long, short, default_value_t = 8888)]
+        port: u16,
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+        /// Directory of the certificate authority, created on first use;
+        /// defaults to ~/.har-analyser.
+        #[arg(long, value_name = "DIR")]
+        ca_dir: Option<PathBuf>,
+        /// Compression to use regardless of the output extension.
+        #[arg(long, value_enum)]
+        compression: Option<CompressionArg>,
+        #[command(flatten)]
+        client: ClientArgs,
+    },
+    /// Checks the file against HAR 1.2. Exits with status 1 on errors.
+    Validate {
+        /// HAR file, optionally compressed (.gz, .zst, .br) or zipped.
+        file: PathBuf,
+        /// The HAR to read from a zip archive holding several.
+        #[arg(long)]
+        member: Option<String>,
+        /// Also fail on warnings.
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Writes the archive with different compression.
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        /// Output file; its extension picks the compression.
+        output: PathBuf,
+        /// Compression to use regardless of the output extension.
+        #[arg(long, value_enum)]
+        compression: Option<CompressionArg>,
+    },
+    /// Writes a copy with credentials replaced by `[REDACTED]`: in headers,
+    /// cookies, URLs and JSON or form bodies, responses included. Other
+    /// response bodies are kept as recorded.
+    Redact {
+        #[command(flatten)]
+        input: Input,
+        /// Output file; its extension picks the compression.
+        output: PathBuf,
+        /// Another header to redact; may be repeated.
+        #[arg(long = "header", value_name = "NAME")]
+        headers: Vec<String>,
+        /// Another query, form or JSON parameter to redact; may be repeated.
+        #[arg(long = "param", value_name = "NAME")]
+        params: Vec<String>,
+        /// Keep cookie values.
+        #[arg(long)]
+        keep_cookies: bool,
+        /// Leave response bodies out, for HTML and other bodies that may
+        /// embed credentials.
+        #[arg(long)]
+        strip_bodies: bool,
+    },
+}
+
+#[derive(Args)]
+struct Input {
+    /// HAR file, optionally compressed (.gz, .zst, .br) or zipped.
+    file: PathBuf,
+    /// The HAR to read from a zip archive holding several.
+    #[arg(long)]
+    member: Option<String>,
+    /// How to treat files that do not follow the spec.
+    #[arg(long, value_enum, default_value_t = Mode::Standard)]
+    mode: Mode,
+}
+
+#[derive(Args)]
+struct EnvironmentArgs {
+    /// JSON file of environment profiles, as an array of
+    /// { name, variables, retarget }.
+    #[arg(long, value_name = "FILE", requires = "env")]
+    env_file: Option<PathBuf>,
+    /// The profile to use from --env-file.
+    #[arg(long, value_name = "NAME", requires = "env_file")]
+    env: Option<String>,
+    /// Send to this scheme, host and port instead, with any path as a
+    /// prefix, e.g. http://localhost:8080/staging.
+    #[arg(long, value_name = "URL")]
+    target: Option<String>,
+    /// Path prefix to remove from recorded URLs before adding the target's.
+    #[arg(long, value_name = "PATH")]
+    strip_prefix: Option<String>,
+    /// Only retarget requests to this host; may be repeated.
+    #[arg(long = "only-host", value_name = "HOST")]
+    hosts: Vec<String>,
+    /// Set `{{NAME}}`, as NAME=VALUE; may be repeated. A placeholder left
+    /// without a value fails the command before anything is sent.
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+    variables: Vec<(String, String)>,
+}
+
+impl EnvironmentArgs {
+    /// The chosen profile, if any, with the options given on the command
+    /// line taking precedence.
+    fn environment(self) -> Result<Environment, Failure> {
+        let mut environment = match (&self.env_file, &self.env) {
+            (Some(file), Some(name)) => {
+                let text = std::fs::read_to_string(file).map_err(|e| Error::io(e, file))?;
+                let profiles: Vec<Environment> =
+                    serde_json::from_str(&text).map_err(|e| Error::json(&e, "").with_path(file))?;
+                profiles
+                    .into_iter()
+                    .find(|p| p.name == *name)
+                    .ok_or_else(|| Failure {
+                        message: format!("no environment {name:?} in {}", file.display()),
+                        code: 2,
+                    })?
+            }
+            _ => Environment::default(),
+        };
+        if let Some(target) = &self.target {
+            let retarget = Retarget::to_base_url(target)?;
+            environment.retarget = Retarget {
+                strip_prefix: environment.retarget.strip_prefix,
+                hosts: environment.retarget.hosts,
+                ..retarget
+            };
+        }
+        if self.strip_prefix.is_some() {
+            environment.retarget.strip_prefix = self.strip_prefix;
+        }
+        if !self.hosts.is_empty() {
+            environment.retarget.hosts = self.hosts;
+        }
+        environment.variables.extend(self.variables);
+        Ok(environment)
+    }
+}
+
+#[derive(Args)]
+struct ClientArgs {
+    /// Limit on connecting, in milliseconds.
+    #[arg(long, value_name = "MS")]
+    connect_timeout: Option<u64>,
+    /// Limit on the whole replay, redirects included, in milliseconds.
+    #[arg(long, value_name = "MS")]
+    timeout: Option<u64>,
+    /// Redirects to follow; 0 prints the first 3xx response.
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    max_redirects: usize,
+    /// Accept invalid TLS certificates.
+    #[arg(long, short = 'k')]
+    insecure: bool,
+    /// PEM file of extra CA certificates to trust.
+    #[arg(long, value_name = "FILE")]
+    cacert: Option<PathBuf>,
+    /// PEM client certificate for mutual TLS.
+    #[arg(long, value_name = "FILE", requires = "key")]
+    cert: Option<PathBuf>,
+    /// PEM PKCS#8 private key of --cert.
+    #[arg(long, value_name = "FILE", requires = "cert")]
+    key: Option<PathBuf>,
+    /// Proxy URL: http://, socks5:// or socks5h://.
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+    /// Only use HTTP/1.1.
+    #[arg(long = "http1.1", conflicts_with = "http2")]
+    http1: bool,
+    /// Only use HTTP/2.
+    #[arg(long)]
+    http2: bool,
+}
+
+impl From<ClientArgs> for ClientConfig {
+    fn from(args: ClientArgs) -> Self {
+        ClientConfig {
+            connect_timeout_ms: args.connect_timeout,
+            timeout_ms: args.timeout,
+            max_redirects: args.max_redirects,
+            accept_invalid_certs: args.insecure,
+            ca_bundle: args.cacert,
+            client_cert: args.cert,
+            client_key: args.key,
+            proxy: args.proxy,
+            http_version: if args.http1 {
+                HttpVersion::Http1
+            } else if args.http2 {
+                HttpVersion::Http2
+            } else {
+                HttpVersion::Auto
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Standard,
+    Lenient,
+    Strict,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CompressionArg {
+    None,
+    Gzip,
+    Zstd,
+    Brotli,
+    Zip,
+}
+
+impl From<CompressionArg> for Compression {
+    fn from(arg: CompressionArg) -> Self {
+        match arg {
+            CompressionArg::None => Compression::None,
+            CompressionArg::Gzip => Compression::Gzip,
+            CompressionArg::Zstd => Compression::Zstd,
+            CompressionArg::Brotli => Compression::Brotli,
+            CompressionArg::Zip => Compression::Zip,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StrategyArg {
+    /// Each recorded response in turn, then the last one again.
+    Sequential,
+    First,
+    Last,
+}
+
+impl From<StrategyArg> for Strategy {
+    fn from(arg: StrategyArg) -> Self {
+        match arg {
+            StrategyArg::Sequential => Strategy::Sequential,
+            StrategyArg::First => Strategy::First,
+            StrategyArg::Last => Strategy::Last,
+        }
+    }
+}
+
+#[derive(Args, Default)]
+struct Filter {
+    /// Only requests with this method, e.g. POST.
+    #[arg(long)]
+    method: Option<String>,
+    /// Only responses with this status or status class, e.g. 404 or 4xx.
+    #[arg(long, value_parser = parse_status)]
+    status: Option<StatusFilter>,
+    /// Only URLs containing this text.
+    #[arg(long)]
+    url: Option<String>,
+    /// Only response MIME types containing this text, e.g. json.
+    #[arg(long)]
+    mime: Option<String>,
+    /// Only entries belonging to this page id, including those without a
+    /// `pageref` that `har pages` counts towards it.
+    #[arg(long)]
+    page: Option<String>,
+    /// Only this resource type as recorded by Chrome, e.g. xhr or fetch.
+    #[arg(long = "type", value_name = "TYPE")]
+    resource_type: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum StatusFilter {
+    Code(i64),
+    Class(i64),
+}
+
+fn parse_status(s: &str) -> Result<StatusFilter, String> {
+    let lower = s.to_ascii_lowercase();
+    if let Some(class) = lower.strip_suffix("xx") {
+        if let Ok(class @ 1..=5) = class.parse() {
+            return Ok(StatusFilter::Class(class));
+        }
+    }
+    s.parse()
+        .map(StatusFilter::Code)
+        .map_err(|_| format!("expected a status code or class like 4xx, got {s:?}"))
+}
+
+fn parse_attachment(s: &str) -> Result<(String, PathBuf), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), PathBuf::from(path)))
+        }
+        _ => Err(format!("expected NAME=PATH, got {s:?}")),
+    }
+}
+
+fn parse_variable(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got {s:?}")),
+    }
+}
+
+fn parse_extraction(s: &str) -> Result<Extraction, String> {
+    let invalid = || format!("expected NAME@STEP=KIND:EXPRESSION, got {s:?}");
+    let (target, source) = s.split_once('=').ok_or_else(invalid)?;
+    let (name, step) = target.split_once('@').ok_or_else(invalid)?;
+    let step = step.parse().map_err(|_| invalid())?;
+    let (kind, expression) = source.split_once(':').ok_or_else(invalid)?;
+    let source = match kind {
+        "json" => Source::JsonPath(expression.to_string()),
+        "regex" => Source::Regex(expression.to_string()),
+        "header" => Source::Header(expression.to_string()),
+        _ => {
+            return Err(format!(
+                "unknown source {kind:?}; use json, regex or header"
+            ))
+        }
+    };
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Extraction {
+        name: name.to_string(),
+        step,
+        source,
+        replace_recorded: false,
+    })
+}
+
+impl Filter {
+    /// The entries of `har` that pass, with their positions.
+    fn select<'a>(&self, har: &'a HarFile) -> Vec<(usize, &'a HarEntry)> {
+        let pages = har.log.pages.as_deref().unwrap_or_default();
+        let assigned = match self.page {
+            Some(_) => pages::assign_entries(har),
+            None => Vec::new(),
+        };
+        har.log
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|&(index, entry)| {
+                let page = assigned
+                    .get(index)
+                    .copied()
+                    .flatten()
+                    .map(|position| pages[position].id.as_str());
+                self.matches(entry, page)
+            })
+            .collect()
+    }
+
+    /// Whether `entry`, which belongs to the page with id `page`, passes.
+    fn matches(&self, entry: &HarEntry, page: Option<&str>) -> bool {
+        let status = entry.response.status;
+        self.method
+            .as_ref()
+            .is_none_or(|m| entry.request.method.eq_ignore_ascii_case(m))
+            && self.status.is_none_or(|filter| match filter {
+                StatusFilter::Code(code) => status == code,
+                StatusFilter::Class(class) => status / 100 == class,
+            })
+            && self
+                .url
+                .as_ref()
+                .is_none_or(|url| entry.request.url.contains(url.as_str()))
+            && self.mime.as_ref().is_none_or(|mime| {
+                entry
+                    .response
+                    .content
+                    .mime_type
+                    .to_ascii_lowercase()
+                    .contains(&mime.to_ascii_lowercase())
+            })
+            && self
+                .page
+                .as_ref()
+                .is_none_or(|wanted| page == Some(wanted.as_str()))
+            && self.resource_type.as_ref().is_none_or(|kind| {
+                entry
+                    .resource_type
+                    .as_ref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(kind))
+            })
+    }
+}
+
+/// A failed command: the message to print and the exit status.
+struct Failure {
+    message: String,
+    code: u8,
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        Failure {
+            message: err.to_string(),
+            code: 2,
+        }
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(err: std::io::Error) -> Self {
+        Failure {
+            message: err.to_string(),
+            code: 2,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command, cli.format) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            if !failure.message.is_empty() {
+                eprintln!("har: {}", failure.message);
+            }
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+fn open(input: &Input) -> Result<HarSession, Error> {
+    let options = LoadOptions {
+        member: input.member.clone(),
+        mode: match input.mode {
+            Mode::Standard => ParseMode::Standard,
+            Mode::Lenient => ParseMode::Lenient,
+            Mode::Strict => ParseMode::Strict,
+        },
+        ..LoadOptions::default()
+    };
+    HarSession::open(&input.file, &options, &AtomicBool::new(false), |_, _, _| {}).map_err(|e| {
+        match e.context.path {
+            Some(_) => e,
+            None => e.with_path(&input.file),
+        }
+    })
+}
+
+/// Entry `index` with its response body read back from the spool.
+fn entry(session: &HarSession, index: usize) -> Result<HarEntry, Failure> {
+    let count = session.har.log.entries.len();
+    let mut entry = session
+        .har
+        .log
+        .entries
+        .get(index)
+        .cloned()
+        .ok_or_else(|| Failure {
+            message: format!("no entry {index}; the archive has {count}"),
+            code: 2,
+        })?;
+    entry.response.content.text = session.response_body(index)?;
+    Ok(entry)
+}
+
+fn run(command: Command, format: Format) -> Result<(), Failure> {
+    match command {
+        Command::Summary { input } => {
+            let session = open(&input)?;
+            let summary = summary::summarize(&session.har);
+            if format == Format::Json {
+                return Ok(output::json(&summary)?);
+            }
+            let counts = |map: &std::collections::BTreeMap<String, usize>| {
+                map.iter()
+                    .map(|(k, v)| format!("{k} {v}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let mut fields = vec![
+                ("Creator", summary.creator.clone()),
+                ("Version", summary.version.clone()),
+                ("Pages", summary.pages.to_string()),
+                ("Entries", summary.entries.to_string()),
+                ("Started", summary.started.clone().unwrap_or_default()),
+                ("Span", output::millis(summary.span_ms)),
+                ("Content", output::bytes(summary.content_bytes)),
+                ("Transferred", output::bytes(summary.transfer_bytes)),
+                ("Statuses", counts(&summary.statuses)),
+                ("MIME types", counts(&summary.mime_types)),
+                ("Methods", counts(&summary.methods)),
+                ("Time", distribution(&summary.time)),
+            ];
+            let timings = &summary.timings;
+            for (name, phase) in [
+                ("Blocked", &timings.blocked),
+                ("DNS", &timings.dns),
+                ("Connect", &timings.connect),
+                ("TLS", &timings.ssl),
+                ("Send", &timings.send),
+                ("Wait", &timings.wait),
+                ("Receive", &timings.receive),
+            ] {
+                if phase.count > 0 {
+                    fields.push((name, distribution(phase)));
+                }
+            }
+            output::fields(&fields)?;
+            print_diagnostics(&session.diagnostics);
+            Ok(())
+        }
+        Command::List { input, filter } => {
+            let session = open(&input)?;
+            let rows = filter.select(&session.har);
+            if format == Format::Json {
+                let rows: Vec<ListRow> = rows.into_iter().map(ListRow::from).collect();
+                return Ok(output::json(&rows)?);
+            }
+            let rows: Vec<Vec<String>> = rows
+                .into_iter()
+                .map(|(index, entry)| {
+                    vec![
+                        index.to_string(),
+                        entry.request.method.clone(),
+                        entry.response.status.to_string(),
+                        entry.response.content.mime_type.clone(),
+                        output::bytes(entry.response.content.size),
+                        output::millis(entry.time),
+                        entry.request.url.clone(),
+                    ]
+                })
+                .collect();
+            Ok(output::table(
+                &["#", "METHOD", "STATUS", "TYPE", "SIZE", "TIME", "URL"],
+                &rows,
+            )?)
+        }
+        Command::Endpoints {
+            input,
+            filter,
+            by_host,
+            top,
+        } => {
+            let session = open(&input)?;
+            let entries = filter.select(&session.har);
+            let by = if by_host {
+                GroupBy::Host
+            } else {
+                GroupBy::Endpoint
+            };
+            let mut groups = group::group(entries, by);
+            groups.truncate(top.unwrap_or(usize::MAX));
+            if format == Format::Json {
+                return Ok(output::json(&groups)?);
+            }
+            let rows: Vec<Vec<String>> = groups
+                .iter()
+                .map(|g| {
+                    vec![
+                        g.count.to_string(),
+                        format!("{:.1}%", g.error_rate * 100.0),
+                        output::millis(g.time.p50),
+                        output::millis(g.time.p90),
+                        output::millis(g.time.p99),
+                        output::bytes(g.response_size.mean.round() as i64),
+                        output::bytes(g.response_bytes),
+                        g.key.clone(),
+                    ]
+                })
+                .collect();
+            Ok(output::table(
+                &[
+                    "COUNT", "ERRORS", "P50", "P90", "P99", "AVG SIZE", "TOTAL", "GROUP",
+                ],
+                &rows,
+            )?)
+        }
+        Command::Pages { input } => {
+            let session = open(&input)?;
+            let metrics = pages::page_metrics(&session.har);
+            if format == Format::Json {
+                return Ok(output::json(&metrics)?);
+            }
+            let optional = |ms: Option<f64>| ms.map_or_else(|| "-".to_string(), output::millis);
+            let rows: Vec<Vec<String>> = metrics
+                .iter()
+                .map(|page| {
+                    vec![
+                        page.id.clone(),
+                        page.requests.to_string(),
+                        output::bytes(page.transfer_bytes),
+                        optional(page.document_ttfb),
+                        optional(page.on_content_load),
+                        optional(page.on_load),
+                        format!(
+                            "{} in {}",
+                            page.longest_chain.entries.len(),
+                            output::millis(page.longest_chain.time)
+                        ),
+                        page.title.clone(),
+                    ]
+                })
+                .collect();
+            Ok(output::table(
+                &[
+                    "PAGE", "REQUESTS", "SIZE", "TTFB", "DCL", "LOAD", "CHAIN", "TITLE",
+                ],
+                &rows,
+            )?)
+        }
+        Command::Show { input, index } => {
+            let session = open(&input)?;
+            let entry = entry(&session, index)?;
+            if format == Format::Json {
+                return Ok(output::json(&entry)?);
+            }
+            Ok(print_entry(index, &entry)?)
+        }
+        Command::Replay {
+            input,
+            index,
+            attachments,
+            cookie_jar,
+            diff,
+            ignore_paths,
+            ignore_headers,
+            environment,
+            client,
+        } => {
+            let session = open(&input)?;
+            let entry = entry(&session, index)?;
+            let mut request = entry.request.clone();
+            environment.environment()?.apply_strict(&mut request)?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let attachments = attachments.into_iter().collect();
+            let mut jar = cookie_jar.then(|| CookieJar::from_entries(&session.har.log.entries));
+            let replayed = runtime.block_on(replay::replay_request(
+                &Client::with_config(client.into())?,
+                &request,
+                &attachments,
+                jar.as_mut(),
+            ))?;
+            if diff {
+                let mut options = DiffOptions {
+                    ignore_paths,
+                    ..DiffOptions::default()
+                };
+                options.ignore_headers.extend(ignore_headers);
+                let diff = diff::diff_responses(&entry, &replayed, &options)?;
+                if format == Format::Json {
+                    output::json(&diff)?;
+                } else {
+                    print_diff(&diff);
+                }
+                return if diff.same {
+                    Ok(())
+                } else {
+                    Err(Failure {
+                        message: String::new(),
+                        code: 1,
+                    })
+                };
+            }
+            if format == Format::Json {
+                return Ok(output::json(&replayed)?);
+            }
+            Ok(print_entry(index, &replayed)?)
+        }
+        Command::Flow {
+            input,
+            first,
+            last,
+            extractions,
+            replace_recorded,
+            keep_going,
+            attachments,
+            cookie_jar,
+            environment,
+            client,
+        } => {
+            let session = open(&input)?;
+            if first > last {
+                return Err(Failure {
+                    message: format!("the range {first}..{last} is empty"),
+                    code: 2,
+                });
+            }
+            let steps = (first..=last)
+                .map(|index| entry(&session, index))
+                .collect::<Result<Vec<_>, _>>()?;
+            let environment = environment.environment()?;
+            let options = FlowOptions {
+                extractions: extractions
+                    .into_iter()
+                    .map(|rule| Extraction {
+                        replace_recorded,
+                        ..rule
+                    })
+                    .collect(),
+                variables: environment.variables,
+                retarget: environment.retarget,
+                continue_on_error: keep_going,
+            };
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let attachments = attachments.into_iter().collect();
+            let mut jar = cookie_jar.then(|| CookieJar::from_entries(&session.har.log.entries));
+            let table = format == Format::Table;
+            let logs = runtime.block_on(flow::replay_flow(
+                &Client::with_config(client.into())?,
+                &steps,
+                &options,
+                &attachments,
+                jar.as_mut(),
+                |log| {
+                    if table {
+                        print_step(first + log.step, log);
+                    }
+                },
+            ))?;
+            if format == Format::Json {
+                output::json(&logs)?;
+            }
+            if logs.iter().all(|log| log.error.is_none()) {
+                Ok(())
+            } else {
+                Err(Failure {
+                    message: String::new(),
+                    code: 1,
+                })
+            }
+        }
+        Command::Batch {
+            input,
+            filter,
+            concurrency,
+            rps,
+            retries,
+            retry_delay,
+            attachments,
+            environment,
+            client,
+        } => {
+            let session = open(&input)?;
+            let environment = environment.environment()?;
+            let mut entries = Vec::new();
+            for (index, _) in filter.select(&session.har) {
+                let mut entry = entry(&session, index)?;
+                environment
+                    .apply_strict(&mut entry.request)
+                    .map_err(|e| Failure {
+                        message: format!("#{index}: {e}"),
+                        code: 2,
+                    })?;
+                entries.push((index, entry));
+            }
+            let options = BatchOptions {
+                concurrency,
+                requests_per_second: rps,
+                retries,
+                retry_delay_ms: retry_delay,
+                ..BatchOptions::default()
+            };
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let attachments = attachments.into_iter().collect();
+            let table = format == Format::Table;
+            let summary = runtime.block_on(batch::replay_batch(
+                &Client::with_config(client.into())?,
+                entries,
+                &options,
+                &attachments,
+                &AtomicBool::new(false),
+                |progress| {
+                    if table {
+                        print_batch_item(progress.item);
+                    }
+                },
+            ));
+            if format == Format::Json {
+                output::json(&summary)?;
+            } else {
+                println!(
+                    "{} passed, {} failed, {} errors in {}",
+                    summary.passed,
+                    summary.failed,
+                    summary.errors,
+                    output::millis(summary.elapsed)
+                );
+            }
+            if summary.passed == summary.items.len() {
+                Ok(())
+            } else {
+                Err(Failure {
+                    message: String::new(),
+                    code: 1,
+                })
+            }
+        }
+        Command::Schedule {
+            input,
+            filter,
+            speed,
+            attachments,
+            environment,
+            client,
+        } => {
+            let session = open(&input)?;
+            let environment = environment.environment()?;
+            let mut entries = Vec::new();
+            for (index, _) in filter.select(&session.har) {
+                let mut entry = entry(&session, index)?;
+                environment
+                    .apply_strict(&mut entry.request)
+                    .map_err(|e| Failure {
+                        message: format!("#{index}: {e}"),
+                        code: 2,
+                    })?;
+                entries.push((index, entry));
+            }
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let attachments = attachments.into_iter().collect();
+            let table = format == Format::Table;
+            let report = runtime.block_on(schedule::replay_schedule(
+                &Client::with_config(client.into())?,
+                entries,
+                &ScheduleOptions { speed },
+                &attachments,
+                &AtomicBool::new(false),
+                |progress| {
+                    if table {
+                        print_scheduled_item(progress.item);
+                    }
+                },
+            ))?;
+            if format == Format::Json {
+                return Ok(output::json(&report)?);
+            }
+            println!();
+            output::fields(&[
+                ("Requests", report.items.len().to_string()),
+                ("Errors", report.errors.to_string()),
+                ("Start lag", distribution(&report.lag)),
+                ("Replayed", distribution(&report.response_time)),
+                ("Recorded", distribution(&report.recorded_time)),
+            ])?;
+            Ok(())
+        }
+        Command::Mock {
+            input,
+            filter,
+            port,
+            bind,
+            strategy,
+            match_body,
+            cors,
+        } => {
+            let session = open(&input)?;
+            let mut entries = Vec::new();
+            for (index, _) in filter.select(&session.har) {
+                entries.push((index, entry(&session, index)?));
+            }
+            let options = MockOptions {
+                strategy: strategy.into(),
+                match_body,
+                cors,
+            };
+            let table = format == Format::Table;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let unmatched = runtime.block_on(async {
+                let server =
+                    MockServer::start((bind, port).into(), entries, options, move |request| {
+                        if table {
+                            let answer = match request.entry {
+                                Some(index) => format!("#{index}"),
+                                None => "unmatched".to_string(),
+                            };
+                            println!(
+                                "{} {} {} {answer}",
+                                request.method, request.url, request.status
+                            );
+                        }
+                    })
+                    .await?;
+                eprintln!(
+                    "Serving {} on http://{}; press Ctrl-C to stop",
+                    input.file.display(),
+                    server.addr()
+                );
+                tokio::signal::ctrl_c().await?;
+                let unmatched = server.unmatched();
+                server.stop().await;
+                Ok::<_, Failure>(unmatched)
+            })?;
+            if format == Format::Json {
+                return Ok(output::json(&unmatched)?);
+            }
+            if !unmatched.is_empty() {
+                println!();
+                println!("No recorded response for:");
+                for request in &unmatched {
+                    println!("  {} {}", request.method, request.url);
+                }
+            }
+            Ok(())
+        }
+        Command::Record {
+            output,
+            port,
+            bind,
+            ca_dir,
+            compression,
+            client,
+        } => {
+            let ca_dir = match ca_dir {
+                Some(dir) => dir,
+                None => std::env::var_os("HOME")
+                    .or_else(|| std::env::var_os("USERPROFILE"))
+                    .map(|home| PathBuf::from(home).join(".har-analyser"))
+                    .ok_or_else(|| Failure {
+                        message: "no home directory; pass --ca-dir".to_string(),
+                        code: 2,
+                    })?,
+            };
+            let ca = CertificateAuthority::load_or_create(&ca_dir)?;
+            let client = Client::with_config(client.into())?;
+            let table = format == Format::Table;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let entries = runtime.block_on(async {
+                let certificate = ca.certificate_path().to_path_buf();
+                let proxy = RecordingProxy::start(
+                    (bind, port).into(),
+                    std::sync::Arc::new(ca),
+                    client,
+                    move |entry| {
+                        if table {
+                            println!(
+                                "{} {} {} {}",
+                                entry.request.method,
+                                entry.request.url,
+                                entry.response.status,
+                                output::millis(entry.time)
+                            );
+                        }
+                    },
+                )
+                .await?;
+                eprintln!("Recording on http://{}; press Ctrl-C to stop", proxy.addr());
+                eprintln!(
+                    "Clients must trust {} for HTTPS to be recorded",
+                    certificate.display()
+                );
+                tokio::signal::ctrl_c().await?;
+                let entries = proxy.entries();
+                proxy.stop().await;
+                Ok::<_, Failure>(entries)
+            })?;
+            let mut har = HarFile::new("har", env!("CARGO_PKG_VERSION"));
+            har.log.entries = entries;
+            let session = HarSession::new(har)?;
+            save(&session, &output, compression)?;
+            let entries = &session.har.log.entries;
+            if format == Format::Json {
+                let rows: Vec<ListRow> = entries.iter().enumerate().map(ListRow::from).collect();
+                output::json(&rows)?;
+            }
+            eprintln!(
+                "Recorded {} requests to {}",
+                entries.len(),
+                output.display()
+            );
+            Ok(())
+        }
+        Command::Validate {
+            file,
+            member,
+            deny_warnings,
+        } => {
+            let findings = validate::validate_file(&file, member.as_deref())?;
+            let limit = if deny_warnings {
+                Severity::Warning
+            } else {
+                Severity::Error
+            };
+            let valid = findings.iter().all(|f| f.severity < limit);
+            if format == Format::Json {
+                output::json(&Validation {
+                    valid,
+                    findings: &findings,
+                })?;
+            } else {
+                print_findings(&findings)?;
+            }
+            if valid {
+                Ok(())
+            } else {
+                Err(Failure {
+                    message: String::new(),
+                    code: 1,
+                })
+            }
+        }
+        Command::Convert {
+            input,
+            output,
+            compression,
+        } => {
+            let session = open(&input)?;
+            save(&session, &output, compression)?;
+            print_diagnostics(&session.diagnostics);
+            Ok(())
+        }
+        Command::Redact {
+            input,
+            output,
+            headers,
+            params,
+            keep_cookies,
+            strip_bodies,
+        } => {
+            let mut session = open(&input)?;
+            let mut redaction = Redaction::default();
+            redaction.headers.extend(headers);
+            redaction.params.extend(params);
+            redaction.cookies = !keep_cookies;
+            let mut count = 0;
+            for index in 0..session.har.log.entries.len() {
+                // Bodies are read back from the spool so JSON responses, such
+                // as token grants, are redacted too.
+                let body = if strip_bodies {
+                    None
+                } else {
+                    session.response_body(index)?
+                };
+                let entry = &mut session.har.log.entries[index];
+                entry.response.content.text = body;
+                let redacted = redaction.apply(entry);
+                let body = entry.response.content.text.take();
+                if strip_bodies {
+                    session.clear_response_body(index);
+                } else if redacted > 0 {
+                    session.set_response_body(index, body)?;
+                }
+                count += redacted;
+            }
+            save(&session, &output, None)?;
+            eprintln!("Redacted {count} values");
+            Ok(())
+        }
+    }
+}
+
+/// Percentiles and maximum of durations, e.g. `p50 1.2 ms, ..., max 3.40 s`.
+fn distribution(d: &Distribution) -> String {
+    format!(
+        "p50 {}, p90 {}, p99 {}, max {}",
+        output::millis(d.p50),
+        output::millis(d.p90),
+        output::millis(d.p99),
+        output::millis(d.max)
+    )
+}
+
+fn save(
+    session: &HarSession,
+    path: &Path,
+    compression: Option<CompressionArg>,
+) -> Result<(), Error> {
+    let compression = compression.map_or_else(|| Compression::from_extension(path), Into::into);
+    session.save(path, compression)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListRow<'a> {
+    index: usize,
+    method: &'a str,
+    status: i64,
+    mime_type: &'a str,
+    size: i64,
+    time: f64,
+    url: &'a str,
+}
+
+impl<'a> From<(usize, &'a HarEntry)> for ListRow<'a> {
+    fn from((index, entry): (usize, &'a HarEntry)) -> Self {
+        ListRow {
+            index,
+            method: &entry.request.method,
+            status: entry.response.status,
+            mime_type: &entry.response.content.mime_type,
+            size: entry.response.content.size,
+            time: entry.time,
+            url: &entry.request.url,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Validation<'a> {
+    valid: bool,
+    findings: &'a [Diagnostic],
+}
+
+fn print_findings(findings: &[Diagnostic]) -> std::io::Result<()> {
+    let rows: Vec<Vec<String>> = findings
+        .iter()
+        .map(|f| {
+            vec![
+                format!("{:?}", f.severity).to_ascii_lowercase(),
+                f.json_path.clone(),
+                f.message.clone(),
+            ]
+        })
+        .collect();
+    if !rows.is_empty() {
+        output::table(&["SEVERITY", "PATH", "MESSAGE"], &rows)?;
+    }
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    println!(
+        "{} errors, {} warnings",
+        count(Severity::Error),
+        count(Severity::Warning)
+    );
+    Ok(())
+}
+
+/// Reports load diagnostics on stderr so stdout stays clean for piping.
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for d in diagnostics {
+        eprintln!(
+            "{}: {}: {}",
+            format!("{:?}", d.severity).to_ascii_lowercase(),
+            d.json_path,
+            d.message
+        );
+    }
+}
+
+/// The diff as `-`/`+` lines for the recorded and replayed side.
+fn print_diff(diff: &ResponseDiff) {
+    if let Some(status) = &diff.status {
+        println!("status {} -> {}", status.recorded, status.replayed);
+    }
+    for header in &diff.headers {
+        if let Some(value) = &header.recorded {
+            println!("- {}: {value}", header.name);
+        }
+        if let Some(value) = &header.replayed {
+            println!("+ {}: {value}", header.name);
+        }
+    }
+    match &diff.body {
+        BodyDiff::Same => {}
+        BodyDiff::Unavailable => println!("body not captured; not compared"),
+        BodyDiff::Json { changes } => {
+            for change in changes {
+                if let Some(value) = &change.recorded {
+                    println!("- {} = {value}", change.path);
+                }
+                if let Some(value) = &change.replayed {
+                    println!("+ {} = {value}", change.path);
+                }
+            }
+        }
+        BodyDiff::Text { lines } => {
+            for line in lines.iter().filter(|l| l.op != LineOp::Equal) {
+                let sign = if line.op == LineOp::Removed { '-' } else { '+' };
+                println!("{sign} {}", line.text);
+            }
+        }
+        BodyDiff::Binary {
+            recorded_size,
+            replayed_size,
+        } => println!("binary body differs ({recorded_size} -> {replayed_size} bytes)"),
+    }
+    if diff.same {
+        println!("no differences");
+    }
+    let timing = &diff.timing;
+    println!(
+        "time {} -> {} ({:+.1} ms)",
+        output::millis(timing.recorded),
+        output::millis(timing.replayed),
+        timing.delta
+    );
+}
+
+/// One line per step as it completes, then the values it extracted and the
+/// placeholders it could not fill.
+fn print_step(index: usize, log: &StepLog) {
+    let outcome = match (&log.entry, &log.error) {
+        (Some(entry), _) => format!("{} {}", entry.response.status, output::millis(entry.time)),
+        (None, Some(err)) => format!("failed: {err}"),
+        (None, None) => String::new(),
+    };
+    println!("#{index} {} {} {outcome}", log.method, log.url);
+    for extracted in &log.extracted {
+        match &extracted.value {
+            Some(value) => println!("    {} = {value}", extracted.name),
+            None => println!("    {} not found", extracted.name),
+        }
+    }
+    if !log.unresolved.is_empty() {
+        println!("    unresolved: {}", log.unresolved.join(", "));
+    }
+}
+
+/// One line per entry as it completes, in completion order.
+fn print_batch_item(item: &BatchItem) {
+    let outcome = match (item.outcome, &item.error) {
+        (Outcome::Passed, _) => "ok".to_string(),
+        (Outcome::Failed, _) => format!(
+            "expected {}, got {}",
+            item.recorded_status,
+            item.status.unwrap_or_default()
+        ),
+        (_, Some(err)) => format!("failed: {err}"),
+        (_, None) => "cancelled".to_string(),
+    };
+    let attempts = if item.attempts > 1 {
+        format!(" after {} attempts", item.attempts)
+    } else {
+        String::new()
+    };
+    println!(
+        "#{} {} {} {} {outcome}{attempts}",
+        item.index,
+        item.method,
+        item.url,
+        output::millis(item.time)
+    );
+}
+
+/// One line per request as it completes: planned and actual start, then
+/// the outcome.
+fn print_scheduled_item(item: &ScheduledItem) {
+    let outcome = match (&item.status, &item.error) {
+        (Some(status), _) => format!("{status} {}", output::millis(item.time.unwrap_or_default())),
+        (None, Some(err)) => format!("failed: {err}"),
+        (None, None) => String::new(),
+    };
+    println!(
+        "+{} (planned +{}) #{} {} {} {outcome}",
+        output::millis(item.achieved_offset.unwrap_or_default()),
+        output::millis(item.planned_offset),
+        item.index,
+        item.method,
+        item.url
+    );
+}
+
+fn print_entry(index: usize, entry: &HarEntry) -> std::io::Result<()> {
+    let request = &entry.request;
+    let response = &entry.response;
+    println!(
+        "#{index} {} {} {}",
+        request.method, request.url, request.http_version
+    );
+    println!(
+        "started {}, {}{}",
+        entry.started_date_time,
+        output::millis(entry.time),
+        entry
+            .pageref
+            .as_ref()
+            .map(|page| format!(", page {page}"))
+            .unwrap_or_default()
+    );
+    println!();
+    for header in &request.headers {
+        println!("{}: {}", header.name, header.value);
+    }
+    if let Some(post) = &request.post_data {
+        match &post.text {
+            Some(_) if post.is_base64() => {
+                println!();
+                println!(
+                    "<{} of base64 {}>",
+                    output::bytes(request.body_size),
+                    post.mime_type
+                );
+            }
+            Some(text) => {
+                println!();
+                println!("{text}");
+            }
+            None => {}
+        }
+    }
+    println!();
+    println!(
+        "{} {} {}",
+        response.http_version, response.status, response.status_text
+    );
+    for header in &response.headers {
+        println!("{}: {}", header.name, header.value);
+    }
+    let content = &response.content;
+    match (&content.text, content.encoding.as_deref()) {
+        (Some(_), Some("base64")) => {
+            println!();
+            println!(
+                "<{} of base64 {}>",
+                output::bytes(content.size),
+                content.mime_type
+            );
+        }
+        (Some(text), _) => {
+            println!();
+            println!("{text}");
+        }
+        (None, _) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_filter_accepts_codes_and_classes() {
+        assert!(matches!(parse_status("404"), Ok(StatusFilter::Code(404))));
+        assert!(matches!(parse_status("4XX"), Ok(StatusFilter::Class(4))));
+        assert!(parse_status("9xx").is_err());
+        assert!(parse_status("ok").is_err());
+    }
+
+    #[test]
+    fn page_filter_includes_entries_assigned_by_time() {
+        let source = include_str!("../../har-core/tests/fixtures/chrome.har");
+        let mut har: HarFile = serde_json::from_str(source).unwrap();
+        har.log.entries[1].pageref = None;
+        let filter = Filter {
+            page: Some("page_1".into()),
+            ..Filter::default()
+        };
+        let selected: Vec<usize> = filter.select(&har).iter().map(|(i, _)| *i).collect();
+        assert_eq!(selected, [0, 1, 2, 3]);
+
+        let filter = Filter {
+            page: Some("page_2".into()),
+            ..Filter::default()
+        };
+        assert!(filter.select(&har).is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+//! Replaying many entries at once.
+//!
+//! Entries are sent independently, up to [`BatchOptions::concurrency`] at a
+//! time and no faster than [`BatchOptions::requests_per_second`], retries
+//! included. Each one passes if the replayed status matches the recorded
+//! one. Requests are sent as given, with their recorded `Cookie` headers:
+//! concurrent replays cannot share a cookie jar in any meaningful order.
+
+use crate::client::{now_millis, Client};
+use crate::error::{Error, ErrorKind};
+use crate::form::Attachments;
+use crate::har::HarEntry;
+use crate::replay;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// How often a running batch checks whether it has been cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BatchOptions {
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Upper bound on requests started per second; unlimited if unset.
+    pub requests_per_second: Option<f64>,
+    /// Further attempts after a request fails to send or answers with one
+    /// of `retry_statuses`.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after.
+    pub retry_delay_ms: u64,
+    pub retry_statuses: Vec<i64>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            concurrency: 4,
+            requests_per_second: None,
+            retries: 0,
+            retry_delay_ms: 500,
+            retry_statuses: vec![429, 502, 503, 504],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    /// The replayed status matches the recorded one.
+    Passed,
+    /// The server answered with a different status.
+    Failed,
+    /// The request could not be sent, even after retrying.
+    Error,
+    /// The batch was cancelled before the request completed.
+    Cancelled,
+}
+
+/// The result for one entry of a batch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItem {
+    /// Position of the entry in the archive.
+    pub index: usize,
+    pub method: String,
+    pub url: String,
+    pub outcome: Outcome,
+    pub recorded_status: i64,
+    /// Status of the last attempt, if the server answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    pub attempts: u32,
+    /// Duration of the last attempt in milliseconds.
+    pub time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+/// Sent as each entry completes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress<'a> {
+    pub completed: usize,
+    pub total: usize,
+    pub item: &'a BatchItem,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    /// One item per entry, in the order given.
+    pub items: Vec<BatchItem>,
+    pub passed: usize,
+    pub failed: usize,
+    pub errors: usize,
+    pub cancelled: usize,
+    /// Wall-clock time of the whole batch in milliseconds.
+    pub elapsed: f64,
+}
+
+/// Spaces out request starts shared by every task of a batch.
+struct RateLimit {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimit {
+    async fn wait(&self) {
+        let at = {
+            let mut next = self.next.lock().unwrap();
+            let at = (*next).max(Instant::now());
+            *next = at + self.interval;
+            at
+        };
+        tokio::time::sleep_until(at).await;
+    }
+}
+
+/// Replays `entries`, each given with its position in the archive, and
+/// calls `on_progress` as each one completes. Setting `cancel` stops the
+/// batch: requests in flight are dropped and those not yet sent are skipped,
+/// all reported as [`Outcome::Cancelled`].
+pub async fn replay_batch(
+    client: &Client,
+    entries: Vec<(usize, HarEntry)>,
+    options: &BatchOptions,
+    attachments: &Attachments,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(&BatchProgress),
+) -> BatchSummary {
+    let started = now_millis();
+    let total = entries.len();
+    let limit = options
+        .requests_per_second
+        .filter(|rps| *rps > 0.0)
+        .map(|rps| {
+            Arc::new(RateLimit {
+                interval: Duration::from_secs_f64(1.0 / rps),
+                next: Mutex::new(Instant::now()),
+            })
+        });
+    let attachments = Arc::new(attachments.clone());
+    let options = Arc::new(options.clone());
+
+    let mut items: Vec<BatchItem> = entries
+        .iter()
+        .map(|(index, entry)| BatchItem {
+            index: *index,
+            method: entry.request.method.clone(),
+            url: entry.request.url.clone(),
+            outcome: Outcome::Cancelled,
+            recorded_status: entry.response.status,
+            status: None,
+            attempts: 0,
+            time: 0.0,
+            error: None,
+        })
+        .collect();
+    let mut queue = entries.into_iter().enumerate();
+    let mut running = JoinSet::new();
+    let mut completed = 0;
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            running.abort_all();
+            break;
+        }
+        while running.len() < options.concurrency.max(1) {
+            let Some((position, (_, entry))) = queue.next() else {
+                break;
+            };
+            let client = client.clone();
+            let options = Arc::clone(&options);
+            let attachments = Arc::clone(&attachments);
+            let limit = limit.clone();
+            running.spawn(async move {
+                let item = replay_entry(&client, &entry, &options, &attachments, limit).await;
+                (position, item)
+            });
+        }
+        match tokio::time::timeout(CANCEL_POLL, running.join_next()).await {
+            Ok(None) => break,
+            Ok(Some(Ok((position, (status, attempts, time, error))))) => {
+                let item = &mut items[position];
+                item.outcome = match status {
+                    Some(status) if status == item.recorded_status => Outcome::Passed,
+                    Some(_) => Outcome::Failed,
+                    None => Outcome::Error,
+                };
+                item.status = status;
+                item.attempts = attempts;
+                item.time = time;
+                item.error = error;
+                completed += 1;
+                on_progress(&BatchProgress {
+                    completed,
+                    total,
+                    item,
+                });
+            }
+            // Either the poll timed out, or a task panicked and its entry
+            // stays cancelled.
+            Ok(Some(Err(_))) | Err(_) => {}
+        }
+    }
+    while running.join_next().await.is_some() {}
+
+    let count = |outcome| items.iter().filter(|i| i.outcome == outcome).count();
+    BatchSummary {
+        passed: count(Outcome::Passed),
+        failed: count(Outcome::Failed),
+        errors: count(Outcome::Error),
+        cancelled: count(Outcome::Cancelled),
+        elapsed: now_millis() - started,
+        items,
+    }
+}
+
+/// Sends one entry, retrying as `options` allow. Returns the last status,
+/// the number of attempts, the time of the last attempt and its error.
+async fn replay_entry(
+    client: &Client,
+    entry: &HarEntry,
+    options: &BatchOptions,
+    attachments: &Attachments,
+    limit: Option<Arc<RateLimit>>,
+) -> (Option<i64>, u32, f64, Option<Error>) {
+    let mut attempts = 0;
+    let mut delay = Duration::from_millis(options.retry_delay_ms);
+    loop {
+        if attempts > 0 {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        if let Some(limit) = &limit {
+            limit.wait().await;
+        }
+        attempts += 1;
+        let started = now_millis();
+        let result = replay::replay_request(client, &entry.request, attachments, None).await;
+        let time = now_millis() - started;
+        let retry = attempts <= options.retries
+            && match &result {
+                Ok(replayed) => options.retry_statuses.contains(&replayed.response.status),
+                Err(err) => is_transient(err),
+            };
+        if !retry {
+            return match result {
+                Ok(replayed) => (Some(replayed.response.status), attempts, time, None),
+                Err(err) => (None, attempts, time, Some(err)),
+            };
+        }
+    }
+}
+
+/// Failures worth trying again; a malformed request fails the same way
+/// every time.
+fn is_transient(err: &Error) -> bool {
+    matches!(
+        err.kind,
+        ErrorKind::Dns
+            | ErrorKind::Connection
+            | ErrorKind::Tls
+            | ErrorKind::Timeout
+            | ErrorKind::Http
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, serve, Reply};
+    use std::sync::atomic::AtomicUsize;
+
+    fn entry(url: String, status: i64) -> HarEntry {
+        test_support::entry("GET", &url).status(status).build()
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_and_compares_statuses() {
+        // `/flaky` fails the first time only.
+        let flaky = AtomicUsize::new(0);
+        let addr = serve(move |request| {
+            let failed = request.target() == "/flaky" && flaky.fetch_add(1, Ordering::SeqCst) == 0;
+            Reply::new(if failed { 503 } else { 200 }, &[], "")
+        })
+        .await;
+        let entries = vec![
+            (3, entry(format!("http://{addr}/flaky"), 200)),
+            (5, entry(format!("http://{addr}/ok"), 404)),
+            (8, entry(format!("http://{addr}/ok"), 200)),
+        ];
+        let options = BatchOptions {
+            concurrency: 2,
+            requests_per_second: Some(20.0),
+            retries: 1,
+            retry_delay_ms: 10,
+            ..BatchOptions::default()
+        };
+        let mut progress = Vec::new();
+        let summary = replay_batch(
+            &Client::new().unwrap(),
+            entries,
+            &options,
+            &Attachments::new(),
+            &AtomicBool::new(false),
+            |p| progress.push((p.completed, p.total, p.item.index)),
+        )
+        .await;
+
+        let outcomes: Vec<_> = summary
+            .items
+            .iter()
+            .map(|i| (i.index, i.outcome, i.attempts))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                (3, Outcome::Passed, 2),
+                (5, Outcome::Failed, 1),
+                (8, Outcome::Passed, 1)
+            ]
+        );
+        assert_eq!((summary.passed, summary.failed), (2, 1));
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress.last().map(|p| (p.0, p.1)), Some((3, 3)));
+        // Four requests at 20 per second take at least 150 ms.
+        assert!(summary.elapsed >= 150.0, "{}", summary.elapsed);
+
+        let summary = replay_batch(
+            &Client::new().unwrap(),
+            vec![(0, entry(format!("http://{addr}/ok"), 200))],
+            &options,
+            &Attachments::new(),
+            &AtomicBool::new(true),
+            |_| {},
+        )
+        .await;
+        assert_eq!(summary.cancelled, 1);
+    }
+}
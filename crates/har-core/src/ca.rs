@@ -0,0 +1,150 @@
+//! A local certificate authority for intercepting HTTPS.
+//!
+//! The recording proxy decrypts TLS by presenting, for each host, a
+//! certificate signed by this authority. Clients trust it once its
+//! certificate is installed, or passed with e.g. `curl --cacert`. The key
+//! never leaves the directory it is created in.
+
+use crate::client::now_millis;
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::format_date_time;
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair,
+    KeyUsagePurpose,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const CERTIFICATE_FILE: &str = "har-analyser-ca.pem";
+const KEY_FILE: &str = "har-analyser-ca.key";
+const NAME: &str = "HAR Analyser Local CA";
+const DAY_MS: f64 = 86_400_000.0;
+
+pub struct CertificateAuthority {
+    certificate_path: PathBuf,
+    certificate_pem: String,
+    issuer: Issuer<'static, KeyPair>,
+    /// PEM certificate chain and key by host, made on first use.
+    leaves: Mutex<HashMap<String, (String, String)>>,
+}
+
+impl CertificateAuthority {
+    /// Loads the authority kept in `dir`, creating the directory and a new
+    /// authority if there is none.
+    pub fn load_or_create(dir: &Path) -> Result<Self> {
+        let certificate_path = dir.join(CERTIFICATE_FILE);
+        let key_path = dir.join(KEY_FILE);
+        let (certificate_pem, key) = if certificate_path.exists() && key_path.exists() {
+            let certificate = fs::read_to_string(&certificate_path)
+                .map_err(|e| Error::io(e, &certificate_path))?;
+            let key = fs::read_to_string(&key_path).map_err(|e| Error::io(e, &key_path))?;
+            let key = KeyPair::from_pem(&key).map_err(|e| {
+                Error::new(ErrorKind::Tls, format!("Invalid CA key: {e}")).with_path(&key_path)
+            })?;
+            (certificate, key)
+        } else {
+            fs::create_dir_all(dir).map_err(|e| Error::io(e, dir))?;
+            let key = KeyPair::generate().map_err(certificate_error)?;
+            let mut params = authority_params();
+            set_validity(&mut params, -1, 3650);
+            let certificate = params.self_signed(&key).map_err(certificate_error)?.pem();
+            write_private(&key_path, &key.serialize_pem())?;
+            fs::write(&certificate_path, &certificate)
+                .map_err(|e| Error::io(e, &certificate_path))?;
+            (certificate, key)
+        };
+        Ok(CertificateAuthority {
+            certificate_path,
+            certificate_pem,
+            issuer: Issuer::new(authority_params(), key),
+            leaves: Mutex::default(),
+        })
+    }
+
+    /// The authority's certificate file, for clients to trust.
+    pub fn certificate_path(&self) -> &Path {
+        &self.certificate_path
+    }
+
+    pub fn certificate_pem(&self) -> &str {
+        &self.certificate_pem
+    }
+
+    /// A PEM certificate chain for `host`, signed by this authority, and
+    /// its PKCS#8 PEM key.
+    pub fn leaf(&self, host: &str) -> Result<(String, String)> {
+        let host = host.to_ascii_lowercase();
+        if let Some(leaf) = self.leaves.lock().unwrap().get(&host) {
+            return Ok(leaf.clone());
+        }
+        let mut params = CertificateParams::new(vec![host.clone()]).map_err(certificate_error)?;
+        params.distinguished_name.push(DnType::CommonName, &host);
+        params.use_authority_key_identifier_extension = true;
+        params.key_usages.push(KeyUsagePurpose::DigitalSignature);
+        params
+            .extended_key_usages
+            .push(ExtendedKeyUsagePurpose::ServerAuth);
+        // Clients reject server certificates valid for much over a year.
+        set_validity(&mut params, -1, 365);
+        let key = KeyPair::generate().map_err(certificate_error)?;
+        let certificate = params
+            .signed_by(&key, &self.issuer)
+            .map_err(certificate_error)?;
+        let leaf = (
+            format!("{}{}", certificate.pem(), self.certificate_pem),
+            key.serialize_pem(),
+        );
+        self.leaves.lock().unwrap().insert(host, leaf.clone());
+        Ok(leaf)
+    }
+}
+
+/// The authority's name and constraints; the key identifies it.
+fn authority_params() -> CertificateParams {
+    let mut params = CertificateParams::default();
+    params.distinguished_name = rcgen::DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, NAME);
+    params
+        .distinguished_name
+        .push(DnType::OrganizationName, "HAR Analyser");
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    params
+}
+
+/// Makes `params` valid from `from` days to `to` days from today.
+fn set_validity(params: &mut CertificateParams, from: i32, to: i32) {
+    let day = |offset: i32| {
+        let date = format_date_time(now_millis() + f64::from(offset) * DAY_MS);
+        let part = |range: std::ops::Range<usize>| date[range].parse().unwrap_or(1);
+        rcgen::date_time_ymd(part(0..4), part(5..7) as u8, part(8..10) as u8)
+    };
+    params.not_before = day(from);
+    params.not_after = day(to);
+}
+
+/// Writes the key readable by its owner only, where the platform allows.
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| Error::io(e, path))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes()).map_err(|e| Error::io(e, path))
+}
+
+fn certificate_error(err: rcgen::Error) -> Error {
+    Error::new(
+        ErrorKind::Tls,
+        format!("Could not make a certificate: {err}"),
+    )
+}
@@ -0,0 +1,385 @@
+//! Compressed HAR archives.
+//!
+//! Captures are often stored as `.har.gz`, `.har.zst`, `.har.br` or inside a
+//! `.zip` bundle. Compression is detected from the leading bytes rather than
+//! the file name, and data is decompressed as it is read so the streaming
+//! loader never holds the whole document in memory.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tempfile::{NamedTempFile, TempPath};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const ZIP_MAGIC: &[u8] = &[0x50, 0x4b, 0x03, 0x04];
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Brotli,
+    Zip,
+}
+
+impl Compression {
+    /// Identifies the format from the first bytes of a file. Brotli streams
+    /// have no magic number, so anything that is neither a known container
+    /// nor JSON is assumed to be Brotli.
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if header.starts_with(ZIP_MAGIC) {
+            Compression::Zip
+        } else {
+            let json = header.strip_prefix(UTF8_BOM).unwrap_or(header);
+            match json.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'{') | None => Compression::None,
+                Some(_) => Compression::Brotli,
+            }
+        }
+    }
+
+    /// Picks the output format for a save path from its extension.
+    pub fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("br") => Compression::Brotli,
+            Some("zip") => Compression::Zip,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Counts bytes as they are consumed, for progress reporting.
+pub struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R> CountingReader<R> {
+    pub fn new(inner: R, count: Rc<Cell<u64>>) -> Self {
+        CountingReader { inner, count }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+fn peek(reader: &mut BufReader<File>) -> io::Result<Compression> {
+    let compression = Compression::detect(reader.fill_buf()?);
+    if compression == Compression::None && reader.buffer().starts_with(UTF8_BOM) {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(compression)
+}
+
+/// Undoes a `Content-Encoding`, such as `gzip` or `br, gzip`. Codings are
+/// listed in the order they were applied, so they are removed in reverse.
+pub fn decode_content(encoding: &str, body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut data = body.to_vec();
+    for coding in encoding.rsplit(',').map(str::trim) {
+        let mut decoded = Vec::new();
+        match coding.to_ascii_lowercase().as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => {
+                flate2::read::MultiGzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+            }
+            // Servers disagree on whether `deflate` is zlib-wrapped; accept both.
+            "deflate" => {
+                if flate2::read::ZlibDecoder::new(&data[..])
+                    .read_to_end(&mut decoded)
+                    .is_err()
+                {
+                    decoded.clear();
+                    flate2::read::DeflateDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+                }
+            }
+            "br" => {
+                brotli::Decompressor::new(&data[..], 64 * 1024).read_to_end(&mut decoded)?;
+            }
+            "zstd" => {
+                zstd::stream::read::Decoder::new(&data[..])?.read_to_end(&mut decoded)?;
+            }
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("unsupported content encoding {other:?}"),
+                ))
+            }
+        }
+        data = decoded;
+    }
+    Ok(data)
+}
+
+/// Lists the `.har` members of a zip archive. Returns an empty list for
+/// files that are not zip archives.
+pub fn list_archive_members(path: &Path) -> Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| Error::io(e, path))?);
+    if peek(&mut reader).map_err(|e| Error::io(e, path))? != Compression::Zip {
+        return Ok(Vec::new());
+    }
+    let archive = zip::ZipArchive::new(reader).map_err(|e| Error::archive(e, path))?;
+    Ok(har_members(&archive))
+}
+
+fn har_members<R: Read + io::Seek>(archive: &zip::ZipArchive<R>) -> Vec<String> {
+    archive
+        .file_names()
+        .filter(|name| name.to_ascii_lowercase().ends_with(".har"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Opens `path`, undoing any compression, and hands the decoded stream to
+/// `read` together with the total number of bytes it will count. For zip
+/// archives `member` selects the HAR inside; it may be omitted when the
+/// archive holds exactly one.
+///
+/// `count` tracks progress against that total: compressed bytes for single
+/// stream formats, decompressed bytes of the member for zip archives.
+pub fn with_decoded<T>(
+    path: &Path,
+    member: Option<&str>,
+    count: Rc<Cell<u64>>,
+    read: impl FnOnce(&mut dyn Read, u64) -> Result<T>,
+) -> Result<T> {
+    let file = File::open(path).map_err(|e| Error::io(e, path))?;
+    let file_size = file.metadata().map_err(|e| Error::io(e, path))?.len();
+    let mut reader = BufReader::new(file);
+    let compression = peek(&mut reader).map_err(|e| Error::io(e, path))?;
+
+    if compression == Compression::Zip {
+        let mut archive = zip::ZipArchive::new(reader).map_err(|e| Error::archive(e, path))?;
+        let name = match member {
+            Some(name) => name.to_string(),
+            None => match har_members(&archive).as_slice() {
+                [only] => only.clone(),
+                [] => return Err(Error::archive("Archive does not contain a .har file", path)),
+                _ => {
+                    return Err(Error::archive(
+                        "Archive contains several .har files; choose one",
+                        path,
+                    ));
+                }
+            },
+        };
+        let entry = archive
+            .by_name(&name)
+            .map_err(|e| Error::archive(e, path))?;
+        let total = entry.size();
+        let mut decoded = BufReader::new(CountingReader::new(entry, count));
+        return read(&mut decoded, total);
+    }
+
+    let raw = CountingReader::new(reader, count);
+    let mut decoded: Box<dyn Read> = match compression {
+        Compression::None => Box::new(BufReader::new(raw)),
+        Compression::Gzip => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(raw))),
+        Compression::Zstd => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::new(raw).map_err(|e| Error::archive(e, path))?,
+        )),
+        Compression::Brotli => Box::new(BufReader::new(brotli::Decompressor::new(raw, 64 * 1024))),
+        Compression::Zip => unreachable!(),
+    };
+    read(&mut decoded, file_size)
+}
+
+/// A file writer that compresses on the fly. Output goes to a temporary
+/// file next to the target, which [`Encoder::finish`] renames over it once
+/// the trailers are written; dropping the encoder without finishing leaves
+/// the target as it was.
+pub struct Encoder {
+    path: PathBuf,
+    temp: TempPath,
+    writer: Writer,
+}
+
+enum Writer {
+    None(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+    Brotli(Box<brotli::CompressorWriter<BufWriter<File>>>),
+    Zip(zip::ZipWriter<File>),
+}
+
+impl Encoder {
+    /// Starts writing `path`. Zip output holds a single member named after
+    /// the file, with the `.zip` extension replaced by `.har`.
+    pub fn create(path: &Path, compression: Compression) -> Result<Self> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let (file, temp) = NamedTempFile::new_in(dir)
+            .map_err(|e| Error::io(e, path))?
+            .into_parts();
+        // A file saved in place keeps its permissions.
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())
+                .map_err(|e| Error::io(e, path))?;
+        }
+        let writer = match compression {
+            Compression::None => Writer::None(BufWriter::new(file)),
+            Compression::Gzip => Writer::Gzip(flate2::write::GzEncoder::new(
+                BufWriter::new(file),
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Writer::Zstd(
+                zstd::stream::write::Encoder::new(BufWriter::new(file), 0)
+                    .map_err(|e| Error::archive(e, path))?,
+            ),
+            Compression::Brotli => Writer::Brotli(Box::new(brotli::CompressorWriter::new(
+                BufWriter::new(file),
+                64 * 1024,
+                9,
+                22,
+            ))),
+            Compression::Zip => {
+                let stem = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("archive");
+                let name = if stem.to_ascii_lowercase().ends_with(".har") {
+                    stem.to_string()
+                } else {
+                    format!("{stem}.har")
+                };
+                let mut zip = zip::ZipWriter::new(file);
+                let options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(true);
+                zip.start_file(name, options)
+                    .map_err(|e| Error::archive(e, path))?;
+                Writer::Zip(zip)
+            }
+        };
+        Ok(Encoder {
+            path: path.to_path_buf(),
+            temp,
+            writer,
+        })
+    }
+
+    /// Writes the trailers and replaces the target with the new file.
+    pub fn finish(self) -> Result<()> {
+        let path = self.path.as_path();
+        let unbuffer =
+            |w: BufWriter<File>| w.into_inner().map_err(|e| Error::io(e.into_error(), path));
+        let file = match self.writer {
+            Writer::None(w) => unbuffer(w)?,
+            Writer::Gzip(w) => unbuffer(w.finish().map_err(|e| Error::io(e, path))?)?,
+            Writer::Zstd(w) => unbuffer(w.finish().map_err(|e| Error::io(e, path))?)?,
+            Writer::Brotli(w) => unbuffer(w.into_inner())?,
+            Writer::Zip(w) => w.finish().map_err(|e| Error::archive(e, path))?,
+        };
+        file.sync_all().map_err(|e| Error::io(e, path))?;
+        self.temp
+            .persist(path)
+            .map_err(|e| Error::io(e.error, path))
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.writer {
+            Writer::None(w) => w.write(buf),
+            Writer::Gzip(w) => w.write(buf),
+            Writer::Zstd(w) => w.write(buf),
+            Writer::Brotli(w) => w.write(buf),
+            Writer::Zip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Writer::None(w) => w.flush(),
+            Writer::Gzip(w) => w.flush(),
+            Writer::Zstd(w) => w.flush(),
+            Writer::Brotli(w) => w.flush(),
+            Writer::Zip(w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats_from_leading_bytes() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+        assert_eq!(Compression::detect(ZSTD_MAGIC), Compression::Zstd);
+        assert_eq!(Compression::detect(b"PK\x03\x04"), Compression::Zip);
+        assert_eq!(
+            Compression::detect(b"\xef\xbb\xbf \r\n{\"log\""),
+            Compression::None
+        );
+        assert_eq!(Compression::detect(b""), Compression::None);
+        assert_eq!(Compression::detect(&[0x1b, 0x2e]), Compression::Brotli);
+    }
+
+    #[test]
+    fn encoded_files_read_back_in_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = r#"{"log":{"version":"1.2","entries":[]}}"#;
+        for name in ["a.har", "a.har.gz", "a.har.zst", "a.har.br", "a.zip"] {
+            let path = dir.path().join(name);
+            let compression = Compression::from_extension(&path);
+            let mut encoder = Encoder::create(&path, compression).unwrap();
+            encoder.write_all(json.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+
+            let header = std::fs::read(&path).unwrap();
+            assert_eq!(Compression::detect(&header), compression, "{name}");
+            let decoded = with_decoded(&path, None, Rc::default(), |reader, _| {
+                let mut text = String::new();
+                reader.read_to_string(&mut text).unwrap();
+                Ok(text)
+            })
+            .unwrap();
+            assert_eq!(decoded, json, "{name}");
+        }
+        assert_eq!(
+            list_archive_members(&dir.path().join("a.zip")).unwrap(),
+            ["a.har"]
+        );
+        assert!(list_archive_members(&dir.path().join("a.har.gz"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn unfinished_saves_leave_the_target_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.har.gz");
+        std::fs::write(&path, "old").unwrap();
+
+        let mut encoder = Encoder::create(&path, Compression::Gzip).unwrap();
+        encoder.write_all(b"{\"log\"").unwrap();
+        drop(encoder);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let encoder = Encoder::create(&path, Compression::None).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
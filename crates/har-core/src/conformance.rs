@@ -0,0 +1,581 @@
+//! Lenient and strict HAR parsing.
+//!
+//! Plenty of tools write almost-valid HAR: a missing `cache` object, `null`
+//! where a string belongs, sizes written as strings. In lenient mode each
+//! object is checked against the HAR 1.2 layout before it reaches the model;
+//! gaps are filled with defaults and every repair is recorded as a
+//! [`Diagnostic`]. Strict mode makes the same repairs so the file still
+//! loads, but reports each one as an error along with anything else the spec
+//! does not allow, such as custom fields without a leading underscore.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParseMode {
+    /// Deserialize straight into the model; the first mismatch fails the load.
+    #[default]
+    Standard,
+    /// Repair what can be repaired and report each repair.
+    Lenient,
+    /// Repair like lenient mode but report every HAR 1.2 violation.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Index into `log.entries`, when the diagnostic concerns an entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<usize>,
+    pub json_path: String,
+    pub message: String,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Str,
+    Int,
+    Num,
+    /// A number that must not be negative; exporters write `-1` for unknown.
+    Duration,
+    Bool,
+    Obj(&'static [Field]),
+    Arr(&'static [Field]),
+    Any,
+}
+
+#[derive(Clone, Copy)]
+struct Field {
+    name: &'static str,
+    kind: Kind,
+    /// Value filled in when a required field is missing.
+    default: Option<Fallback>,
+}
+
+#[derive(Clone, Copy)]
+enum Fallback {
+    Str(&'static str),
+    Int(i64),
+    Num(f64),
+    Obj,
+    Arr,
+}
+
+impl Fallback {
+    fn value(self) -> Value {
+        match self {
+            Fallback::Str(s) => Value::from(s),
+            Fallback::Int(n) => Value::from(n),
+            Fallback::Num(n) => Value::from(n),
+            Fallback::Obj => Value::Object(Map::new()),
+            Fallback::Arr => Value::Array(Vec::new()),
+        }
+    }
+}
+
+const fn req(name: &'static str, kind: Kind, default: Fallback) -> Field {
+    Field {
+        name,
+        kind,
+        default: Some(default),
+    }
+}
+
+const fn opt(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        default: None,
+    }
+}
+
+const COMMENT: Field = opt("comment", Kind::Str);
+
+const CREATOR: &[Field] = &[
+    req("name", Kind::Str, Fallback::Str("")),
+    req("version", Kind::Str, Fallback::Str("")),
+    COMMENT,
+];
+
+const PAGE_TIMINGS: &[Field] = &[
+    opt("onContentLoad", Kind::Num),
+    opt("onLoad", Kind::Num),
+    COMMENT,
+];
+
+const PAGE: &[Field] = &[
+    req("startedDateTime", Kind::Str, Fallback::Str("")),
+    req("id", Kind::Str, Fallback::Str("")),
+    req("title", Kind::Str, Fallback::Str("")),
+    req("pageTimings", Kind::Obj(PAGE_TIMINGS), Fallback::Obj),
+    COMMENT,
+];
+
+/// `log` without `entries`, which the loader streams separately.
+const LOG: &[Field] = &[
+    req("version", Kind::Str, Fallback::Str("1.2")),
+    req("creator", Kind::Obj(CREATOR), Fallback::Obj),
+    opt("browser", Kind::Obj(CREATOR)),
+    opt("pages", Kind::Arr(PAGE)),
+    COMMENT,
+];
+
+const NAME_VALUE: &[Field] = &[
+    req("name", Kind::Str, Fallback::Str("")),
+    req("value", Kind::Str, Fallback::Str("")),
+    COMMENT,
+];
+
+const COOKIE: &[Field] = &[
+    req("name", Kind::Str, Fallback::Str("")),
+    req("value", Kind::Str, Fallback::Str("")),
+    opt("path", Kind::Str),
+    opt("domain", Kind::Str),
+    opt("expires", Kind::Str),
+    opt("httpOnly", Kind::Bool),
+    opt("secure", Kind::Bool),
+    COMMENT,
+];
+
+const PARAM: &[Field] = &[
+    req("name", Kind::Str, Fallback::Str("")),
+    opt("value", Kind::Str),
+    opt("fileName", Kind::Str),
+    opt("contentType", Kind::Str),
+    COMMENT,
+];
+
+const POST_DATA: &[Field] = &[
+    req("mimeType", Kind::Str, Fallback::Str("")),
+    opt("params", Kind::Arr(PARAM)),
+    opt("text", Kind::Str),
+    COMMENT,
+];
+
+const REQUEST: &[Field] = &[
+    req("method", Kind::Str, Fallback::Str("GET")),
+    req("url", Kind::Str, Fallback::Str("")),
+    req("httpVersion", Kind::Str, Fallback::Str("")),
+    req("cookies", Kind::Arr(COOKIE), Fallback::Arr),
+    req("headers", Kind::Arr(NAME_VALUE), Fallback::Arr),
+    req("queryString", Kind::Arr(NAME_VALUE), Fallback::Arr),
+    opt("postData", Kind::Obj(POST_DATA)),
+    req("headersSize", Kind::Int, Fallback::Int(-1)),
+    req("bodySize", Kind::Int, Fallback::Int(-1)),
+    COMMENT,
+];
+
+const CONTENT: &[Field] = &[
+    req("size", Kind::Int, Fallback::Int(0)),
+    opt("compression", Kind::Int),
+    req("mimeType", Kind::Str, Fallback::Str("")),
+    opt("text", Kind::Str),
+    opt("encoding", Kind::Str),
+    COMMENT,
+];
+
+const RESPONSE: &[Field] = &[
+    req("status", Kind::Int, Fallback::Int(0)),
+    req("statusText", Kind::Str, Fallback::Str("")),
+    req("httpVersion", Kind::Str, Fallback::Str("")),
+    req("cookies", Kind::Arr(COOKIE), Fallback::Arr),
+    req("headers", Kind::Arr(NAME_VALUE), Fallback::Arr),
+    req("content", Kind::Obj(CONTENT), Fallback::Obj),
+    req("redirectURL", Kind::Str, Fallback::Str("")),
+    req("headersSize", Kind::Int, Fallback::Int(-1)),
+    req("bodySize", Kind::Int, Fallback::Int(-1)),
+    COMMENT,
+    opt("_transferSize", Kind::Int),
+];
+
+const CACHE_STATE: &[Field] = &[
+    opt("expires", Kind::Str),
+    req("lastAccess", Kind::Str, Fallback::Str("")),
+    req("eTag", Kind::Str, Fallback::Str("")),
+    req("hitCount", Kind::Int, Fallback::Int(0)),
+    COMMENT,
+];
+
+const CACHE: &[Field] = &[
+    opt("beforeRequest", Kind::Obj(CACHE_STATE)),
+    opt("afterRequest", Kind::Obj(CACHE_STATE)),
+    COMMENT,
+];
+
+const TIMINGS: &[Field] = &[
+    opt("blocked", Kind::Num),
+    opt("dns", Kind::Num),
+    opt("connect", Kind::Num),
+    req("send", Kind::Duration, Fallback::Num(0.0)),
+    req("wait", Kind::Duration, Fallback::Num(0.0)),
+    req("receive", Kind::Duration, Fallback::Num(0.0)),
+    opt("ssl", Kind::Num),
+    COMMENT,
+    opt("_blocked_queueing", Kind::Num),
+];
+
+const INITIATOR: &[Field] = &[
+    req("type", Kind::Str, Fallback::Str("other")),
+    opt("url", Kind::Str),
+    opt("lineNumber", Kind::Int),
+    opt("stack", Kind::Any),
+];
+
+const WEB_SOCKET_MESSAGE: &[Field] = &[
+    req("type", Kind::Str, Fallback::Str("")),
+    req("time", Kind::Num, Fallback::Num(0.0)),
+    req("opcode", Kind::Int, Fallback::Int(0)),
+    req("data", Kind::Str, Fallback::Str("")),
+];
+
+const ENTRY: &[Field] = &[
+    opt("pageref", Kind::Str),
+    req("startedDateTime", Kind::Str, Fallback::Str("")),
+    req("time", Kind::Num, Fallback::Num(0.0)),
+    req("request", Kind::Obj(REQUEST), Fallback::Obj),
+    req("response", Kind::Obj(RESPONSE), Fallback::Obj),
+    req("cache", Kind::Obj(CACHE), Fallback::Obj),
+    req("timings", Kind::Obj(TIMINGS), Fallback::Obj),
+    opt("serverIPAddress", Kind::Str),
+    opt("connection", Kind::Str),
+    COMMENT,
+    opt("_initiator", Kind::Obj(INITIATOR)),
+    opt("_priority", Kind::Str),
+    opt("_resourceType", Kind::Str),
+    opt("_fromCache", Kind::Str),
+    opt("_securityState", Kind::Str),
+    opt("_webSocketMessages", Kind::Arr(WEB_SOCKET_MESSAGE)),
+];
+
+/// Checks and repairs the `log` object minus its entries.
+pub fn check_log(log: &mut Value, mode: ParseMode, diagnostics: &mut Vec<Diagnostic>) {
+    let mut walker = Walker {
+        mode,
+        entry: None,
+        diagnostics,
+    };
+    walker.object(log, LOG, "log");
+}
+
+/// Checks and repairs entry `index` of `log.entries`.
+pub fn check_entry(
+    entry: &mut Value,
+    index: usize,
+    mode: ParseMode,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut walker = Walker {
+        mode,
+        entry: Some(index),
+        diagnostics,
+    };
+    walker.object(entry, ENTRY, &format!("log.entries[{index}]"));
+}
+
+struct Walker<'a> {
+    mode: ParseMode,
+    entry: Option<usize>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Walker<'_> {
+    fn report(&mut self, severity: Severity, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            entry: self.entry,
+            json_path: path.to_string(),
+            message,
+        });
+    }
+
+    /// Severity of a repair: a deviation to note when lenient, a spec
+    /// violation when strict.
+    fn violation(&self) -> Severity {
+        match self.mode {
+            ParseMode::Strict => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+
+    fn object(&mut self, value: &mut Value, fields: &[Field], path: &str) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        for field in fields {
+            let child = format!("{path}.{}", field.name);
+            match map.get_mut(field.name) {
+                None | Some(Value::Null) if field.default.is_none() => {
+                    if map.remove(field.name).is_some() {
+                        if self.mode == ParseMode::Strict {
+                            self.report(
+                                Severity::Warning,
+                                &child,
+                                "optional field is null; omit it instead".into(),
+                            );
+                        } else {
+                            self.report(
+                                Severity::Info,
+                                &child,
+                                "null optional field dropped".into(),
+                            );
+                        }
+                    }
+                }
+                None | Some(Value::Null) => {
+                    let what = if map.contains_key(field.name) {
+                        "is null"
+                    } else {
+                        "is missing"
+                    };
+                    let default = field.default.unwrap().value();
+                    self.report(
+                        self.violation(),
+                        &child,
+                        format!("required field {what}; using {default}"),
+                    );
+                    let slot = map.entry(field.name).or_insert(Value::Null);
+                    *slot = default;
+                    self.descend(slot, field.kind, &child);
+                }
+                Some(slot) => {
+                    if self.coerce(slot, field, &child) {
+                        self.descend(slot, field.kind, &child);
+                    } else {
+                        map.remove(field.name);
+                    }
+                }
+            }
+        }
+
+        // Objects nested in a custom field are not governed by the spec.
+        if self.mode == ParseMode::Strict && !path.contains("._") {
+            let known: Vec<&str> = fields.iter().map(|f| f.name).collect();
+            for key in map.keys() {
+                if path == "log" && key == "entries" {
+                    continue;
+                }
+                if !key.starts_with('_') && !known.contains(&key.as_str()) {
+                    self.report(
+                        Severity::Warning,
+                        &format!("{path}.{key}"),
+                        "custom fields must start with an underscore".into(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn descend(&mut self, value: &mut Value, kind: Kind, path: &str) {
+        match (kind, value) {
+            (Kind::Obj(fields), value) => self.object(value, fields, path),
+            (Kind::Arr(fields), Value::Array(items)) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    if item.is_object() {
+                        self.object(item, fields, &format!("{path}[{i}]"));
+                    } else {
+                        self.report(
+                            self.violation(),
+                            &format!("{path}[{i}]"),
+                            format!("expected an object, got {}; dropped", type_name(item)),
+                        );
+                    }
+                }
+                items.retain(Value::is_object);
+            }
+            _ => {}
+        }
+    }
+
+    /// Brings `value` to the field's type. Returns false when it could not
+    /// and the field has no default, in which case the caller drops it.
+    fn coerce(&mut self, value: &mut Value, field: &Field, path: &str) -> bool {
+        let repaired = match (field.kind, &*value) {
+            (Kind::Any, _) => return true,
+            (Kind::Str, Value::String(_)) => return true,
+            (Kind::Str, Value::Number(n)) => Some(Value::from(n.to_string())),
+            (Kind::Str, Value::Bool(b)) => Some(Value::from(b.to_string())),
+            (Kind::Int, Value::Number(n)) if n.is_i64() || n.is_u64() => return true,
+            (Kind::Int, Value::Number(n)) => n.as_f64().map(|f| Value::from(f.round() as i64)),
+            (Kind::Int, Value::String(s)) => parse_number(s).map(|f| Value::from(f.round() as i64)),
+            (Kind::Num | Kind::Duration, Value::Number(n)) => {
+                if matches!(field.kind, Kind::Duration) && n.as_f64().unwrap_or(0.0) < 0.0 {
+                    self.report(
+                        self.violation(),
+                        path,
+                        format!("must not be negative, got {n}; using 0"),
+                    );
+                    *value = Value::from(0.0);
+                }
+                return true;
+            }
+            (Kind::Num | Kind::Duration, Value::String(s)) => parse_number(s).map(Value::from),
+            (Kind::Bool, Value::Bool(_)) => return true,
+            (Kind::Bool, Value::String(s)) => match s.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (Kind::Obj(_), Value::Object(_)) => return true,
+            (Kind::Arr(_), Value::Array(_)) => return true,
+            _ => None,
+        };
+
+        let found = type_name(value);
+        let expected = match field.kind {
+            Kind::Str => "a string",
+            Kind::Int => "an integer",
+            Kind::Num | Kind::Duration => "a number",
+            Kind::Bool => "a boolean",
+            Kind::Obj(_) => "an object",
+            Kind::Arr(_) => "an array",
+            Kind::Any => unreachable!(),
+        };
+        let severity = self.violation();
+        match (repaired, field.default) {
+            (Some(fixed), _) => {
+                self.report(
+                    severity,
+                    path,
+                    format!("expected {expected}, converted {found} {value}"),
+                );
+                *value = fixed;
+                true
+            }
+            (None, Some(default)) => {
+                let default = default.value();
+                self.report(
+                    severity,
+                    path,
+                    format!("expected {expected}, got {found}; using {default}"),
+                );
+                *value = default;
+                true
+            }
+            (None, None) => {
+                self.report(
+                    severity,
+                    path,
+                    format!("expected {expected}, got {found}; dropped"),
+                );
+                false
+            }
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|f| f.is_finite())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::har::HarEntry;
+    use serde_json::json;
+
+    /// Checks an almost-valid entry: `time` and `content.size` written as
+    /// strings, a null comment, a negative `wait`, no `cache` and a custom
+    /// field without an underscore.
+    fn check(mode: ParseMode) -> (Value, Vec<(String, Severity)>) {
+        let mut entry = json!({
+            "startedDateTime": "2024-05-14T09:00:00Z",
+            "time": "12.5",
+            "request": {
+                "method": "GET",
+                "url": "https://example.com/",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "queryString": [],
+                "headersSize": -1,
+                "bodySize": 0,
+                "comment": null
+            },
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "content": { "size": "12", "mimeType": "text/plain" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": 12
+            },
+            "timings": { "send": 0, "wait": -1, "receive": 0 },
+            "tool": "recorder"
+        });
+        let mut diagnostics = Vec::new();
+        check_entry(&mut entry, 3, mode, &mut diagnostics);
+        assert!(diagnostics.iter().all(|d| d.entry == Some(3)));
+        let found = diagnostics
+            .into_iter()
+            .map(|d| (d.json_path, d.severity))
+            .collect();
+        (entry, found)
+    }
+
+    fn expected(severities: &[(&str, Severity)]) -> Vec<(String, Severity)> {
+        severities
+            .iter()
+            .map(|&(path, severity)| (format!("log.entries[3].{path}"), severity))
+            .collect()
+    }
+
+    #[test]
+    fn lenient_repairs_are_warnings() {
+        let (entry, found) = check(ParseMode::Lenient);
+        assert_eq!(
+            found,
+            expected(&[
+                ("time", Severity::Warning),
+                ("request.comment", Severity::Info),
+                ("response.content.size", Severity::Warning),
+                ("cache", Severity::Warning),
+                ("timings.wait", Severity::Warning),
+            ])
+        );
+        let entry: HarEntry = serde_json::from_value(entry).unwrap();
+        assert_eq!((entry.time, entry.response.content.size), (12.5, 12));
+        assert_eq!(entry.timings.wait, 0.0);
+    }
+
+    #[test]
+    fn strict_reports_the_same_repairs_as_errors() {
+        let (entry, found) = check(ParseMode::Strict);
+        assert_eq!(
+            found,
+            expected(&[
+                ("time", Severity::Error),
+                ("request.comment", Severity::Warning),
+                ("response.content.size", Severity::Error),
+                ("cache", Severity::Error),
+                ("timings.wait", Severity::Error),
+                ("tool", Severity::Warning),
+            ])
+        );
+        assert_eq!(entry, check(ParseMode::Lenient).0);
+    }
+}
@@ -0,0 +1,454 @@
+//! Cookies as they appear in `Cookie` and `Set-Cookie` headers.
+//!
+//! Replayed entries list their cookies the way browser exports do: request
+//! cookies split out of the `Cookie` header, response cookies parsed from
+//! each `Set-Cookie` with their attributes. Dates follow the lenient
+//! algorithm of RFC 6265 §5.1.1, since servers send every HTTP date format.
+//!
+//! A [`CookieJar`] keeps cookies across replays the way a browser would, so
+//! flows whose session cookie rotates can be replayed after the recorded
+//! value has stopped working.
+
+use crate::har::{format_date_time, parse_date_time, Extensions, HarCookie, HarEntry};
+use hyper::header::SET_COOKIE;
+use hyper::Uri;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+fn cookie(name: &str, value: &str) -> HarCookie {
+    HarCookie {
+        name: name.trim().to_string(),
+        value: value.trim().trim_matches('"').to_string(),
+        path: None,
+        domain: None,
+        expires: None,
+        http_only: None,
+        secure: None,
+        comment: None,
+        extensions: Extensions::new(),
+    }
+}
+
+/// The `name=value` pairs of a `Cookie` request header.
+pub fn parse_cookie_header(value: &str) -> Vec<HarCookie> {
+    value
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(name, _)| !name.trim().is_empty())
+        .map(|(name, value)| cookie(name, value))
+        .collect()
+}
+
+/// Parses a `Set-Cookie` header received at `now` (milliseconds since the
+/// epoch), which `Max-Age` is relative to. `SameSite` is kept as the
+/// `sameSite` extension Chrome writes.
+pub fn parse_set_cookie(header: &str, now: f64) -> Option<HarCookie> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    if name.trim().is_empty() {
+        return None;
+    }
+    let mut cookie = cookie(name, value);
+    let mut max_age = None;
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "path" if value.starts_with('/') => cookie.path = Some(value.to_string()),
+            "domain" if !value.is_empty() => {
+                cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase())
+            }
+            "expires" => {
+                if let Some(expires) = parse_http_date(value) {
+                    cookie.expires.get_or_insert(format_date_time(expires));
+                }
+            }
+            "max-age" => {
+                if let Ok(seconds) = value.parse::<i64>() {
+                    max_age = Some(seconds);
+                }
+            }
+            "secure" => cookie.secure = Some(true),
+            "httponly" => cookie.http_only = Some(true),
+            "samesite" if !value.is_empty() => {
+                cookie
+                    .extensions
+                    .insert("sameSite".into(), Value::from(value));
+            }
+            _ => {}
+        }
+    }
+    // Max-Age takes precedence over Expires; zero or less expires at once.
+    if let Some(seconds) = max_age {
+        let expires = if seconds <= 0 {
+            0.0
+        } else {
+            now + seconds as f64 * 1000.0
+        };
+        cookie.expires = Some(format_date_time(expires));
+    }
+    Some(cookie)
+}
+
+/// Parses any of the HTTP date formats (`Sun, 06 Nov 1994 08:49:37 GMT`,
+/// `Sunday, 06-Nov-94 08:49:37 GMT`, `Sun Nov  6 08:49:37 1994`) into
+/// milliseconds since the epoch.
+pub fn parse_http_date(s: &str) -> Option<f64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+    for token in s
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != ':')
+        .filter(|t| !t.is_empty())
+    {
+        let digits = token.bytes().take_while(u8::is_ascii_digit).count();
+        if time.is_none() && token.contains(':') {
+            let fields: Vec<u32> = token.split(':').map_while(|f| f.parse().ok()).collect();
+            if let [h, m, s] = fields[..] {
+                time = Some((h, m, s));
+            }
+        } else if day.is_none() && (1..=2).contains(&digits) {
+            day = token[..digits].parse::<u32>().ok();
+        } else if month.is_none() && token.len() >= 3 {
+            let prefix = token[..3].to_ascii_lowercase();
+            if let Some(i) = MONTHS.iter().position(|m| *m == prefix) {
+                month = Some(i + 1);
+            }
+        } else if year.is_none() && (2..=4).contains(&digits) {
+            year = token[..digits].parse::<u32>().ok().map(|y| match y {
+                70..=99 => y + 1900,
+                0..=69 => y + 2000,
+                _ => y,
+            });
+        }
+    }
+    let ((hour, minute, second), day, month, year) = (time?, day?, month?, year?);
+    parse_date_time(&format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"
+    ))
+}
+
+/// A cookie held by a [`CookieJar`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    /// Lowercase host name, without a leading dot.
+    pub domain: String,
+    /// Sent to `domain` only, not its subdomains; true for cookies set
+    /// without a `Domain` attribute.
+    #[serde(default)]
+    pub host_only: bool,
+    #[serde(default = "root_path")]
+    pub path: String,
+    /// ISO 8601 expiry; session cookies have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+}
+
+fn root_path() -> String {
+    "/".into()
+}
+
+impl StoredCookie {
+    fn expired(&self, now: f64) -> bool {
+        self.expires
+            .as_deref()
+            .and_then(parse_date_time)
+            .is_some_and(|expires| expires <= now)
+    }
+
+    fn same(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    fn matches(&self, uri: &Uri, now: f64) -> bool {
+        let host = host(uri);
+        let domain = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+        domain
+            && path_match(uri.path(), &self.path)
+            && (!self.secure || uri.scheme_str() == Some("https"))
+            && !self.expired(now)
+    }
+}
+
+/// Cookies kept between replays, following the storage model of RFC 6265
+/// §5.3 without a public suffix list. It serializes as a plain array so the
+/// app can show and edit it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// The jar a browser would have had at the end of the capture: the
+    /// cookies each request sent, updated by each response's cookies in
+    /// entry order. Expiry is judged at each entry's start time.
+    pub fn from_entries(entries: &[HarEntry]) -> Self {
+        let mut jar = CookieJar::new();
+        for entry in entries {
+            let Ok(uri) = entry.request.url.parse::<Uri>() else {
+                continue;
+            };
+            let now = parse_date_time(&entry.started_date_time).unwrap_or(0.0);
+            for cookie in &entry.request.cookies {
+                jar.seed(&uri, cookie, now);
+            }
+            if entry.response.cookies.is_empty() {
+                for header in &entry.response.headers {
+                    if header.name.eq_ignore_ascii_case(SET_COOKIE.as_str()) {
+                        if let Some(cookie) = parse_set_cookie(&header.value, now) {
+                            jar.store(&uri, &cookie, now);
+                        }
+                    }
+                }
+            } else {
+                for cookie in &entry.response.cookies {
+                    jar.store(&uri, cookie, now);
+                }
+            }
+        }
+        jar
+    }
+
+    pub fn cookies(&self) -> &[StoredCookie] {
+        &self.cookies
+    }
+
+    /// Adds `cookie`, replacing one with the same name, domain and path, or
+    /// removes that cookie if `cookie` has expired by `now`.
+    pub fn insert(&mut self, cookie: StoredCookie, now: f64) {
+        let existing = self.cookies.iter().position(|c| c.same(&cookie));
+        match existing {
+            _ if cookie.expired(now) => {
+                if let Some(i) = existing {
+                    self.cookies.remove(i);
+                }
+            }
+            // Replacing keeps the original's place, which orders the header.
+            Some(i) => self.cookies[i] = cookie,
+            None => self.cookies.push(cookie),
+        }
+    }
+
+    /// Stores a cookie received from `uri`, as parsed by
+    /// [`parse_set_cookie`]. Cookies for a domain `uri` is not within are
+    /// ignored, as are `Secure` ones set over plain HTTP.
+    pub fn store(&mut self, uri: &Uri, cookie: &HarCookie, now: f64) {
+        let host = host(uri);
+        let (domain, host_only) = match cookie.domain.as_deref() {
+            Some(domain) if !domain.is_empty() => {
+                // A bare top-level domain would reach every site under it.
+                if !domain_match(&host, domain) || (!domain.contains('.') && domain != host) {
+                    return;
+                }
+                (domain.to_string(), false)
+            }
+            _ => (host, true),
+        };
+        let secure = cookie.secure.unwrap_or(false);
+        if secure && uri.scheme_str() != Some("https") {
+            return;
+        }
+        self.insert(
+            StoredCookie {
+                name: cookie.name.clone(),
+                value: cookie.value.clone(),
+                domain,
+                host_only,
+                path: cookie
+                    .path
+                    .clone()
+                    .filter(|p| p.starts_with('/'))
+                    .unwrap_or_else(|| default_path(uri.path())),
+                expires: cookie.expires.clone(),
+                secure,
+                http_only: cookie.http_only.unwrap_or(false),
+            },
+            now,
+        );
+    }
+
+    /// Records a cookie a captured request sent to `uri`. The value replaces
+    /// that of a stored cookie that would have been sent with it; otherwise
+    /// the cookie is kept for the whole host.
+    fn seed(&mut self, uri: &Uri, cookie: &HarCookie, now: f64) {
+        if let Some(stored) = self
+            .cookies
+            .iter_mut()
+            .find(|c| c.name == cookie.name && c.matches(uri, now))
+        {
+            stored.value = cookie.value.clone();
+            return;
+        }
+        let domain = cookie.domain.as_deref().map(|d| d.trim_start_matches('.'));
+        self.insert(
+            StoredCookie {
+                name: cookie.name.clone(),
+                value: cookie.value.clone(),
+                domain: domain.map_or_else(|| host(uri), str::to_ascii_lowercase),
+                host_only: domain.is_none(),
+                path: cookie.path.clone().unwrap_or_else(root_path),
+                expires: None,
+                secure: cookie.secure.unwrap_or(false),
+                http_only: cookie.http_only.unwrap_or(false),
+            },
+            now,
+        );
+    }
+
+    /// Stores every `Set-Cookie` of a response from `uri`.
+    pub fn store_response(&mut self, uri: &Uri, headers: &hyper::HeaderMap, now: f64) {
+        for value in headers.get_all(SET_COOKIE) {
+            if let Some(cookie) = value.to_str().ok().and_then(|v| parse_set_cookie(v, now)) {
+                self.store(uri, &cookie, now);
+            }
+        }
+    }
+
+    /// The `Cookie` header to send to `uri`, longest paths first, or `None`
+    /// if no cookie applies.
+    pub fn header(&self, uri: &Uri, now: f64) -> Option<String> {
+        let mut matching: Vec<&StoredCookie> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(uri, now))
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+        Some(
+            matching
+                .iter()
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+fn host(uri: &Uri) -> String {
+    uri.host()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase()
+}
+
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err())
+}
+
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    let path = if path.is_empty() { "/" } else { path };
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the request path, RFC 6265 §5.1.4.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => root_path(),
+        Some(i) => path[..i].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_set_cookie_attributes() {
+        let now = parse_date_time("2024-05-14T09:00:00Z").unwrap();
+        let cookie = parse_set_cookie(
+            "sid=abc; Path=/app; Domain=.Example.com; Expires=Wed, 21-Oct-2015 07:28:00 GMT; \
+             Max-Age=60; Secure; HttpOnly; SameSite=Lax",
+            now,
+        )
+        .unwrap();
+        assert_eq!(
+            (cookie.name.as_str(), cookie.value.as_str()),
+            ("sid", "abc")
+        );
+        assert_eq!(cookie.path.as_deref(), Some("/app"));
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert_eq!(cookie.expires.as_deref(), Some("2024-05-14T09:01:00.000Z"));
+        assert_eq!((cookie.secure, cookie.http_only), (Some(true), Some(true)));
+        assert_eq!(cookie.extensions["sameSite"], "Lax");
+
+        let cookie = parse_set_cookie("a=1; expires=Sun, 06 Nov 1994 08:49:37 GMT", now).unwrap();
+        assert_eq!(cookie.expires.as_deref(), Some("1994-11-06T08:49:37.000Z"));
+        assert!(parse_set_cookie("no-value", now).is_none());
+    }
+
+    #[test]
+    fn parses_every_http_date_format() {
+        let expected = parse_date_time("1994-11-06T08:49:37Z");
+        for date in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(parse_http_date(date), expected, "{date}");
+        }
+        assert_eq!(parse_http_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn jar_follows_rotating_session_cookies() {
+        let mut har: crate::HarFile =
+            serde_json::from_str(include_str!("../tests/fixtures/firefox.har")).unwrap();
+        let now = parse_date_time("2024-05-14T12:00:00Z").unwrap();
+        har.log.entries[1].response.cookies =
+            vec![parse_set_cookie("sid=s1; Path=/; Domain=example.org; Secure", now).unwrap()];
+        let mut jar = CookieJar::from_entries(&har.log.entries);
+        let search: Uri = "https://docs.example.org/search".parse().unwrap();
+        assert_eq!(jar.header(&search, now).as_deref(), Some("lang=en; sid=s1"));
+
+        // A rotated session replaces the old one in place; cookies for other
+        // domains are refused.
+        let mut headers = hyper::HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            "sid=s2; Path=/; Domain=example.org".parse().unwrap(),
+        );
+        headers.append(SET_COOKIE, "evil=1; Domain=other.com".parse().unwrap());
+        jar.store_response(&search, &headers, now);
+        assert_eq!(jar.header(&search, now).as_deref(), Some("lang=en; sid=s2"));
+
+        // `lang` is host-only, while `sid` reaches every subdomain and, no
+        // longer `Secure`, plain HTTP too.
+        let api: Uri = "https://api.example.org/v1".parse().unwrap();
+        assert_eq!(jar.header(&api, now).as_deref(), Some("sid=s2"));
+        let plain: Uri = "http://docs.example.org/".parse().unwrap();
+        assert_eq!(jar.header(&plain, now).as_deref(), Some("lang=en; sid=s2"));
+
+        headers.clear();
+        headers.append(
+            SET_COOKIE,
+            "sid=; Domain=example.org; Max-Age=0".parse().unwrap(),
+        );
+        jar.store_response(&search, &headers, now);
+        assert_eq!(jar.header(&search, now).as_deref(), Some("lang=en"));
+    }
+}
@@ -0,0 +1,560 @@
+//! Comparing a replayed response with the recorded one.
+//!
+//! The result is structured for display: the status change, header changes
+//! that skip values expected to differ on every response, a body diff that
+//! is structural for JSON and line-based for other text, and how the timings
+//! moved. JSON differences are reported at JSONPath-like locations such as
+//! `$.items[2].price`.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::{HarContent, HarEntry, HarHeader, HarTimings};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::{BTreeMap, HashSet};
+
+/// Headers whose values change from one response to the next.
+const VOLATILE_HEADERS: &[&str] = &[
+    "age",
+    "cf-ray",
+    "content-length",
+    "date",
+    "etag",
+    "expires",
+    "last-modified",
+    "nel",
+    "report-to",
+    "request-id",
+    "server-timing",
+    "set-cookie",
+    "traceparent",
+    "via",
+    "x-amz-cf-id",
+    "x-amzn-requestid",
+    "x-amzn-trace-id",
+    "x-cache",
+    "x-correlation-id",
+    "x-request-id",
+    "x-runtime",
+    "x-served-by",
+    "x-timer",
+    "x-trace-id",
+];
+
+/// Beyond this many line pairs, text bodies are reported as replaced
+/// rather than diffed line by line.
+const MAX_LINE_PAIRS: usize = 4_000_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+    /// Header names to leave out of the comparison; the volatile ones by
+    /// default.
+    pub ignore_headers: Vec<String>,
+    /// JSONPath queries for JSON body values to leave out, e.g.
+    /// `$.meta.requestId` or `$.items[*].updatedAt`.
+    pub ignore_paths: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            ignore_headers: VOLATILE_HEADERS.iter().map(|h| h.to_string()).collect(),
+            ignore_paths: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseDiff {
+    /// No difference in status, compared headers or body.
+    pub same: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<StatusChange>,
+    pub headers: Vec<HeaderChange>,
+    pub body: BodyDiff,
+    pub timing: TimingDiff,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusChange {
+    pub recorded: i64,
+    pub replayed: i64,
+}
+
+/// A header that was added, removed or changed. Repeated headers are
+/// compared as their values joined by newlines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderChange {
+    /// Lowercase header name.
+    pub name: String,
+    /// `None` if the header is new in the replay.
+    pub recorded: Option<String>,
+    /// `None` if the replay no longer has the header.
+    pub replayed: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BodyDiff {
+    Same,
+    /// Either body was not captured, so there is nothing to compare.
+    Unavailable,
+    Json {
+        changes: Vec<JsonChange>,
+    },
+    Text {
+        lines: Vec<LineDiff>,
+    },
+    Binary {
+        recorded_size: usize,
+        replayed_size: usize,
+    },
+}
+
+/// A value that differs between two JSON documents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonChange {
+    pub path: String,
+    /// `None` if the value was added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded: Option<Value>,
+    /// `None` if the value was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replayed: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineOp {
+    Equal,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineDiff {
+    pub op: LineOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingDiff {
+    pub recorded: f64,
+    pub replayed: f64,
+    /// `replayed - recorded`, in milliseconds; positive when slower.
+    pub delta: f64,
+    /// The phases both entries measured.
+    pub phases: Vec<PhaseDelta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseDelta {
+    pub name: &'static str,
+    pub recorded: f64,
+    pub replayed: f64,
+    pub delta: f64,
+}
+
+/// Compares the response of `replayed` with that of `recorded`. Both need
+/// their bodies for the body to be compared.
+pub fn diff_responses(
+    recorded: &HarEntry,
+    replayed: &HarEntry,
+    options: &DiffOptions,
+) -> Result<ResponseDiff> {
+    let ignore_paths = options
+        .ignore_paths
+        .iter()
+        .map(|path| {
+            JsonPath::parse(path).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidRule,
+                    format!("{path:?} is not a valid JSONPath: {e}"),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (a, b) = (&recorded.response, &replayed.response);
+    let status = (a.status != b.status).then_some(StatusChange {
+        recorded: a.status,
+        replayed: b.status,
+    });
+    let headers = diff_headers(&a.headers, &b.headers, &options.ignore_headers);
+    let body = diff_bodies(&a.content, &b.content, &ignore_paths);
+    Ok(ResponseDiff {
+        same: status.is_none()
+            && headers.is_empty()
+            && matches!(body, BodyDiff::Same | BodyDiff::Unavailable),
+        status,
+        headers,
+        body,
+        timing: diff_timings(recorded, replayed),
+    })
+}
+
+fn header_map(headers: &[HarHeader], ignore: &[String]) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for header in headers {
+        // HTTP/2 pseudo-headers are not part of the response proper.
+        if header.name.starts_with(':')
+            || ignore.iter().any(|i| i.eq_ignore_ascii_case(&header.name))
+        {
+            continue;
+        }
+        map.entry(header.name.to_ascii_lowercase())
+            .and_modify(|value| {
+                value.push('\n');
+                value.push_str(&header.value);
+            })
+            .or_insert_with(|| header.value.clone());
+    }
+    map
+}
+
+fn diff_headers(
+    recorded: &[HarHeader],
+    replayed: &[HarHeader],
+    ignore: &[String],
+) -> Vec<HeaderChange> {
+    let recorded = header_map(recorded, ignore);
+    let mut replayed = header_map(replayed, ignore);
+    let mut changes = Vec::new();
+    for (name, value) in recorded {
+        match replayed.remove(&name) {
+            Some(new) if new == value => {}
+            new => changes.push(HeaderChange {
+                name,
+                recorded: Some(value),
+                replayed: new,
+            }),
+        }
+    }
+    changes.extend(replayed.into_iter().map(|(name, value)| HeaderChange {
+        name,
+        recorded: None,
+        replayed: Some(value),
+    }));
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+fn diff_bodies(recorded: &HarContent, replayed: &HarContent, ignore: &[JsonPath]) -> BodyDiff {
+    let (Some(a), Some(b)) = (recorded.bytes(), replayed.bytes()) else {
+        return BodyDiff::Unavailable;
+    };
+    let (Ok(a), Ok(b)) = (String::from_utf8(a.clone()), String::from_utf8(b.clone())) else {
+        return if a == b {
+            BodyDiff::Same
+        } else {
+            BodyDiff::Binary {
+                recorded_size: a.len(),
+                replayed_size: b.len(),
+            }
+        };
+    };
+    if let (Ok(a), Ok(b)) = (
+        serde_json::from_str::<Value>(&a),
+        serde_json::from_str::<Value>(&b),
+    ) {
+        let mut ignored = HashSet::new();
+        for path in ignore {
+            for doc in [&a, &b] {
+                ignored.extend(
+                    path.query_located(doc)
+                        .locations()
+                        .map(|location| location.to_json_pointer()),
+                );
+            }
+        }
+        let mut changes = Vec::new();
+        diff_json(
+            &a,
+            &b,
+            &mut String::from("$"),
+            &mut String::new(),
+            &ignored,
+            &mut changes,
+        );
+        return if changes.is_empty() {
+            BodyDiff::Same
+        } else {
+            BodyDiff::Json { changes }
+        };
+    }
+    if a == b {
+        BodyDiff::Same
+    } else {
+        BodyDiff::Text {
+            lines: diff_lines(&a, &b),
+        }
+    }
+}
+
+/// Walks both values in step, tracking the location both as a display path
+/// and as the JSON Pointer the ignored locations are given in.
+fn diff_json(
+    a: &Value,
+    b: &Value,
+    path: &mut String,
+    pointer: &mut String,
+    ignored: &HashSet<String>,
+    changes: &mut Vec<JsonChange>,
+) {
+    if ignored.contains(pointer.as_str()) {
+        return;
+    }
+    let (path_len, pointer_len) = (path.len(), pointer.len());
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                push_key(path, pointer, key);
+                match b.get(key) {
+                    Some(other) => diff_json(value, other, path, pointer, ignored, changes),
+                    None if !ignored.contains(pointer.as_str()) => changes.push(JsonChange {
+                        path: path.clone(),
+                        recorded: Some(value.clone()),
+                        replayed: None,
+                    }),
+                    None => {}
+                }
+                path.truncate(path_len);
+                pointer.truncate(pointer_len);
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                push_key(path, pointer, key);
+                if !ignored.contains(pointer.as_str()) {
+                    changes.push(JsonChange {
+                        path: path.clone(),
+                        recorded: None,
+                        replayed: Some(value.clone()),
+                    });
+                }
+                path.truncate(path_len);
+                pointer.truncate(pointer_len);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                path.push_str(&format!("[{i}]"));
+                pointer.push_str(&format!("/{i}"));
+                match (a.get(i), b.get(i)) {
+                    (Some(x), Some(y)) => diff_json(x, y, path, pointer, ignored, changes),
+                    (x, y) if !ignored.contains(pointer.as_str()) => changes.push(JsonChange {
+                        path: path.clone(),
+                        recorded: x.cloned(),
+                        replayed: y.cloned(),
+                    }),
+                    _ => {}
+                }
+                path.truncate(path_len);
+                pointer.truncate(pointer_len);
+            }
+        }
+        _ if a != b => changes.push(JsonChange {
+            path: path.clone(),
+            recorded: Some(a.clone()),
+            replayed: Some(b.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn push_key(path: &mut String, pointer: &mut String, key: &str) {
+    let plain = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        path.push('.');
+        path.push_str(key);
+    } else {
+        path.push_str(&format!(
+            "['{}']",
+            key.replace('\\', "\\\\").replace('\'', "\\'")
+        ));
+    }
+    pointer.push('/');
+    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+/// A line diff over the longest common subsequence of the lines between
+/// the common prefix and suffix.
+fn diff_lines(a: &str, b: &str) -> Vec<LineDiff> {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let line = |op, text: &str| LineDiff {
+        op,
+        text: text.to_string(),
+    };
+    let mut lines: Vec<LineDiff> = a[..prefix].iter().map(|l| line(LineOp::Equal, l)).collect();
+    let (n, m) = (middle_a.len(), middle_b.len());
+    if n.saturating_mul(m) > MAX_LINE_PAIRS {
+        lines.extend(middle_a.iter().map(|l| line(LineOp::Removed, l)));
+        lines.extend(middle_b.iter().map(|l| line(LineOp::Added, l)));
+    } else {
+        // lcs[i][j] is the LCS length of middle_a[i..] and middle_b[j..].
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if middle_a[i] == middle_b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && middle_a[i] == middle_b[j] {
+                lines.push(line(LineOp::Equal, middle_a[i]));
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+                lines.push(line(LineOp::Added, middle_b[j]));
+                j += 1;
+            } else {
+                lines.push(line(LineOp::Removed, middle_a[i]));
+                i += 1;
+            }
+        }
+    }
+    lines.extend(a[a.len() - suffix..].iter().map(|l| line(LineOp::Equal, l)));
+    lines
+}
+
+fn diff_timings(recorded: &HarEntry, replayed: &HarEntry) -> TimingDiff {
+    let phases = |t: &HarTimings| {
+        [
+            ("blocked", t.blocked),
+            ("dns", t.dns),
+            ("connect", t.connect),
+            ("ssl", t.ssl),
+            ("send", Some(t.send)),
+            ("wait", Some(t.wait)),
+            ("receive", Some(t.receive)),
+        ]
+    };
+    TimingDiff {
+        recorded: recorded.time,
+        replayed: replayed.time,
+        delta: replayed.time - recorded.time,
+        phases: phases(&recorded.timings)
+            .into_iter()
+            .zip(phases(&replayed.timings))
+            .filter_map(|((name, a), (_, b))| match (a, b) {
+                (Some(a), Some(b)) if a >= 0.0 && b >= 0.0 => Some(PhaseDelta {
+                    name,
+                    recorded: a,
+                    replayed: b,
+                    delta: b - a,
+                }),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::har::HarFile;
+    use serde_json::json;
+
+    const FIREFOX: &str = include_str!("../tests/fixtures/firefox.har");
+
+    #[test]
+    fn diffs_status_headers_json_and_timings() {
+        let har: HarFile = serde_json::from_str(FIREFOX).unwrap();
+        let mut recorded = har.log.entries[0].clone();
+        recorded.response.content.mime_type = "application/json".into();
+        recorded.response.content.text = Some(
+            json!({ "id": 1, "items": [{ "sku": "a", "at": 1 }], "meta": { "requestId": "x" } })
+                .to_string(),
+        );
+        let mut replayed = recorded.clone();
+        replayed.response.status = 500;
+        replayed
+            .response
+            .headers
+            .retain(|h| !h.name.eq_ignore_ascii_case("date"));
+        replayed.response.headers.push(HarHeader::new("X-New", "1"));
+        replayed.response.content.text = Some(
+            json!({ "id": 2, "items": [{ "sku": "a", "at": 2 }, 3], "meta": { "requestId": "y" } })
+                .to_string(),
+        );
+        replayed.time = recorded.time + 10.0;
+        replayed.timings.wait += 10.0;
+
+        let options = DiffOptions {
+            ignore_paths: vec!["$.meta.requestId".into(), "$.items[*].at".into()],
+            ..DiffOptions::default()
+        };
+        let diff = diff_responses(&recorded, &replayed, &options).unwrap();
+        assert!(!diff.same);
+        assert_eq!(
+            diff.status,
+            Some(StatusChange {
+                recorded: 200,
+                replayed: 500
+            })
+        );
+        assert_eq!(
+            diff.headers,
+            [HeaderChange {
+                name: "x-new".into(),
+                recorded: None,
+                replayed: Some("1".into())
+            }]
+        );
+        let BodyDiff::Json { changes } = &diff.body else {
+            panic!("expected a JSON diff, got {:?}", diff.body);
+        };
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["$.id", "$.items[1]"]);
+        assert_eq!(diff.timing.delta, 10.0);
+        let wait = diff
+            .timing
+            .phases
+            .iter()
+            .find(|p| p.name == "wait")
+            .unwrap();
+        assert_eq!(wait.delta, 10.0);
+
+        assert!(diff_responses(&recorded, &recorded, &options).unwrap().same);
+    }
+
+    #[test]
+    fn diffs_text_by_line() {
+        let lines = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        let ops: Vec<(LineOp, &str)> = lines.iter().map(|l| (l.op, l.text.as_str())).collect();
+        assert_eq!(
+            ops,
+            [
+                (LineOp::Equal, "a"),
+                (LineOp::Removed, "b"),
+                (LineOp::Equal, "c"),
+                (LineOp::Added, "x"),
+                (LineOp::Equal, "d"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Sending recorded requests to a different environment.
+//!
+//! A [`Retarget`] rewrites the scheme, host, port and path prefix of request
+//! URLs, and fixes up the `Host`, `Origin` and `Referer` headers to match, so
+//! traffic recorded in production can be replayed against staging or a
+//! local server. An [`Environment`] is a named profile combining a retarget
+//! with variables for `{{name}}` placeholders.
+//!
+//! Only the recorded request is rewritten: redirects are followed to
+//! wherever the server points.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::HarRequest;
+use crate::template::{self, Variables};
+use hyper::Uri;
+use serde::{Deserialize, Serialize};
+
+/// URL rewriting. Fields left unset keep the recorded value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Retarget {
+    /// `http` or `https`.
+    pub scheme: Option<String>,
+    pub host: Option<String>,
+    /// The port to connect to. Without one, a recorded explicit port is
+    /// kept only if neither the scheme nor the host changes.
+    pub port: Option<u16>,
+    /// Path prefix to remove from recorded URLs, e.g. `/api`. It matches
+    /// whole segments only.
+    pub strip_prefix: Option<String>,
+    /// Path prefix to put in front, e.g. `/staging/api`.
+    pub add_prefix: Option<String>,
+    /// Hosts to retarget; requests to other hosts, like a CDN, are sent as
+    /// recorded. Empty means every host.
+    pub hosts: Vec<String>,
+}
+
+/// A named set of variables and URL rewriting to replay with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    pub name: String,
+    #[serde(default)]
+    pub variables: Variables,
+    #[serde(default)]
+    pub retarget: Retarget,
+}
+
+impl Environment {
+    /// Fills in placeholders, then retargets `request`. Returns the names of
+    /// placeholders without a value.
+    pub fn apply(&self, request: &mut HarRequest) -> Vec<String> {
+        let missing = template::apply(request, &self.variables);
+        self.retarget.apply(request);
+        missing
+    }
+
+    /// Like [`Environment::apply`], but fails with `InvalidRequest` naming
+    /// the placeholders without a value rather than leaving a literal
+    /// `{{name}}` to be sent.
+    pub fn apply_strict(&self, request: &mut HarRequest) -> Result<()> {
+        let mut missing = self.apply(request);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort();
+        missing.dedup();
+        let names: Vec<String> = missing
+            .iter()
+            .map(|name| format!("{{{{{name}}}}}"))
+            .collect();
+        let message = if self.name.is_empty() {
+            format!("No value for {}", names.join(", "))
+        } else {
+            format!(
+                "Environment {:?} has no value for {}",
+                self.name,
+                names.join(", ")
+            )
+        };
+        Err(Error::new(ErrorKind::InvalidRequest, message))
+    }
+}
+
+impl Retarget {
+    /// Sends requests to the scheme, host and port of `url`, with its path,
+    /// if any, as the prefix: `http://localhost:8080/staging`.
+    pub fn to_base_url(url: &str) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidRequest,
+                format!("{url:?} is not a base URL like http://localhost:8080"),
+            )
+        };
+        let uri: Uri = url.parse().map_err(|_| invalid())?;
+        let (Some(scheme), Some(host)) = (uri.scheme_str(), uri.host()) else {
+            return Err(invalid());
+        };
+        Ok(Retarget {
+            scheme: Some(scheme.to_string()),
+            host: Some(host.to_string()),
+            port: uri.port_u16(),
+            add_prefix: Some(uri.path()).filter(|p| *p != "/").map(str::to_string),
+            ..Retarget::default()
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scheme.is_none()
+            && self.host.is_none()
+            && self.port.is_none()
+            && self.strip_prefix.is_none()
+            && self.add_prefix.is_none()
+    }
+
+    fn targets(&self, uri: &Uri, request_host: &str) -> bool {
+        let host = uri.host().unwrap_or_default();
+        if self.hosts.is_empty() {
+            host.eq_ignore_ascii_case(request_host)
+        } else {
+            self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host))
+        }
+    }
+
+    /// Rewrites the URL of `request`, and the `Host`, `Origin` and `Referer`
+    /// headers that pointed at the same site. Requests to hosts not
+    /// retargeted are left alone.
+    pub fn apply(&self, request: &mut HarRequest) {
+        if self.is_empty() {
+            return;
+        }
+        let Ok(uri) = request.url.parse::<Uri>() else {
+            return;
+        };
+        let request_host = uri.host().unwrap_or_default().to_string();
+        if !self.targets(&uri, &request_host) {
+            return;
+        }
+        request.url = self.rewrite(&uri);
+        let authority = request
+            .url
+            .parse::<Uri>()
+            .ok()
+            .and_then(|uri| uri.authority().map(|a| a.to_string()));
+
+        for header in &mut request.headers {
+            let name = header.name.to_ascii_lowercase();
+            match name.as_str() {
+                "host" => {
+                    if let Some(authority) = &authority {
+                        header.value = authority.clone();
+                    }
+                }
+                "origin" | "referer" => {
+                    if let Ok(uri) = header.value.parse::<Uri>() {
+                        if uri.scheme().is_some() && self.targets(&uri, &request_host) {
+                            header.value = if name == "origin" {
+                                self.origin(&uri)
+                            } else {
+                                self.rewrite(&uri)
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The retargeted `scheme://host[:port]` of `uri`.
+    fn origin(&self, uri: &Uri) -> String {
+        let recorded_scheme = uri.scheme_str().unwrap_or("http");
+        let scheme = self.scheme.as_deref().unwrap_or(recorded_scheme);
+        let recorded_host = uri.host().unwrap_or_default();
+        let host = self.host.as_deref().unwrap_or(recorded_host);
+        let unchanged = scheme == recorded_scheme && host.eq_ignore_ascii_case(recorded_host);
+        let default_port = if scheme == "https" { 443 } else { 80 };
+        match self
+            .port
+            .or(uri.port_u16().filter(|_| unchanged))
+            .filter(|port| *port != default_port)
+        {
+            Some(port) => format!("{scheme}://{host}:{port}"),
+            None => format!("{scheme}://{host}"),
+        }
+    }
+
+    fn rewrite(&self, uri: &Uri) -> String {
+        let mut path = uri.path();
+        if let Some(prefix) = self
+            .strip_prefix
+            .as_deref()
+            .map(|p| p.trim_end_matches('/'))
+        {
+            if let Some(rest) = path.strip_prefix(prefix) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    path = rest;
+                }
+            }
+        }
+        let mut url = self.origin(uri);
+        if let Some(prefix) = &self.add_prefix {
+            url.push_str(prefix.trim_end_matches('/'));
+        }
+        if !path.starts_with('/') {
+            url.push('/');
+        }
+        url.push_str(path);
+        if let Some(query) = uri.query() {
+            url.push('?');
+            url.push_str(query);
+        }
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn request(url: &str, headers: &[(&str, &str)]) -> HarRequest {
+        headers
+            .iter()
+            .fold(test_support::entry("GET", url), |entry, &(name, value)| {
+                entry.request_header(name, value)
+            })
+            .build()
+            .request
+    }
+
+    #[test]
+    fn retargets_url_and_fixes_up_headers() {
+        let environment = Environment {
+            name: "local".into(),
+            variables: Variables::from([("token".to_string(), "dev".to_string())]),
+            retarget: Retarget {
+                scheme: Some("http".into()),
+                host: Some("localhost".into()),
+                port: Some(8080),
+                strip_prefix: Some("/api".into()),
+                add_prefix: Some("/v2/".into()),
+                hosts: Vec::new(),
+            },
+        };
+        let mut sent = request(
+            "https://shop.example.com/api/cart?id=1",
+            &[
+                ("Host", "shop.example.com"),
+                ("Origin", "https://shop.example.com"),
+                ("Referer", "https://shop.example.com/checkout"),
+                ("Authorization", "Bearer {{token}}"),
+            ],
+        );
+        assert!(environment.apply(&mut sent).is_empty());
+        assert_eq!(sent.url, "http://localhost:8080/v2/cart?id=1");
+        let values: Vec<&str> = sent.headers.iter().map(|h| h.value.as_str()).collect();
+        assert_eq!(
+            values,
+            [
+                "localhost:8080",
+                "http://localhost:8080",
+                "http://localhost:8080/v2/checkout",
+                "Bearer dev"
+            ]
+        );
+
+        assert_eq!(
+            Retarget::to_base_url("http://localhost:8080/v2/").unwrap(),
+            Retarget {
+                strip_prefix: None,
+                ..environment.retarget.clone()
+            }
+        );
+        assert!(Retarget::to_base_url("localhost:8080").is_err());
+
+        // `/apiary` is not under `/api`, and other hosts stay as recorded.
+        let mut sent = request("https://shop.example.com/apiary", &[]);
+        environment.retarget.apply(&mut sent);
+        assert_eq!(sent.url, "http://localhost:8080/v2/apiary");
+        let retarget = Retarget {
+            hosts: vec!["api.example.com".into()],
+            ..environment.retarget.clone()
+        };
+        let mut sent = request("https://cdn.example.com/app.js", &[]);
+        retarget.apply(&mut sent);
+        assert_eq!(sent.url, "https://cdn.example.com/app.js");
+    }
+
+    #[test]
+    fn strict_apply_names_missing_placeholders() {
+        let environment = Environment {
+            name: "staging".into(),
+            variables: Variables::from([("id".to_string(), "7".to_string())]),
+            ..Environment::default()
+        };
+        let mut sent = request(
+            "https://example.com/{{tenant}}/{{id}}",
+            &[("Authorization", "Bearer {{token}} {{tenant}}")],
+        );
+        let err = environment.apply_strict(&mut sent).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidRequest);
+        assert_eq!(
+            err.message,
+            r#"Environment "staging" has no value for {{tenant}}, {{token}}"#
+        );
+
+        let mut sent = request("https://example.com/{{id}}", &[]);
+        environment.apply_strict(&mut sent).unwrap();
+        assert_eq!(sent.url, "https://example.com/7");
+    }
+}
@@ -0,0 +1,165 @@
+//! Errors returned by the library.
+//!
+//! Every operation fails with an [`Error`] that serializes as
+//! `{ kind, message, context }`, so the frontend can tell a missing file from
+//! a malformed entry or an unreachable host and say something useful.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// Reading or writing a file failed.
+    Io,
+    /// The document is not valid JSON or does not match the HAR model.
+    Json,
+    /// A compressed or zip archive could not be read or written.
+    Archive,
+    /// The user cancelled a long-running operation.
+    Cancelled,
+    /// A command needed a loaded archive but none is open.
+    NotLoaded,
+    /// The request method is not a valid HTTP token, or is CONNECT.
+    UnsupportedMethod,
+    /// The request could not be built, e.g. because the URL is invalid.
+    InvalidRequest,
+    /// A user-supplied rule is malformed, e.g. an extraction regex or an
+    /// ignored JSONPath.
+    InvalidRule,
+    /// The host name could not be resolved.
+    Dns,
+    /// The TCP connection could not be established or was reset.
+    Connection,
+    /// The TLS handshake failed, e.g. on an untrusted certificate.
+    Tls,
+    /// The request did not complete in time.
+    Timeout,
+    /// The server answered but the exchange failed, e.g. on a bad body.
+    Http,
+    /// A bug or an unexpected runtime failure.
+    Internal,
+}
+
+/// Where an error happened. Only the fields relevant to the error are set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Location inside the document, e.g. `log.entries[412].response.content`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub context: Box<ErrorContext>,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            context: Box::default(),
+        }
+    }
+
+    pub fn io(err: std::io::Error, path: &Path) -> Self {
+        Error::new(ErrorKind::Io, err.to_string()).with_path(path)
+    }
+
+    pub fn archive(err: impl fmt::Display, path: &Path) -> Self {
+        Error::new(ErrorKind::Archive, err.to_string()).with_path(path)
+    }
+
+    /// A JSON error at `json_path`, which may be empty for the document root.
+    pub fn json(err: &serde_json::Error, json_path: impl Into<String>) -> Self {
+        let mut error = Error::new(ErrorKind::Json, err.to_string());
+        if err.line() > 0 {
+            error.context.line = Some(err.line());
+            error.context.column = Some(err.column());
+        }
+        let json_path = json_path.into();
+        if !json_path.is_empty() {
+            error.context.json_path = Some(json_path);
+        }
+        error
+    }
+
+    pub fn cancelled() -> Self {
+        Error::new(ErrorKind::Cancelled, "Operation cancelled")
+    }
+
+    pub fn not_loaded() -> Self {
+        Error::new(ErrorKind::NotLoaded, "No HAR file loaded")
+    }
+
+    pub fn internal(err: impl fmt::Display) -> Self {
+        Error::new(ErrorKind::Internal, err.to_string())
+    }
+
+    /// A replay that failed in the phase `kind` names. The message includes
+    /// the error's sources, which is where the useful detail usually is.
+    pub fn replay(kind: ErrorKind, err: &dyn std::error::Error) -> Self {
+        Error::new(kind, error_chain(err))
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.context.path = Some(path.display().to_string());
+        self
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.context.url = Some(url.into());
+        self
+    }
+
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.context.method = Some(method.into());
+        self
+    }
+}
+
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !message.contains(&text) {
+            message.push_str(": ");
+            message.push_str(&text);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(json_path) = &self.context.json_path {
+            write!(f, " at {json_path}")?;
+        }
+        if let Some(path) = &self.context.path {
+            write!(f, " ({path})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,392 @@
+//! Replaying a sequence of entries as one flow.
+//!
+//! Steps are sent in order. After each one, the extraction rules for that
+//! step capture values such as CSRF tokens, IDs or bearer tokens from its
+//! response, and later steps use them through `{{name}}` placeholders (see
+//! [`crate::template`]). A rule can also replace the value it finds in the
+//! recorded response wherever it appears as a whole token in later recorded
+//! requests, so a captured flow replays without editing.
+
+use crate::client::Client;
+use crate::cookies::CookieJar;
+use crate::environment::Retarget;
+use crate::error::{Error, ErrorKind, Result};
+use crate::form::Attachments;
+use crate::har::{HarContent, HarEntry, HarHeader};
+use crate::replay;
+use crate::template::{self, Variables};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+/// Where a rule finds its value in a response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "expression", rename_all = "camelCase")]
+pub enum Source {
+    /// A JSONPath query (RFC 9535) on a JSON body; the first match. Strings
+    /// are taken as is, other values as JSON.
+    JsonPath(String),
+    /// A regular expression on the body; the first capture group if it has
+    /// one, else the whole match.
+    Regex(String),
+    /// The first response header with this name.
+    Header(String),
+}
+
+/// Captures a value from the response of one step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extraction {
+    /// Variable the value is stored in.
+    pub name: String,
+    /// Position of the step in the flow, from 0.
+    pub step: usize,
+    pub source: Source,
+    /// Also replace the value this rule finds in the recorded response
+    /// wherever it appears in later requests as a whole token, not as part
+    /// of a longer word or number.
+    #[serde(default)]
+    pub replace_recorded: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FlowOptions {
+    pub extractions: Vec<Extraction>,
+    /// Values for placeholders before any are extracted.
+    pub variables: Variables,
+    /// Where to send the steps, applied after placeholders are filled in.
+    pub retarget: Retarget,
+    /// Carry on after a step fails to send; by default the flow stops.
+    pub continue_on_error: bool,
+}
+
+/// What one step of a flow did.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepLog {
+    pub step: usize,
+    pub method: String,
+    /// The URL after substitution.
+    pub url: String,
+    /// The replayed exchange, unless sending failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<HarEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+    /// Placeholders no variable was set for, left as written.
+    pub unresolved: Vec<String>,
+    pub extracted: Vec<Extracted>,
+}
+
+/// The outcome of one extraction rule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extracted {
+    pub name: String,
+    /// `None` if the source did not match.
+    pub value: Option<String>,
+}
+
+enum Matcher {
+    JsonPath(JsonPath),
+    Regex(Regex),
+    Header(String),
+}
+
+impl Matcher {
+    fn new(source: &Source) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| Error::new(ErrorKind::InvalidRule, e.to_string());
+        Ok(match source {
+            Source::JsonPath(path) => Matcher::JsonPath(
+                JsonPath::parse(path)
+                    .map_err(|e| invalid(&format!("{path:?} is not a valid JSONPath: {e}")))?,
+            ),
+            Source::Regex(regex) => Matcher::Regex(Regex::new(regex).map_err(|e| invalid(&e))?),
+            Source::Header(name) => Matcher::Header(name.clone()),
+        })
+    }
+
+    fn find(&self, headers: &[HarHeader], content: &HarContent) -> Option<String> {
+        match self {
+            Matcher::Header(name) => headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(name))
+                .map(|h| h.value.clone()),
+            Matcher::JsonPath(path) => {
+                let json: Value = serde_json::from_str(&body(content)?).ok()?;
+                match path.query(&json).first()? {
+                    Value::String(s) => Some(s.clone()),
+                    value => Some(value.to_string()),
+                }
+            }
+            Matcher::Regex(regex) => {
+                let body = body(content)?;
+                let captures = regex.captures(&body)?;
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|m| m.as_str().to_string())
+            }
+        }
+    }
+}
+
+/// The response body as text, decoding base64.
+fn body(content: &HarContent) -> Option<String> {
+    content
+        .bytes()
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Replays the requests of `steps` in order and returns a log per step sent.
+/// `on_step` sees each log as soon as its step is done. Recorded responses
+/// need their bodies for rules that replace recorded values. With a `jar`,
+/// cookies carry over between steps as in [`replay::replay_request`].
+pub async fn replay_flow(
+    client: &Client,
+    steps: &[HarEntry],
+    options: &FlowOptions,
+    attachments: &Attachments,
+    mut jar: Option<&mut CookieJar>,
+    mut on_step: impl FnMut(&StepLog),
+) -> Result<Vec<StepLog>> {
+    let rules = options
+        .extractions
+        .iter()
+        .map(|rule| {
+            if rule.step >= steps.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidRule,
+                    format!(
+                        "{:?} reads step {}, but the flow has {} steps",
+                        rule.name,
+                        rule.step,
+                        steps.len()
+                    ),
+                ));
+            }
+            Ok((rule, Matcher::new(&rule.source)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut variables = options.variables.clone();
+    // Recorded values to swap for replayed ones in later requests.
+    let mut replacements: Vec<(String, String)> = Vec::new();
+    let mut logs = Vec::new();
+    for (step, recorded) in steps.iter().enumerate() {
+        let mut request = recorded.request.clone();
+        if !replacements.is_empty() {
+            template::edit(&mut request, |text| {
+                replacements
+                    .iter()
+                    .fold(text.to_string(), |text, (old, new)| {
+                        replace_token(&text, old, new)
+                    })
+            });
+        }
+        let unresolved = template::apply(&mut request, &variables);
+        options.retarget.apply(&mut request);
+        let result =
+            replay::replay_request(client, &request, attachments, jar.as_deref_mut()).await;
+
+        let mut log = StepLog {
+            step,
+            method: request.method,
+            url: request.url,
+            entry: None,
+            error: None,
+            unresolved,
+            extracted: Vec::new(),
+        };
+        match result {
+            Ok(entry) => {
+                for (rule, matcher) in rules.iter().filter(|(rule, _)| rule.step == step) {
+                    let value = matcher.find(&entry.response.headers, &entry.response.content);
+                    if let Some(value) = &value {
+                        variables.insert(rule.name.clone(), value.clone());
+                        let response = &recorded.response;
+                        if rule.replace_recorded {
+                            if let Some(old) = matcher.find(&response.headers, &response.content) {
+                                if !old.is_empty() && old != *value {
+                                    replacements.push((old, value.clone()));
+                                }
+                            }
+                        }
+                    }
+                    log.extracted.push(Extracted {
+                        name: rule.name.clone(),
+                        value,
+                    });
+                }
+                log.entry = Some(entry);
+            }
+            Err(err) => log.error = Some(err),
+        }
+        on_step(&log);
+        let failed = log.error.is_some();
+        logs.push(log);
+        if failed && !options.continue_on_error {
+            break;
+        }
+    }
+    Ok(logs)
+}
+
+/// `text` with `old` replaced by `new` where it stands alone: between
+/// quotes, slashes, `=` and `&`, spaces and the like, but not inside a
+/// longer word, number or dotted or dashed token.
+fn replace_token(text: &str, old: &str, new: &str) -> String {
+    let word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    // An edge of `old` that is not a word character is a boundary itself.
+    let open_start = !old.starts_with(word);
+    let open_end = !old.ends_with(word);
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (at, _) in text.match_indices(old) {
+        let end = at + old.len();
+        let before = text[..at].chars().next_back();
+        let after = text[end..].chars().next();
+        if (open_start || !before.is_some_and(word)) && (open_end || !after.is_some_and(word)) {
+            out.push_str(&text[copied..at]);
+            out.push_str(new);
+            copied = end;
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, Reply};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers each request with a JSON token and echoes the request line
+    /// and `Authorization` header back in `X-Seen`.
+    async fn serve() -> std::net::SocketAddr {
+        let served = AtomicUsize::new(0);
+        test_support::serve(move |request| {
+            let n = served.fetch_add(1, Ordering::SeqCst) + 1;
+            let auth = request.header("authorization").unwrap_or("none");
+            Reply::new(
+                200,
+                &[
+                    ("Content-Type", "application/json"),
+                    ("X-Seen", &format!("{} | {auth}", request.line)),
+                ],
+                format!(r#"{{"token":"live{n}","order":{{"id":4{n}}}}}"#),
+            )
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn carries_extracted_values_into_later_steps() {
+        let addr = serve().await;
+        let steps = [
+            test_support::entry("GET", &format!("http://{addr}/login"))
+                .response_body("application/json", r#"{"token":"rec"}"#)
+                .build(),
+            test_support::entry(
+                "GET",
+                &format!("http://{addr}/orders/{{{{order}}}}?from=recent"),
+            )
+            .request_header("Authorization", "Bearer rec")
+            .build(),
+            test_support::entry("GET", &format!("http://{addr}/{{{{missing}}}}")).build(),
+        ];
+        let options = FlowOptions {
+            extractions: vec![
+                Extraction {
+                    name: "token".into(),
+                    step: 0,
+                    source: Source::JsonPath("$.token".into()),
+                    replace_recorded: true,
+                },
+                Extraction {
+                    name: "order".into(),
+                    step: 0,
+                    source: Source::Regex(r#""id":(\d+)"#.into()),
+                    replace_recorded: false,
+                },
+                Extraction {
+                    name: "seen".into(),
+                    step: 1,
+                    source: Source::Header("x-seen".into()),
+                    replace_recorded: false,
+                },
+            ],
+            ..FlowOptions::default()
+        };
+
+        let mut seen = 0;
+        let logs = replay_flow(
+            &Client::new().unwrap(),
+            &steps,
+            &options,
+            &Attachments::new(),
+            None,
+            |_| seen += 1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(seen, 3);
+        assert_eq!(
+            logs[0].extracted,
+            [
+                Extracted {
+                    name: "token".into(),
+                    value: Some("live1".into())
+                },
+                Extracted {
+                    name: "order".into(),
+                    value: Some("41".into())
+                },
+            ]
+        );
+        assert_eq!(
+            logs[1].extracted[0].value.as_deref(),
+            Some("GET /orders/41?from=recent HTTP/1.1 | Bearer live1")
+        );
+        assert!(logs[1].unresolved.is_empty());
+        assert_eq!(logs[2].unresolved, ["missing"]);
+
+        let options = FlowOptions {
+            extractions: vec![Extraction {
+                name: "x".into(),
+                step: 0,
+                source: Source::Regex("(".into()),
+                replace_recorded: false,
+            }],
+            ..FlowOptions::default()
+        };
+        let err = replay_flow(
+            &Client::new().unwrap(),
+            &steps,
+            &options,
+            &Attachments::new(),
+            None,
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidRule);
+    }
+
+    #[test]
+    fn replaces_recorded_values_only_as_whole_tokens() {
+        let text = r#"{"id":7,"qty":17,"ref":"7-a","v":"1.7","tags":["7"]}"#;
+        assert_eq!(
+            replace_token(text, "7", "99"),
+            r#"{"id":99,"qty":17,"ref":"7-a","v":"1.7","tags":["99"]}"#
+        );
+        assert_eq!(
+            replace_token("/users/7/items?user=7&page=70", "7", "8"),
+            "/users/8/items?user=8&page=70"
+        );
+        assert_eq!(replace_token("Bearer abc.def", "abc.def", "x"), "Bearer x");
+    }
+}
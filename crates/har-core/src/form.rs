@@ -0,0 +1,290 @@
+//! Request bodies rebuilt from `postData.params`.
+//!
+//! Browsers often record form submissions and uploads as a parameter list
+//! with no `text`, or with file contents left out. These helpers encode the
+//! parameters again as `application/x-www-form-urlencoded` or
+//! `multipart/form-data`, reading files the user attached in place of the
+//! contents that were not captured.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::{HarParam, HarPostData};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Local files to send for `fileName` params, keyed by param name.
+pub type Attachments = HashMap<String, PathBuf>;
+
+/// A request body and the `Content-Type` it must be sent with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+}
+
+/// The body to send for `post`. The recorded `text` wins unless it is empty
+/// and there are params, or a file has been attached for one of them.
+pub fn body(post: &HarPostData, attachments: &Attachments) -> Result<Body> {
+    let params = post.params.as_deref().unwrap_or_default();
+    let text = post.text.as_deref().unwrap_or_default();
+    let attached = params.iter().any(|p| attachments.contains_key(&p.name));
+    if params.is_empty() || (!text.is_empty() && !attached) {
+        let bytes = post.bytes().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidRequest,
+                "postData.text is marked as base64 but is not valid base64",
+            )
+        })?;
+        return Ok(Body {
+            bytes,
+            content_type: post.mime_type.clone(),
+        });
+    }
+
+    let essence = post
+        .mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if essence == "multipart/form-data" {
+        multipart(post, params, attachments)
+    } else {
+        Ok(Body {
+            bytes: urlencoded(params).into_bytes(),
+            content_type: if essence.is_empty() {
+                "application/x-www-form-urlencoded".into()
+            } else {
+                post.mime_type.clone()
+            },
+        })
+    }
+}
+
+/// Encodes params as `name=value` pairs, percent-encoding both sides.
+pub fn urlencoded(params: &[HarParam]) -> String {
+    params
+        .iter()
+        .map(|p| {
+            format!(
+                "{}={}",
+                form_encode(&p.name),
+                form_encode(p.value.as_deref().unwrap_or_default())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn form_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                out.push(b as char)
+            }
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+/// The `boundary` parameter of a multipart content type, if any.
+pub fn boundary(mime_type: &str) -> Option<&str> {
+    mime_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"'))
+            .filter(|b| !b.is_empty())
+    })
+}
+
+fn multipart(post: &HarPostData, params: &[HarParam], attachments: &Attachments) -> Result<Body> {
+    let (boundary, content_type) = match boundary(&post.mime_type) {
+        Some(b) => (b.to_string(), post.mime_type.clone()),
+        None => {
+            let b = new_boundary();
+            let content_type = format!("multipart/form-data; boundary={b}");
+            (b, content_type)
+        }
+    };
+
+    let mut bytes = Vec::new();
+    for param in params {
+        bytes.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        let name = quote(&param.name);
+        match &param.file_name {
+            Some(file_name) => {
+                let attachment = attachments.get(&param.name);
+                let file_name = match attachment {
+                    Some(path) if file_name.is_empty() => path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    _ => file_name.clone(),
+                };
+                bytes.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{name}\"; filename=\"{}\"\r\n",
+                        quote(&file_name)
+                    )
+                    .as_bytes(),
+                );
+                let content_type = param
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream");
+                bytes.extend_from_slice(format!("Content-Type: {content_type}\r\n\r\n").as_bytes());
+                match attachment {
+                    Some(path) => {
+                        let contents = std::fs::read(path).map_err(|e| Error::io(e, path))?;
+                        push_content(&mut bytes, &contents, &boundary)?;
+                    }
+                    None => push_content(&mut bytes, value(param), &boundary)?,
+                }
+            }
+            None => {
+                bytes.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{name}\"\r\n").as_bytes(),
+                );
+                if let Some(content_type) = &param.content_type {
+                    bytes.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+                }
+                bytes.extend_from_slice(b"\r\n");
+                push_content(&mut bytes, value(param), &boundary)?;
+            }
+        }
+        bytes.extend_from_slice(b"\r\n");
+    }
+    bytes.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    Ok(Body {
+        bytes,
+        content_type,
+    })
+}
+
+fn value(param: &HarParam) -> &[u8] {
+    param.value.as_deref().unwrap_or_default().as_bytes()
+}
+
+/// Appends a part's contents, refusing any that would end the part early.
+fn push_content(bytes: &mut Vec<u8>, content: &[u8], boundary: &str) -> Result<()> {
+    let delimiter = format!("--{boundary}");
+    if content
+        .windows(delimiter.len())
+        .any(|w| w == delimiter.as_bytes())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidRequest,
+            format!("A form field contains the multipart boundary {boundary:?}"),
+        ));
+    }
+    bytes.extend_from_slice(content);
+    Ok(())
+}
+
+fn new_boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!(
+        "----HarAnalyser{:032x}",
+        nanos ^ (u128::from(std::process::id()) << 64)
+    )
+}
+
+/// Escapes a quoted-string value in a `Content-Disposition` header.
+fn quote(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post(value: serde_json::Value) -> HarPostData {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn urlencodes_params_without_text() {
+        let post = post(json!({
+            "mimeType": "application/x-www-form-urlencoded",
+            "params": [
+                { "name": "q", "value": "a b&c" },
+                { "name": "lang", "value": "en" }
+            ]
+        }));
+        let body = body(&post, &Attachments::new()).unwrap();
+        assert_eq!(body.bytes, b"q=a+b%26c&lang=en");
+        assert_eq!(body.content_type, "application/x-www-form-urlencoded");
+    }
+
+    #[test]
+    fn multipart_keeps_boundary_and_reads_attachments() {
+        let post = post(json!({
+            "mimeType": "multipart/form-data; boundary=----WebKitFormBoundaryX",
+            "params": [
+                { "name": "title", "value": "Report" },
+                { "name": "upload", "fileName": "data.csv", "contentType": "text/csv" }
+            ]
+        }));
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "a,b\n1,2\n").unwrap();
+        let attachments = Attachments::from([("upload".to_string(), file.path().to_path_buf())]);
+
+        let body = body(&post, &attachments).unwrap();
+        assert_eq!(
+            body.content_type,
+            "multipart/form-data; boundary=----WebKitFormBoundaryX"
+        );
+        assert_eq!(
+            String::from_utf8(body.bytes).unwrap(),
+            "------WebKitFormBoundaryX\r\n\
+             Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+             Report\r\n\
+             ------WebKitFormBoundaryX\r\n\
+             Content-Disposition: form-data; name=\"upload\"; filename=\"data.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n\
+             a,b\n1,2\n\r\n\
+             ------WebKitFormBoundaryX--\r\n"
+        );
+    }
+
+    #[test]
+    fn recorded_text_wins_without_attachments() {
+        let post = post(json!({
+            "mimeType": "multipart/form-data",
+            "text": "raw",
+            "params": [{ "name": "a", "value": "b" }]
+        }));
+        let raw = body(&post, &Attachments::new()).unwrap();
+        assert_eq!(raw.bytes, b"raw");
+
+        let post = HarPostData { text: None, ..post };
+        let rebuilt = body(&post, &Attachments::new()).unwrap();
+        let boundary = boundary(&rebuilt.content_type).unwrap().to_string();
+        assert!(String::from_utf8(rebuilt.bytes)
+            .unwrap()
+            .ends_with(&format!("--{boundary}--\r\n")));
+    }
+
+    #[test]
+    fn base64_text_is_sent_decoded() {
+        let mut post = post(json!({ "mimeType": "application/octet-stream" }));
+        post.set_bytes(&[0x1f, 0x8b, 0x08, 0xff]);
+        assert!(post.is_base64());
+        let sent = body(&post, &Attachments::new()).unwrap();
+        assert_eq!(sent.bytes, [0x1f, 0x8b, 0x08, 0xff]);
+
+        post.text = Some("not base64!".into());
+        assert!(body(&post, &Attachments::new()).is_err());
+    }
+}
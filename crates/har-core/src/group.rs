@@ -0,0 +1,234 @@
+//! Entries grouped by host or by endpoint.
+//!
+//! Endpoints are a method and a URL template: path segments that look like
+//! identifiers, meaning numbers, UUIDs and hex hashes, become `{id}`, so
+//! `/users/42/orders` and `/users/7/orders` are one endpoint. The query
+//! is ignored.
+
+use crate::har::HarEntry;
+use crate::summary::Distribution;
+use hyper::Uri;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupBy {
+    Host,
+    /// Method, host and templated path.
+    #[default]
+    Endpoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    /// `host`, or `METHOD host/path/{id}` for an endpoint.
+    pub key: String,
+    /// Host and port as in the URLs, in lower case.
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// The templated path of an endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub count: usize,
+    /// Entries that got no response or a 4xx or 5xx status.
+    pub errors: usize,
+    pub error_rate: f64,
+    /// Total entry times.
+    pub time: Distribution,
+    /// Request body bytes sent, in total.
+    pub request_bytes: i64,
+    /// Decoded response body bytes, in total.
+    pub response_bytes: i64,
+    /// Decoded response body bytes per entry.
+    pub response_size: Distribution,
+    /// Positions of the group's entries in the archive.
+    pub entries: Vec<usize>,
+}
+
+/// Groups `entries`, each given with its position in the archive, slowest
+/// group first by 90th percentile time.
+pub fn group<'a>(
+    entries: impl IntoIterator<Item = (usize, &'a HarEntry)>,
+    by: GroupBy,
+) -> Vec<Group> {
+    let mut groups: Vec<(Group, Vec<f64>, Vec<f64>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (index, entry) in entries {
+        let (host, path) = host_and_path(&entry.request.url);
+        let method = entry.request.method.to_ascii_uppercase();
+        let (key, method, path) = match by {
+            GroupBy::Host => (host.clone(), None, None),
+            GroupBy::Endpoint => {
+                let path = template_path(&path);
+                (format!("{method} {host}{path}"), Some(method), Some(path))
+            }
+        };
+        let position = *positions.entry(key.clone()).or_insert_with(|| {
+            let group = Group {
+                key,
+                host,
+                method,
+                path,
+                count: 0,
+                errors: 0,
+                error_rate: 0.0,
+                time: Distribution::default(),
+                request_bytes: 0,
+                response_bytes: 0,
+                response_size: Distribution::default(),
+                entries: Vec::new(),
+            };
+            groups.push((group, Vec::new(), Vec::new()));
+            groups.len() - 1
+        });
+
+        let (group, times, sizes) = &mut groups[position];
+        let status = entry.response.status;
+        group.count += 1;
+        group.errors += usize::from(status == 0 || status >= 400);
+        group.request_bytes += entry.request.body_size.max(0);
+        group.response_bytes += entry.response.content.size.max(0);
+        group.entries.push(index);
+        if entry.time >= 0.0 {
+            times.push(entry.time);
+        }
+        sizes.push(entry.response.content.size.max(0) as f64);
+    }
+
+    let mut groups: Vec<Group> = groups
+        .into_iter()
+        .map(|(mut group, times, sizes)| {
+            group.error_rate = group.errors as f64 / group.count as f64;
+            group.time = Distribution::of(times);
+            group.response_size = Distribution::of(sizes);
+            group
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.time
+            .p90
+            .total_cmp(&a.time.p90)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    groups
+}
+
+/// `path` with each segment that looks like an identifier replaced by
+/// `{id}`.
+pub fn template_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if is_identifier(segment) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Numbers, UUIDs and hex strings of 16 digits or more, like MD5 and SHA
+/// hashes and MongoDB object IDs.
+fn is_identifier(segment: &str) -> bool {
+    let hex = |s: &str| s.bytes().all(|b| b.is_ascii_hexdigit());
+    if segment.is_empty() {
+        return false;
+    }
+    if segment.bytes().all(|b| b.is_ascii_digit()) {
+        return true;
+    }
+    let parts: Vec<&str> = segment.split('-').collect();
+    if parts.iter().map(|p| p.len()).eq([8, 4, 4, 4, 12]) && parts.iter().all(|p| hex(p)) {
+        return true;
+    }
+    segment.len() >= 16 && hex(segment)
+}
+
+/// The lower-case authority and the path of a recorded URL.
+fn host_and_path(url: &str) -> (String, String) {
+    match url.parse::<Uri>() {
+        Ok(uri) => (
+            uri.authority()
+                .map(|a| a.as_str().to_ascii_lowercase())
+                .unwrap_or_default(),
+            uri.path().to_string(),
+        ),
+        Err(_) => {
+            let path = url.split(['?', '#']).next().unwrap_or_default();
+            (String::new(), path.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn templates_identifiers() {
+        assert_eq!(
+            template_path("/users/42/orders/3f2b9c4e-1a2b-4c3d-8e9f-0a1b2c3d4e5f"),
+            "/users/{id}/orders/{id}"
+        );
+        assert_eq!(
+            template_path("/blobs/d41d8cd98f00b204e9800998ecf8427e/v2/cafe"),
+            "/blobs/{id}/v2/cafe"
+        );
+        assert_eq!(template_path("/"), "/");
+    }
+
+    #[test]
+    fn groups_by_host_and_endpoint() {
+        let entries = [
+            test_support::entry("GET", "https://api.example.com/users/1/orders?page=2")
+                .time(100.0)
+                .set("/request/bodySize", 10)
+                .set("/response/content/size", 200)
+                .build(),
+            test_support::entry("get", "https://API.example.com/users/2/orders")
+                .status(500)
+                .time(300.0)
+                .set("/request/bodySize", 10)
+                .set("/response/content/size", 200)
+                .build(),
+            test_support::entry("POST", "https://api.example.com/users/2/orders")
+                .status(201)
+                .time(50.0)
+                .build(),
+            test_support::entry("GET", "https://cdn.example.com/app.js")
+                .time(20.0)
+                .build(),
+        ];
+        let indexed = || entries.iter().enumerate();
+
+        let endpoints = group(indexed(), GroupBy::Endpoint);
+        let keys: Vec<_> = endpoints.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "GET api.example.com/users/{id}/orders",
+                "POST api.example.com/users/{id}/orders",
+                "GET cdn.example.com/app.js"
+            ]
+        );
+        let orders = &endpoints[0];
+        assert_eq!(
+            (orders.count, orders.errors, orders.error_rate),
+            (2, 1, 0.5)
+        );
+        assert_eq!((orders.time.p50, orders.time.p99), (100.0, 300.0));
+        assert_eq!((orders.request_bytes, orders.response_bytes), (20, 400));
+        assert_eq!(orders.entries, [0, 1]);
+        assert_eq!(orders.path.as_deref(), Some("/users/{id}/orders"));
+
+        let hosts = group(indexed(), GroupBy::Host);
+        let counts: Vec<_> = hosts.iter().map(|g| (g.key.as_str(), g.count)).collect();
+        assert_eq!(counts, [("api.example.com", 3), ("cdn.example.com", 1)]);
+    }
+}
@@ -0,0 +1,687 @@
+//! HAR 1.2 data model.
+//!
+//! Field names follow the spec (<http://www.softwareishard.com/blog/har-12-spec/>)
+//! exactly, so archives exported by Chrome, Firefox and other tools load
+//! without any key rewriting and save back out in the same shape.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Fields the spec does not define, keyed by their name as written. Exporters
+/// prefix custom fields with `_`; keeping them here makes save lossless.
+pub type Extensions = Map<String, Value>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarFile {
+    pub log: HarLog,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl HarFile {
+    /// An empty HAR 1.2 archive written by the named tool.
+    pub fn new(creator: &str, version: &str) -> Self {
+        HarFile {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: creator.to_string(),
+                    version: version.to_string(),
+                    comment: None,
+                    extensions: Extensions::new(),
+                },
+                browser: None,
+                pages: None,
+                entries: Vec::new(),
+                comment: None,
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<HarBrowser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<Vec<HarPage>>,
+    pub entries: Vec<HarEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarBrowser {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPage {
+    pub started_date_time: String,
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub page_timings: HarPageTimings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPageTimings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_content_load: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_load: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pageref: Option<String>,
+    pub started_date_time: String,
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: HarCache,
+    pub timings: HarTimings,
+    #[serde(
+        rename = "serverIPAddress",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub server_ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Chrome: what caused the request (parser, script, preflight, ...).
+    #[serde(
+        rename = "_initiator",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub initiator: Option<HarInitiator>,
+    /// Chrome: network priority, e.g. `VeryHigh` or `Low`.
+    #[serde(rename = "_priority", default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Chrome: DevTools resource type, e.g. `document`, `xhr`, `fetch`, `image`.
+    #[serde(
+        rename = "_resourceType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resource_type: Option<String>,
+    /// Chrome: `memory` or `disk` when the response came from the browser cache.
+    #[serde(
+        rename = "_fromCache",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub from_cache: Option<String>,
+    /// Firefox: TLS state of the connection, e.g. `secure` or `insecure`.
+    #[serde(
+        rename = "_securityState",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub security_state: Option<String>,
+    /// Chrome: frames sent and received over a WebSocket connection.
+    #[serde(
+        rename = "_webSocketMessages",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub web_socket_messages: Option<Vec<HarWebSocketMessage>>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl HarEntry {
+    /// `startedDateTime` as milliseconds since the Unix epoch.
+    pub fn started_millis(&self) -> Option<f64> {
+        parse_date_time(&self.started_date_time)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarInitiator {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<i64>,
+    /// JavaScript call stack for script initiators, kept as written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Value>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarWebSocketMessage {
+    /// `send` or `receive`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Seconds since the epoch.
+    pub time: f64,
+    pub opcode: i64,
+    pub data: String,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarHeader>,
+    pub query_string: Vec<HarQueryString>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: i64,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarHeader>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Chrome: bytes received on the wire, headers included.
+    #[serde(
+        rename = "_transferSize",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub transfer_size: Option<i64>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarHeader {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl HarHeader {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        HarHeader {
+            name: name.into(),
+            value: value.into(),
+            comment: None,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarQueryString {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<HarParam>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl HarPostData {
+    /// Whether `text` is base64, which the `_encoding` extension marks. HAR
+    /// has no `encoding` for request bodies, so bodies that are not UTF-8
+    /// are recorded this way instead of as corrupted text.
+    pub fn is_base64(&self) -> bool {
+        self.extensions.get("_encoding").and_then(Value::as_str) == Some("base64")
+    }
+
+    /// The body as bytes, decoding base64. Empty when no text was recorded;
+    /// `None` when `text` is marked as base64 but is not.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        let text = self.text.as_deref().unwrap_or_default();
+        if self.is_base64() {
+            BASE64.decode(text).ok()
+        } else {
+            Some(text.as_bytes().to_vec())
+        }
+    }
+
+    /// Records `bytes` as `text`, in base64 if they are not UTF-8.
+    pub fn set_bytes(&mut self, bytes: &[u8]) {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => {
+                self.text = Some(text.to_string());
+                self.extensions.remove("_encoding");
+            }
+            Err(_) => {
+                self.text = Some(BASE64.encode(bytes));
+                self.extensions
+                    .insert("_encoding".to_string(), Value::from("base64"));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarParam {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<i64>,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl HarContent {
+    /// The body as bytes, decoding base64. `None` when no text was recorded
+    /// or it is not valid base64.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        let text = self.text.as_ref()?;
+        match self.encoding.as_deref() {
+            Some("base64") => BASE64.decode(text).ok(),
+            _ => Some(text.clone().into_bytes()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCache {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_request: Option<HarCacheState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_request: Option<HarCacheState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarCacheState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    pub last_access: String,
+    #[serde(rename = "eTag")]
+    pub etag: String,
+    pub hit_count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+/// Phase timings in milliseconds. `blocked`, `dns`, `connect` and `ssl` are
+/// optional in the spec; exporters write `-1` when a phase does not apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarTimings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect: Option<f64>,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Chrome: part of `blocked` spent queued before the request could start.
+    #[serde(
+        rename = "_blocked_queueing",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub blocked_queueing: Option<f64>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+/// Parses an ISO 8601 date-time with a time zone, as HAR requires
+/// (`YYYY-MM-DDThh:mm:ss.sTZD`), into milliseconds since the Unix epoch.
+/// Fractional seconds are optional; the zone is `Z` or `±hh:mm`.
+pub fn parse_date_time(s: &str) -> Option<f64> {
+    fn digits(s: &[u8], len: usize) -> Option<i64> {
+        let part = s.get(..len)?;
+        part.iter()
+            .all(u8::is_ascii_digit)
+            .then(|| part.iter().fold(0, |n, &b| n * 10 + i64::from(b - b'0')))
+    }
+
+    let b = s.as_bytes();
+    if b.len() < 20
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || b[10] != b'T'
+        || b[13] != b':'
+        || b[16] != b':'
+    {
+        return None;
+    }
+    let (year, month, day) = (digits(b, 4)?, digits(&b[5..], 2)?, digits(&b[8..], 2)?);
+    let (hour, minute, second) = (
+        digits(&b[11..], 2)?,
+        digits(&b[14..], 2)?,
+        digits(&b[17..], 2)?,
+    );
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > month_days[month as usize - 1]
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &b[19..];
+    let mut fraction = 0.0;
+    if let Some(digits) = rest.strip_prefix(b".") {
+        let len = digits.iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        fraction = std::str::from_utf8(&rest[..=len])
+            .ok()?
+            .parse::<f64>()
+            .ok()?;
+        rest = &digits[len..];
+    }
+    let offset = match rest {
+        b"Z" => 0,
+        [sign @ (b'+' | b'-'), zone @ ..] => {
+            let (h, m) = match zone {
+                [_, _, b':', _, _] => (digits(zone, 2)?, digits(&zone[3..], 2)?),
+                [_, _, _, _] => (digits(zone, 2)?, digits(&zone[2..], 2)?),
+                _ => return None,
+            };
+            if h > 23 || m > 59 {
+                return None;
+            }
+            let minutes = h * 60 + m;
+            if *sign == b'-' {
+                -minutes
+            } else {
+                minutes
+            }
+        }
+        _ => return None,
+    };
+
+    // Days since the epoch for the proleptic Gregorian calendar.
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + hour * 3600 + minute * 60 + second - offset * 60;
+    Some((seconds as f64 + fraction) * 1000.0)
+}
+
+/// Formats milliseconds since the Unix epoch as a HAR date-time in UTC, e.g.
+/// `2024-05-14T09:12:03.482Z`.
+pub fn format_date_time(millis: f64) -> String {
+    let millis = millis.round() as i64;
+    let (days, ms_of_day) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+
+    // Inverse of the day count in `parse_date_time`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let m = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * m + 2) / 5 + 1;
+    let month = if m < 10 { m + 3 } else { m - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const CHROME: &str = include_str!("../tests/fixtures/chrome.har");
+    const FIREFOX: &str = include_str!("../tests/fixtures/firefox.har");
+
+    /// Structural equality where numbers compare numerically, since `-1`
+    /// reads back into an `f64` field and is written out as `-1.0`.
+    fn assert_same(written: &Value, original: &Value, path: &str) {
+        match (written, original) {
+            (Value::Object(w), Value::Object(o)) => {
+                for key in o.keys() {
+                    assert!(w.contains_key(key), "{path}.{key} was dropped");
+                }
+                for (key, value) in w {
+                    let source = o
+                        .get(key)
+                        .unwrap_or_else(|| panic!("{path}.{key} is not in the source archive"));
+                    assert_same(value, source, &format!("{path}.{key}"));
+                }
+            }
+            (Value::Array(w), Value::Array(o)) => {
+                assert_eq!(w.len(), o.len(), "{path} length differs");
+                for (i, (a, b)) in w.iter().zip(o).enumerate() {
+                    assert_same(a, b, &format!("{path}[{i}]"));
+                }
+            }
+            (Value::Number(a), Value::Number(b)) => {
+                assert_eq!(a.as_f64(), b.as_f64(), "{path} differs");
+            }
+            (a, b) => assert_eq!(a, b, "{path} differs"),
+        }
+    }
+
+    fn round_trip(source: &str) -> HarFile {
+        let original: Value = serde_json::from_str(source).unwrap();
+        let har: HarFile = serde_json::from_str(source).unwrap();
+        let written = serde_json::to_value(&har).unwrap();
+        assert_same(&written, &original, "$");
+
+        let reloaded: HarFile = serde_json::from_value(written).unwrap();
+        assert_eq!(reloaded, har);
+        har
+    }
+
+    #[test]
+    fn chrome_export_round_trips() {
+        round_trip(CHROME);
+    }
+
+    #[test]
+    fn chrome_extensions_are_typed() {
+        let har = round_trip(CHROME);
+        let fetch = &har.log.entries[1];
+        assert_eq!(fetch.resource_type.as_deref(), Some("fetch"));
+        assert_eq!(fetch.initiator.as_ref().unwrap().kind, "script");
+        assert_eq!(fetch.response.transfer_size, Some(201));
+
+        let socket = &har.log.entries[3];
+        let messages = socket.web_socket_messages.as_ref().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].kind, "send");
+
+        let cookie = &har.log.entries[0].request.cookies[0];
+        assert_eq!(cookie.extensions["sameSite"], "Lax");
+        assert!(har.log.entries[0].response.extensions["_error"].is_null());
+    }
+
+    #[test]
+    fn firefox_export_round_trips() {
+        round_trip(FIREFOX);
+    }
+
+    #[test]
+    fn parses_har_date_times() {
+        assert_eq!(parse_date_time("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(
+            parse_date_time("2024-05-14T09:12:03.482Z"),
+            Some(1_715_677_923_482.0)
+        );
+        assert_eq!(
+            parse_date_time("2024-05-14T11:40:17.218+02:00"),
+            parse_date_time("2024-05-14T09:40:17.218Z")
+        );
+        for invalid in [
+            "2024-05-14 09:12:03Z",
+            "2024-05-14T09:12:03",
+            "2024-02-30T09:12:03Z",
+            "2024-05-14T09:12:03.Z",
+            "2024-05-14T25:00:00Z",
+        ] {
+            assert_eq!(parse_date_time(invalid), None, "{invalid}");
+        }
+
+        for date in [
+            "1970-01-01T00:00:00.000Z",
+            "2000-02-29T23:59:59.999Z",
+            "2024-05-14T09:12:03.482Z",
+        ] {
+            assert_eq!(format_date_time(parse_date_time(date).unwrap()), date);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! HAR 1.2 archives: the data model, a streaming loader for plain and
+//! compressed files, conformance checks and validation, summaries, page
+//! load metrics and per-host and per-endpoint statistics, redaction,
+//! request replay, singly, in batches, as flows or on the recorded schedule,
+//! a mock server answering with recorded responses, and a recording proxy
+//! that captures new archives from any client.
+//!
+//! This is the engine behind the HAR Analyser desktop app. It has no
+//! dependency on Tauri, so services and command-line tools can load and
+//! replay archives with exactly the same behaviour as the app.
+
+pub mod batch;
+pub mod ca;
+pub mod client;
+pub mod compression;
+pub mod conformance;
+pub mod cookies;
+pub mod diff;
+pub mod environment;
+pub mod error;
+pub mod flow;
+pub mod form;
+pub mod group;
+pub mod har;
+pub mod loader;
+pub mod mock;
+pub mod pages;
+pub mod proxy;
+pub mod redact;
+pub mod replay;
+pub mod schedule;
+pub mod summary;
+pub mod template;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod validate;
+
+pub use conformance::{Diagnostic, ParseMode, Severity};
+pub use error::{Error, ErrorKind, Result};
+pub use har::{HarEntry, HarFile};
+pub use loader::{HarSession, LoadOptions};
@@ -0,0 +1,829 @@
+//! Incremental HAR loading.
+//!
+//! Multi-gigabyte captures do not fit in memory as a single document, so the
+//! loader walks `log.entries` one element at a time and spools each response
+//! body to a temporary file. Only the entry metadata stays resident; bodies are
+//! read back from disk when the UI asks for them.
+
+use crate::compression::{self, Compression, Encoder};
+use crate::conformance::{self, Diagnostic, ParseMode, Severity};
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::{HarEntry, HarFile};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self as ser, SerializeMap, SerializeSeq};
+use serde::{Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::cell::Cell;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Number of entries handed to the UI per page while loading.
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadProgress {
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// The HAR to read from a zip archive holding several.
+    pub member: Option<String>,
+    pub page_size: usize,
+    pub mode: ParseMode,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            member: None,
+            page_size: DEFAULT_PAGE_SIZE,
+            mode: ParseMode::default(),
+        }
+    }
+}
+
+/// Streams a HAR document, calling `on_entry` with each element of
+/// `log.entries` and its position in the array as soon as it is parsed.
+/// Returns the rest of the document with an empty `entries` list. Breaking
+/// from `on_entry` aborts the parse.
+///
+/// In lenient and strict mode findings are appended to `diagnostics`.
+/// Entries that cannot be repaired are reported and skipped, so positions,
+/// like `Diagnostic::entry`, refer to the source document and may skip
+/// numbers.
+pub fn stream_har<R: Read>(
+    reader: R,
+    mode: ParseMode,
+    diagnostics: &mut Vec<Diagnostic>,
+    on_entry: impl FnMut(usize, HarEntry) -> ControlFlow<()>,
+) -> Result<HarFile> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut state = StreamState {
+        mode,
+        stopped: false,
+        json_path: None,
+        diagnostics,
+    };
+    let visitor = FileVisitor {
+        on_entry,
+        state: &mut state,
+    };
+    let result = deserializer
+        .deserialize_map(visitor)
+        .and_then(|har| deserializer.end().map(|_| har));
+    match result {
+        Ok(har) => Ok(har),
+        Err(_) if state.stopped => Err(Error::cancelled()),
+        Err(e) => Err(Error::json(&e, state.json_path.unwrap_or_default())),
+    }
+}
+
+/// Reads a whole HAR document into memory.
+pub fn read_har<R: Read>(reader: R, mode: ParseMode) -> Result<(HarFile, Vec<Diagnostic>)> {
+    let mut entries = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut har = stream_har(reader, mode, &mut diagnostics, |_, entry| {
+        entries.push(entry);
+        ControlFlow::Continue(())
+    })?;
+    har.log.entries = entries;
+    Ok((har, diagnostics))
+}
+
+/// Reads a whole HAR file into memory, decompressing it if needed; `member`
+/// picks the HAR inside a zip archive.
+pub fn read_har_file(
+    path: &Path,
+    member: Option<&str>,
+    mode: ParseMode,
+) -> Result<(HarFile, Vec<Diagnostic>)> {
+    compression::with_decoded(path, member, Rc::default(), |reader, _| {
+        read_har(reader, mode).map_err(|e| e.with_path(path))
+    })
+}
+
+/// Shared between the visitors so a failure can be traced back to where in
+/// the document it happened once serde has unwound.
+struct StreamState<'a> {
+    mode: ParseMode,
+    stopped: bool,
+    json_path: Option<String>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl StreamState<'_> {
+    /// Deserializes `value` into the model, recording the failing path.
+    fn deserialize_value<T, E>(&mut self, value: Value, prefix: &str) -> Result<T, E>
+    where
+        T: serde::de::DeserializeOwned,
+        E: de::Error,
+    {
+        serde_path_to_error::deserialize(value).map_err(|e| {
+            self.json_path = Some(join_path(prefix, e.path()));
+            E::custom(e.into_inner())
+        })
+    }
+}
+
+fn join_path(prefix: &str, path: &serde_path_to_error::Path) -> String {
+    match path.to_string().as_str() {
+        "." => prefix.to_string(),
+        rest if prefix.is_empty() || rest.starts_with('[') => format!("{prefix}{rest}"),
+        rest => format!("{prefix}.{rest}"),
+    }
+}
+
+struct FileVisitor<'a, 'b, F> {
+    on_entry: F,
+    state: &'a mut StreamState<'b>,
+}
+
+impl<'de, F> Visitor<'de> for FileVisitor<'_, '_, F>
+where
+    F: FnMut(usize, HarEntry) -> ControlFlow<()>,
+{
+    type Value = HarFile;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a HAR document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<HarFile, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = if key == "log" {
+                map.next_value_seed(LogSeed {
+                    on_entry: &mut self.on_entry,
+                    state: &mut *self.state,
+                })?
+            } else {
+                self.state.json_path = Some(key.clone());
+                map.next_value()?
+            };
+            fields.insert(key, value);
+        }
+        if self.state.mode != ParseMode::Standard {
+            let severity = match self.state.mode {
+                ParseMode::Strict => Severity::Error,
+                _ => Severity::Warning,
+            };
+            let log = fields.entry("log").or_insert(Value::Null);
+            if !log.is_object() {
+                self.state.diagnostics.push(Diagnostic {
+                    severity,
+                    entry: None,
+                    json_path: "log".to_string(),
+                    message: format!("expected an object, got {log}; using an empty log"),
+                });
+                *log = Value::Object(Map::new());
+            }
+            if let Value::Object(log) = log {
+                if !log.contains_key("entries") {
+                    self.state.diagnostics.push(Diagnostic {
+                        severity,
+                        entry: None,
+                        json_path: "log.entries".to_string(),
+                        message: "required field is missing; using []".to_string(),
+                    });
+                    log.insert("entries".to_string(), Value::Array(Vec::new()));
+                }
+            }
+        }
+        if let Some(log) = fields.get_mut("log") {
+            if self.state.mode != ParseMode::Standard {
+                conformance::check_log(log, self.state.mode, self.state.diagnostics);
+            }
+        }
+        self.state.deserialize_value(Value::Object(fields), "")
+    }
+}
+
+/// Deserializes the `log` object into a `Value` with `entries` left empty,
+/// so everything but the entries goes through the regular model afterwards.
+struct LogSeed<'a, 'b, F> {
+    on_entry: &'a mut F,
+    state: &'a mut StreamState<'b>,
+}
+
+impl<'de, F> DeserializeSeed<'de> for LogSeed<'_, '_, F>
+where
+    F: FnMut(usize, HarEntry) -> ControlFlow<()>,
+{
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for LogSeed<'_, '_, F>
+where
+    F: FnMut(usize, HarEntry) -> ControlFlow<()>,
+{
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a HAR log object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "entries" {
+                self.state.json_path = Some("log.entries".to_string());
+                map.next_value_seed(EntriesSeed {
+                    on_entry: &mut *self.on_entry,
+                    state: &mut *self.state,
+                })?;
+                fields.insert(key, Value::Array(Vec::new()));
+            } else {
+                self.state.json_path = Some(format!("log.{key}"));
+                let value = map.next_value()?;
+                fields.insert(key, value);
+            }
+        }
+        self.state.json_path = Some("log".to_string());
+        Ok(Value::Object(fields))
+    }
+}
+
+struct EntriesSeed<'a, 'b, F> {
+    on_entry: &'a mut F,
+    state: &'a mut StreamState<'b>,
+}
+
+impl<'de, F> DeserializeSeed<'de> for EntriesSeed<'_, '_, F>
+where
+    F: FnMut(usize, HarEntry) -> ControlFlow<()>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for EntriesSeed<'_, '_, F>
+where
+    F: FnMut(usize, HarEntry) -> ControlFlow<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of HAR entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        loop {
+            let seed = EntrySeed {
+                index,
+                state: &mut *self.state,
+            };
+            let Some(entry) = seq.next_element_seed(seed)? else {
+                return Ok(());
+            };
+            index += 1;
+            let Some(entry) = entry else {
+                continue;
+            };
+            if (self.on_entry)(index - 1, entry).is_break() {
+                self.state.stopped = true;
+                return Err(de::Error::custom("cancelled"));
+            }
+        }
+    }
+}
+
+/// Deserializes one entry, recording the path of any failure inside it.
+/// Yields `None` for entries that could not be repaired.
+struct EntrySeed<'a, 'b> {
+    index: usize,
+    state: &'a mut StreamState<'b>,
+}
+
+impl<'de> DeserializeSeed<'de> for EntrySeed<'_, '_> {
+    type Value = Option<HarEntry>;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Option<HarEntry>, D::Error> {
+        let prefix = format!("log.entries[{}]", self.index);
+        let mode = self.state.mode;
+        if mode == ParseMode::Standard {
+            return serde_path_to_error::deserialize(deserializer)
+                .map(Some)
+                .map_err(|e| {
+                    self.state.json_path = Some(join_path(&prefix, e.path()));
+                    e.into_inner()
+                });
+        }
+
+        let mut value: Value = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            self.state.json_path = Some(join_path(&prefix, e.path()));
+            e.into_inner()
+        })?;
+        if !value.is_object() {
+            self.state.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                entry: Some(self.index),
+                json_path: prefix,
+                message: "entry is not an object; skipped".to_string(),
+            });
+            return Ok(None);
+        }
+        conformance::check_entry(&mut value, self.index, mode, self.state.diagnostics);
+        match self
+            .state
+            .deserialize_value::<_, serde_json::Error>(value, &prefix)
+        {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => {
+                self.state.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    entry: Some(self.index),
+                    json_path: self.state.json_path.take().unwrap_or(prefix),
+                    message: format!("{e}; entry skipped"),
+                });
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BodySpan {
+    offset: u64,
+    len: u64,
+}
+
+/// Append-only spool of response bodies backed by an anonymous temp file,
+/// which the OS removes once the store is dropped.
+struct BodyStore {
+    file: Mutex<BufWriter<File>>,
+    spans: Vec<Option<BodySpan>>,
+    end: u64,
+}
+
+impl BodyStore {
+    fn new() -> io::Result<Self> {
+        Ok(BodyStore {
+            file: Mutex::new(BufWriter::new(tempfile::tempfile()?)),
+            spans: Vec::new(),
+            end: 0,
+        })
+    }
+
+    fn push(&mut self, body: Option<String>) -> io::Result<()> {
+        let span = self.write(body)?;
+        self.spans.push(span);
+        Ok(())
+    }
+
+    /// Points `index` at a new body. The old one stays in the file, which
+    /// only ever grows.
+    fn replace(&mut self, index: usize, body: Option<String>) -> io::Result<()> {
+        let span = self.write(body)?;
+        if let Some(slot) = self.spans.get_mut(index) {
+            *slot = span;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, body: Option<String>) -> io::Result<Option<BodySpan>> {
+        let Some(text) = body else {
+            return Ok(None);
+        };
+        let file = self.file.get_mut().unwrap();
+        file.write_all(text.as_bytes())?;
+        let span = BodySpan {
+            offset: self.end,
+            len: text.len() as u64,
+        };
+        self.end += span.len;
+        Ok(Some(span))
+    }
+
+    fn get(&self, index: usize) -> io::Result<Option<String>> {
+        let Some(span) = self.spans.get(index).copied().flatten() else {
+            return Ok(None);
+        };
+        let mut file = self.file.lock().unwrap();
+        file.flush()?;
+        let file = file.get_mut();
+        file.seek(SeekFrom::Start(span.offset))?;
+        let mut buf = vec![0; span.len as usize];
+        file.read_exact(&mut buf)?;
+        file.seek(SeekFrom::End(0))?;
+        String::from_utf8(buf)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn spool_error(err: io::Error) -> Error {
+    Error::new(ErrorKind::Io, format!("Body spool file: {err}"))
+}
+
+/// A loaded archive whose response bodies live on disk.
+pub struct HarSession {
+    /// The archive with `response.content.text` removed from every entry.
+    pub har: HarFile,
+    /// Repairs and spec violations found while loading.
+    pub diagnostics: Vec<Diagnostic>,
+    bodies: BodyStore,
+}
+
+impl HarSession {
+    /// A session over an archive built in memory, such as a new recording.
+    pub fn new(mut har: HarFile) -> Result<Self> {
+        let mut bodies = BodyStore::new().map_err(spool_error)?;
+        for entry in &mut har.log.entries {
+            bodies
+                .push(entry.response.content.text.take())
+                .map_err(spool_error)?;
+        }
+        Ok(HarSession {
+            har,
+            diagnostics: Vec::new(),
+            bodies,
+        })
+    }
+
+    /// Loads `path` incrementally, decompressing it if needed. `on_page`
+    /// receives each batch of `options.page_size` entries (bodies stripped)
+    /// together with the progress so far; setting `cancel` stops the load at
+    /// the next entry.
+    pub fn open(
+        path: &Path,
+        options: &LoadOptions,
+        cancel: &AtomicBool,
+        on_page: impl FnMut(usize, &[HarEntry], LoadProgress),
+    ) -> Result<Self> {
+        let count = Rc::new(Cell::new(0));
+        let member = options.member.as_deref();
+        compression::with_decoded(path, member, Rc::clone(&count), |reader, total_bytes| {
+            Self::read(reader, total_bytes, &count, options, cancel, on_page)
+        })
+    }
+
+    fn read(
+        reader: &mut dyn Read,
+        total_bytes: u64,
+        count: &Cell<u64>,
+        options: &LoadOptions,
+        cancel: &AtomicBool,
+        mut on_page: impl FnMut(usize, &[HarEntry], LoadProgress),
+    ) -> Result<Self> {
+        let page_size = options.page_size.max(1);
+        let mut diagnostics = Vec::new();
+        let mut bodies = BodyStore::new().map_err(spool_error)?;
+        let mut entries: Vec<HarEntry> = Vec::new();
+        let mut io_error = None;
+        let mut emit = |entries: &[HarEntry], from: usize| {
+            let progress = LoadProgress {
+                bytes_read: count.get(),
+                total_bytes,
+                entries: entries.len(),
+            };
+            on_page(from, &entries[from..], progress);
+        };
+
+        let result = stream_har(reader, options.mode, &mut diagnostics, |_, mut entry| {
+            if cancel.load(Ordering::Relaxed) {
+                return ControlFlow::Break(());
+            }
+            if let Err(e) = bodies.push(entry.response.content.text.take()) {
+                io_error = Some(e);
+                return ControlFlow::Break(());
+            }
+            entries.push(entry);
+            if entries.len().is_multiple_of(page_size) {
+                emit(&entries, entries.len() - page_size);
+            }
+            ControlFlow::Continue(())
+        });
+        if let Some(e) = io_error {
+            return Err(spool_error(e));
+        }
+        let mut har = result?;
+
+        let remainder = entries.len() % page_size;
+        if remainder != 0 || entries.is_empty() {
+            emit(&entries, entries.len() - remainder);
+        }
+
+        har.log.entries = entries;
+        Ok(HarSession {
+            har,
+            diagnostics,
+            bodies,
+        })
+    }
+
+    pub fn entries(&self, offset: usize, limit: usize) -> &[HarEntry] {
+        let entries = &self.har.log.entries;
+        let start = offset.min(entries.len());
+        let end = start.saturating_add(limit).min(entries.len());
+        &entries[start..end]
+    }
+
+    /// Appends `entry`, such as a replay, spooling its body like the loaded
+    /// ones. Returns the new entry's index.
+    pub fn push_entry(&mut self, mut entry: HarEntry) -> Result<usize> {
+        self.bodies
+            .push(entry.response.content.text.take())
+            .map_err(spool_error)?;
+        self.har.log.entries.push(entry);
+        Ok(self.har.log.entries.len() - 1)
+    }
+
+    /// Reads the spooled `response.content.text` of entry `index`.
+    pub fn response_body(&self, index: usize) -> Result<Option<String>> {
+        self.bodies.get(index).map_err(spool_error)
+    }
+
+    /// Drops the response body of entry `index`; it is left out on save.
+    pub fn clear_response_body(&mut self, index: usize) {
+        if let Some(span) = self.bodies.spans.get_mut(index) {
+            *span = None;
+        }
+    }
+
+    /// Replaces the spooled response body of entry `index`.
+    pub fn set_response_body(&mut self, index: usize, body: Option<String>) -> Result<()> {
+        self.bodies.replace(index, body).map_err(spool_error)
+    }
+
+    /// Writes the archive, bodies included, one entry at a time.
+    pub fn write_to<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, &SavedHar(self))
+    }
+
+    pub fn save(&self, path: &Path, compression: Compression) -> Result<()> {
+        let mut encoder = Encoder::create(path, compression)?;
+        self.write_to(&mut encoder).map_err(|e| {
+            if e.is_io() {
+                Error::io(e.into(), path)
+            } else {
+                Error::json(&e, "")
+            }
+        })?;
+        encoder.finish()
+    }
+}
+
+/// Serializes a session as a HAR document, reading each body back from the
+/// spool as its entry is written.
+struct SavedHar<'a>(&'a HarSession);
+
+impl Serialize for SavedHar<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let har = &self.0.har;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("log", &SavedLog(self.0))?;
+        for (key, value) in &har.extensions {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct SavedLog<'a>(&'a HarSession);
+
+impl Serialize for SavedLog<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let log = &self.0.har.log;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("version", &log.version)?;
+        map.serialize_entry("creator", &log.creator)?;
+        if let Some(browser) = &log.browser {
+            map.serialize_entry("browser", browser)?;
+        }
+        if let Some(pages) = &log.pages {
+            map.serialize_entry("pages", pages)?;
+        }
+        map.serialize_entry("entries", &SavedEntries(self.0))?;
+        if let Some(comment) = &log.comment {
+            map.serialize_entry("comment", comment)?;
+        }
+        for (key, value) in &log.extensions {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct SavedEntries<'a>(&'a HarSession);
+
+impl Serialize for SavedEntries<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = &self.0.har.log.entries;
+        let mut seq = serializer.serialize_seq(Some(entries.len()))?;
+        for (index, entry) in entries.iter().enumerate() {
+            let mut entry = entry.clone();
+            entry.response.content.text = self.0.bodies.get(index).map_err(ser::Error::custom)?;
+            seq.serialize_element(&entry)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CHROME: &str = include_str!("../tests/fixtures/chrome.har");
+
+    #[test]
+    fn streamed_entries_match_full_parse() {
+        let full: HarFile = serde_json::from_str(CHROME).unwrap();
+        let mut entries = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut har = stream_har(
+            CHROME.as_bytes(),
+            ParseMode::Standard,
+            &mut diagnostics,
+            |_, entry| {
+                entries.push(entry);
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+        assert!(diagnostics.is_empty());
+        assert!(har.log.entries.is_empty());
+        har.log.entries = entries;
+        assert_eq!(har, full);
+    }
+
+    #[test]
+    fn session_spools_bodies_and_pages_entries() {
+        let path = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(path.path(), CHROME).unwrap();
+        let full: HarFile = serde_json::from_str(CHROME).unwrap();
+
+        let mut pages = Vec::new();
+        let options = LoadOptions {
+            page_size: 3,
+            ..LoadOptions::default()
+        };
+        let session = HarSession::open(
+            path.path(),
+            &options,
+            &AtomicBool::new(false),
+            |offset, e, _| pages.push((offset, e.len())),
+        )
+        .unwrap();
+        assert_eq!(pages, vec![(0, 3), (3, 1)]);
+
+        for (i, entry) in full.log.entries.iter().enumerate() {
+            assert_eq!(session.har.log.entries[i].response.content.text, None);
+            assert_eq!(
+                session.response_body(i).unwrap(),
+                entry.response.content.text
+            );
+        }
+    }
+
+    #[test]
+    fn saved_archives_reload_in_every_format() {
+        let source = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(source.path(), CHROME).unwrap();
+        let full: HarFile = serde_json::from_str(CHROME).unwrap();
+        let options = LoadOptions::default();
+        let session = HarSession::open(
+            source.path(),
+            &options,
+            &AtomicBool::new(false),
+            |_, _, _| {},
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        for (name, compression) in [
+            ("out.har", Compression::None),
+            ("out.har.gz", Compression::Gzip),
+            ("out.har.zst", Compression::Zstd),
+            ("out.har.br", Compression::Brotli),
+            ("out.zip", Compression::Zip),
+        ] {
+            let path = dir.path().join(name);
+            session.save(&path, compression).unwrap();
+            let header = std::fs::read(&path).unwrap();
+            assert_eq!(Compression::detect(&header), compression, "{name}");
+
+            let reloaded =
+                HarSession::open(&path, &options, &AtomicBool::new(false), |_, _, _| {}).unwrap();
+            let mut har = reloaded.har.clone();
+            for (i, entry) in har.log.entries.iter_mut().enumerate() {
+                entry.response.content.text = reloaded.response_body(i).unwrap();
+            }
+            assert_eq!(har, full, "{name}");
+        }
+        assert_eq!(
+            compression::list_archive_members(&dir.path().join("out.zip")).unwrap(),
+            vec!["out.har".to_string()]
+        );
+    }
+
+    #[test]
+    fn malformed_entry_reports_its_json_path() {
+        let mut doc: Value = serde_json::from_str(CHROME).unwrap();
+        doc["log"]["entries"][2]["response"]["content"]["size"] = "68".into();
+        let text = serde_json::to_string_pretty(&doc).unwrap();
+
+        let err = read_har(text.as_bytes(), ParseMode::Standard).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Json);
+        assert_eq!(
+            err.context.json_path.as_deref(),
+            Some("log.entries[2].response.content.size")
+        );
+        assert!(err.context.line.is_some());
+
+        doc["log"]["entries"][2]["response"]["content"]["size"] = 68.into();
+        doc["log"]["creator"] = Value::Null;
+        let err = read_har(doc.to_string().as_bytes(), ParseMode::Standard).unwrap_err();
+        assert_eq!(err.context.json_path.as_deref(), Some("log.creator"));
+
+        doc["log"]["creator"] = json!({ "name": "test", "version": "1" });
+        for entries in [json!(5), json!({})] {
+            doc["log"]["entries"] = entries;
+            let err = read_har(doc.to_string().as_bytes(), ParseMode::Standard).unwrap_err();
+            assert_eq!(err.context.json_path.as_deref(), Some("log.entries"));
+        }
+    }
+
+    #[test]
+    fn cancelled_load_stops() {
+        let path = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(path.path(), CHROME).unwrap();
+        let options = LoadOptions {
+            page_size: 1,
+            ..LoadOptions::default()
+        };
+        let result = HarSession::open(path.path(), &options, &AtomicBool::new(true), |_, _, _| {});
+        assert_eq!(result.err().map(|e| e.kind), Some(ErrorKind::Cancelled));
+    }
+
+    #[test]
+    fn lenient_mode_repairs_and_reports() {
+        let mut doc: Value = serde_json::from_str(CHROME).unwrap();
+        doc["log"].as_object_mut().unwrap().remove("creator");
+        let entry = doc["log"]["entries"][1].as_object_mut().unwrap();
+        entry.remove("cache");
+        entry["response"]["content"]["size"] = "68".into();
+        entry["response"]["statusText"] = Value::Null;
+        entry["timings"]["wait"] = (-1).into();
+        doc["log"]["entries"][2] = "garbage".into();
+        let text = doc.to_string();
+
+        assert!(read_har(text.as_bytes(), ParseMode::Standard).is_err());
+        let (_, strict) = read_har(text.as_bytes(), ParseMode::Strict).unwrap();
+        assert!(strict.iter().any(|d| d.severity == Severity::Error));
+
+        let (har, diagnostics) = read_har(text.as_bytes(), ParseMode::Lenient).unwrap();
+        assert_eq!(har.log.entries.len(), 3);
+        assert_eq!(har.log.creator.name, "");
+        let fetch = &har.log.entries[1];
+        assert_eq!(fetch.response.content.size, 68);
+        assert_eq!(fetch.response.status_text, "");
+        assert_eq!(fetch.timings.wait, 0.0);
+
+        let paths: Vec<(Option<usize>, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.entry, d.json_path.as_str()))
+            .collect();
+        for expected in [
+            (None, "log.creator"),
+            (Some(1), "log.entries[1].cache"),
+            (Some(1), "log.entries[1].response.content.size"),
+            (Some(1), "log.entries[1].response.statusText"),
+            (Some(1), "log.entries[1].timings.wait"),
+            (Some(2), "log.entries[2]"),
+        ] {
+            assert!(paths.contains(&expected), "{expected:?} in {paths:?}");
+        }
+    }
+
+    #[test]
+    fn strict_mode_reports_spec_violations() {
+        let mut doc: Value = serde_json::from_str(CHROME).unwrap();
+        doc["log"]["entries"][0]["custom"] = true.into();
+        let (_, diagnostics) = read_har(doc.to_string().as_bytes(), ParseMode::Strict).unwrap();
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Warning && d.json_path == "log.entries[0].custom"));
+    }
+}
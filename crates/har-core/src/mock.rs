@@ -0,0 +1,430 @@
+//! A local HTTP server answering with recorded responses.
+//!
+//! Requests are matched to entries on method, path and query, with query
+//! parameters in any order, and optionally on body. The scheme and host
+//! are ignored, so a front end pointed at the server gets the responses
+//! its backend gave during the capture. When a request was recorded more
+//! than once, the [`Strategy`] picks which response to give.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::form::{self, Attachments};
+use crate::har::HarEntry;
+use hyper::header::{self, HeaderName, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Which recorded response answers a request captured several times.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Strategy {
+    /// Each in turn, in archive order, then the last one again.
+    #[default]
+    Sequential,
+    /// Always the first one.
+    First,
+    /// Always the last one.
+    Last,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockOptions {
+    pub strategy: Strategy,
+    /// Only answer requests whose body matches the recorded one; JSON
+    /// bodies are compared as values.
+    pub match_body: bool,
+    /// Allow cross-origin calls from any origin, answering preflight
+    /// requests and replacing the recorded CORS headers.
+    pub cors: bool,
+}
+
+/// A request the server received and how it was answered.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockRequest {
+    pub method: String,
+    /// Path and query as requested.
+    pub url: String,
+    /// Position in the archive of the entry that answered, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<usize>,
+    pub status: u16,
+}
+
+type Listener = Box<dyn Fn(&MockRequest) + Send + Sync>;
+
+struct Shared {
+    entries: Vec<(usize, HarEntry)>,
+    /// Positions in `entries` by [`route`], in archive order.
+    routes: HashMap<String, Vec<usize>>,
+    /// Responses given so far by the first candidate of each set, for
+    /// [`Strategy::Sequential`].
+    served: Mutex<HashMap<usize, usize>>,
+    unmatched: Mutex<Vec<MockRequest>>,
+    options: MockOptions,
+    on_request: Listener,
+}
+
+/// A running mock server. It stops when [`MockServer::stop`] is called or
+/// it is dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Listens on `addr`, port 0 picking a free one, and answers from
+    /// `entries`, each given with its position in the archive. Entries
+    /// without a final status, like failed requests and WebSocket
+    /// upgrades, are never served. `on_request` is called for every
+    /// request received.
+    pub async fn start(
+        addr: SocketAddr,
+        entries: Vec<(usize, HarEntry)>,
+        options: MockOptions,
+        on_request: impl Fn(&MockRequest) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let mut routes: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, (_, entry)) in entries.iter().enumerate() {
+            let Ok(uri) = entry.request.url.parse::<Uri>() else {
+                continue;
+            };
+            if (200..600).contains(&entry.response.status) {
+                routes
+                    .entry(route(&entry.request.method, &uri))
+                    .or_default()
+                    .push(position);
+            }
+        }
+        let shared = Arc::new(Shared {
+            entries,
+            routes,
+            served: Mutex::default(),
+            unmatched: Mutex::default(),
+            options,
+            on_request: Box::new(on_request),
+        });
+
+        let listener = std::net::TcpListener::bind(addr)
+            .and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .map_err(|e| Error::new(ErrorKind::Io, format!("Could not listen on {addr}: {e}")))?;
+        let addr = listener.local_addr().map_err(Error::internal)?;
+        let service = {
+            let shared = Arc::clone(&shared);
+            make_service_fn(move |_| {
+                let shared = Arc::clone(&shared);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        handle(Arc::clone(&shared), request)
+                    }))
+                }
+            })
+        };
+        let (shutdown, stopped) = oneshot::channel();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| Error::new(ErrorKind::Io, format!("Could not listen on {addr}: {e}")))?
+            .serve(service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        let task = tokio::spawn(async {
+            let _ = server.await;
+        });
+        Ok(MockServer {
+            addr,
+            shared,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+
+    /// The address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Requests no recorded entry matched, oldest first.
+    pub fn unmatched(&self) -> Vec<MockRequest> {
+        self.shared.unmatched.lock().unwrap().clone()
+    }
+
+    /// Stops accepting connections and waits for open ones to finish.
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// Method, path and sorted query: what requests are matched on.
+fn route(method: &str, uri: &Uri) -> String {
+    let mut query: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .collect();
+    query.sort_unstable();
+    format!(
+        "{} {}?{}",
+        method.to_ascii_uppercase(),
+        uri.path(),
+        query.join("&")
+    )
+}
+
+impl Shared {
+    /// The position of the entry to answer with, if one matches.
+    fn find(&self, method: &Method, uri: &Uri, body: &[u8]) -> Option<usize> {
+        let candidates = self.routes.get(&route(method.as_str(), uri))?;
+        let candidates: Vec<usize> = if self.options.match_body {
+            candidates
+                .iter()
+                .copied()
+                .filter(|&position| same_body(&self.entries[position].1, body))
+                .collect()
+        } else {
+            candidates.clone()
+        };
+        let (&first, &last) = (candidates.first()?, candidates.last()?);
+        Some(match self.options.strategy {
+            Strategy::First => first,
+            Strategy::Last => last,
+            Strategy::Sequential => {
+                let mut served = self.served.lock().unwrap();
+                let count = served.entry(first).or_default();
+                let position = candidates[(*count).min(candidates.len() - 1)];
+                *count += 1;
+                position
+            }
+        })
+    }
+
+    fn respond(&self, position: usize) -> Response<Body> {
+        let recorded = &self.entries[position].1.response;
+        let status = u16::try_from(recorded.status)
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::OK);
+        let mut response = Response::builder().status(status);
+        for h in &recorded.headers {
+            let lower = h.name.to_ascii_lowercase();
+            // The body is served decoded and whole, and the connection is
+            // the server's own.
+            let skip = lower.starts_with(':')
+                || matches!(
+                    lower.as_str(),
+                    "content-encoding"
+                        | "content-length"
+                        | "transfer-encoding"
+                        | "connection"
+                        | "keep-alive"
+                )
+                || (self.options.cors && lower.starts_with("access-control-"));
+            if let (false, Ok(name), Ok(value)) = (
+                skip,
+                HeaderName::from_bytes(h.name.as_bytes()),
+                HeaderValue::from_str(&h.value),
+            ) {
+                response = response.header(name, value);
+            }
+        }
+        let body = match status {
+            StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED => Vec::new(),
+            _ => recorded.content.bytes().unwrap_or_default(),
+        };
+        response
+            .body(Body::from(body))
+            .unwrap_or_else(|_| Response::new(Body::empty()))
+    }
+}
+
+/// Whether `body` matches the recorded request body.
+fn same_body(entry: &HarEntry, body: &[u8]) -> bool {
+    let Some(post) = &entry.request.post_data else {
+        return body.is_empty();
+    };
+    let Ok(recorded) = form::body(post, &Attachments::new()) else {
+        return false;
+    };
+    match (
+        serde_json::from_slice::<serde_json::Value>(&recorded.bytes),
+        serde_json::from_slice::<serde_json::Value>(body),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => recorded.bytes == body,
+    }
+}
+
+async fn handle(shared: Arc<Shared>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let url = parts
+        .uri
+        .path_and_query()
+        .map_or("/", |p| p.as_str())
+        .to_string();
+    let found = shared.find(&parts.method, &parts.uri, &body);
+    let preflight = parts.method == Method::OPTIONS
+        && parts
+            .headers
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    let mut response = match found {
+        Some(position) => shared.respond(position),
+        None if shared.options.cors && preflight => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap(),
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(format!(
+                "No recorded response for {} {url}\n",
+                parts.method
+            )))
+            .unwrap(),
+    };
+    if shared.options.cors {
+        let headers = response.headers_mut();
+        match parts.headers.get(header::ORIGIN) {
+            Some(origin) => {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                    HeaderValue::from_static("true"),
+                );
+                headers.append(header::VARY, HeaderValue::from_static("Origin"));
+            }
+            None => {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_ORIGIN,
+                    HeaderValue::from_static("*"),
+                );
+            }
+        }
+        if let Some(method) = parts.headers.get(header::ACCESS_CONTROL_REQUEST_METHOD) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, method.clone());
+        }
+        if let Some(names) = parts.headers.get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, names.clone());
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            HeaderValue::from_static("*"),
+        );
+    }
+
+    let served = MockRequest {
+        method: parts.method.to_string(),
+        url,
+        entry: found.map(|position| shared.entries[position].0),
+        status: response.status().as_u16(),
+    };
+    if found.is_none() && response.status() == StatusCode::NOT_FOUND {
+        shared.unmatched.lock().unwrap().push(served.clone());
+    }
+    (shared.on_request)(&served);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    async fn call(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(format!("http://{addr}{path}"))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = hyper::Client::new().request(request).await.unwrap();
+        let status = response.status().as_u16();
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn serves_recorded_responses_in_turn() {
+        let entries = vec![
+            (
+                0,
+                test_support::entry("GET", "https://api.example.com/items?b=2&a=1")
+                    .response_body("text/plain", "one")
+                    .build(),
+            ),
+            (
+                1,
+                // The body is recorded decoded, so the encoding is not sent.
+                test_support::entry("GET", "https://api.example.com/items?a=1&b=2")
+                    .response_header("Content-Encoding", "gzip")
+                    .response_body("text/plain", "two")
+                    .build(),
+            ),
+            (
+                2,
+                test_support::entry("POST", "https://api.example.com/orders")
+                    .request_body("application/json", r#"{"id":1}"#)
+                    .response_body("text/plain", "ok")
+                    .build(),
+            ),
+        ];
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let server = MockServer::start(
+            ([127, 0, 0, 1], 0).into(),
+            entries,
+            MockOptions {
+                match_body: true,
+                ..MockOptions::default()
+            },
+            move |request| log.lock().unwrap().push(request.entry),
+        )
+        .await
+        .unwrap();
+        let addr = server.addr();
+
+        for expected in ["one", "two", "two"] {
+            let (status, body) = call(addr, "GET", "/items?a=1&b=2", "").await;
+            assert_eq!((status, body.as_str()), (200, expected));
+        }
+        assert_eq!(
+            call(addr, "POST", "/orders", r#"{ "id": 1 }"#).await,
+            (200, "ok".to_string())
+        );
+        assert_eq!(call(addr, "POST", "/orders", r#"{"id":2}"#).await.0, 404);
+        assert_eq!(call(addr, "GET", "/missing", "").await.0, 404);
+
+        let unmatched: Vec<String> = server.unmatched().into_iter().map(|r| r.url).collect();
+        assert_eq!(unmatched, ["/orders", "/missing"]);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [Some(0), Some(1), Some(1), Some(2), None, None]
+        );
+        server.stop().await;
+    }
+}
@@ -0,0 +1,364 @@
+//! Page loads: which entries belong to each page and how the page loaded.
+//!
+//! Entries belong to the page their `pageref` names. Exporters that leave
+//! `pageref` out, or drop it for some requests, are handled by assigning
+//! each such entry to the last page that had started by the time it did.
+//! Durations are in milliseconds from the page's `startedDateTime`, like
+//! its `pageTimings`.
+
+use crate::har::{parse_date_time, HarEntry, HarFile, HarPage};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageMetrics {
+    pub id: String,
+    pub title: String,
+    pub started_date_time: String,
+    /// `pageTimings.onContentLoad`, if the exporter recorded it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_content_load: Option<f64>,
+    /// `pageTimings.onLoad`, if the exporter recorded it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_load: Option<f64>,
+    pub requests: usize,
+    /// Decoded response body bytes.
+    pub content_bytes: i64,
+    /// Response body bytes on the wire, where the exporter recorded them.
+    pub transfer_bytes: i64,
+    /// Position in the archive of the page's HTML document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<usize>,
+    /// From the page starting to the first byte of its document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_ttfb: Option<f64>,
+    /// From the page starting to its last request finishing.
+    pub finished: f64,
+    pub longest_chain: Chain,
+    /// Positions of the page's entries in the archive.
+    pub entries: Vec<usize>,
+}
+
+/// Requests each started by the one before: the document, a stylesheet it
+/// links, a font the stylesheet loads, and so on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chain {
+    /// Positions in the archive, first request first.
+    pub entries: Vec<usize>,
+    /// From the first request starting to the last one finishing.
+    pub time: f64,
+}
+
+/// The position in `har.log.pages` of each entry's page, if it has one.
+pub fn assign_entries(har: &HarFile) -> Vec<Option<usize>> {
+    let pages = har.log.pages.as_deref().unwrap_or_default();
+    let ids: HashMap<&str, usize> = pages
+        .iter()
+        .enumerate()
+        .map(|(position, page)| (page.id.as_str(), position))
+        .collect();
+    let mut starts: Vec<(f64, usize)> = pages
+        .iter()
+        .enumerate()
+        .filter_map(|(position, page)| Some((page_start(page)?, position)))
+        .collect();
+    starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    har.log
+        .entries
+        .iter()
+        .map(|entry| {
+            if let Some(&position) = entry.pageref.as_deref().and_then(|id| ids.get(id)) {
+                return Some(position);
+            }
+            let started = entry.started_millis()?;
+            let count = starts.partition_point(|(start, _)| *start <= started);
+            count.checked_sub(1).map(|last| starts[last].1)
+        })
+        .collect()
+}
+
+/// Sets the `pageref` of entries that have none, or name no page, by
+/// [`assign_entries`]. Returns how many entries changed.
+pub fn assign_pages(har: &mut HarFile) -> usize {
+    let assigned = assign_entries(har);
+    let pages = har.log.pages.as_deref().unwrap_or_default();
+    let mut changed = 0;
+    for (entry, position) in har.log.entries.iter_mut().zip(assigned) {
+        let Some(page) = position.map(|p| &pages[p]) else {
+            continue;
+        };
+        if entry.pageref.as_deref() != Some(page.id.as_str()) {
+            entry.pageref = Some(page.id.clone());
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Metrics for every page, in archive order.
+pub fn page_metrics(har: &HarFile) -> Vec<PageMetrics> {
+    let pages = har.log.pages.as_deref().unwrap_or_default();
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); pages.len()];
+    for (index, position) in assign_entries(har).into_iter().enumerate() {
+        if let Some(position) = position {
+            members[position].push(index);
+        }
+    }
+    pages
+        .iter()
+        .zip(members)
+        .map(|(page, entries)| metrics(page, &har.log.entries, entries))
+        .collect()
+}
+
+fn metrics(page: &HarPage, all: &[HarEntry], entries: Vec<usize>) -> PageMetrics {
+    let start_of = |index: usize| all[index].started_millis();
+    let start = page_start(page)
+        .or_else(|| entries.iter().filter_map(|&i| start_of(i)).reduce(f64::min))
+        .unwrap_or_default();
+    let offset = |index: usize| start_of(index).map_or(0.0, |s| s - start);
+
+    let document = entries
+        .iter()
+        .copied()
+        .find(|&i| {
+            all[i]
+                .resource_type
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case("document"))
+        })
+        .or_else(|| {
+            entries.iter().copied().find(|&i| {
+                all[i]
+                    .response
+                    .content
+                    .mime_type
+                    .to_ascii_lowercase()
+                    .starts_with("text/html")
+            })
+        })
+        .or_else(|| entries.first().copied());
+    let document_ttfb = document.map(|i| {
+        let t = &all[i].timings;
+        let phase = |value: Option<f64>| value.unwrap_or(0.0).max(0.0);
+        // `ssl` is part of `connect`.
+        offset(i)
+            + phase(t.blocked)
+            + phase(t.dns)
+            + phase(t.connect)
+            + phase(Some(t.send))
+            + phase(Some(t.wait))
+    });
+    let page_timing = |value: Option<f64>| value.filter(|v| *v >= 0.0);
+
+    PageMetrics {
+        id: page.id.clone(),
+        title: page.title.clone(),
+        started_date_time: page.started_date_time.clone(),
+        on_content_load: page_timing(page.page_timings.on_content_load),
+        on_load: page_timing(page.page_timings.on_load),
+        requests: entries.len(),
+        content_bytes: entries
+            .iter()
+            .map(|&i| all[i].response.content.size.max(0))
+            .sum(),
+        transfer_bytes: entries
+            .iter()
+            .map(|&i| all[i].response.body_size.max(0))
+            .sum(),
+        document,
+        document_ttfb,
+        finished: entries
+            .iter()
+            .map(|&i| offset(i) + all[i].time.max(0.0))
+            .fold(0.0, f64::max),
+        longest_chain: longest_chain(all, &entries),
+        entries,
+    }
+}
+
+/// The chain of `entries` taking longest from its first request starting
+/// to its last finishing. A request's parent is the latest earlier request
+/// for the URL its initiator names, or failing that its `Referer`.
+fn longest_chain(all: &[HarEntry], entries: &[usize]) -> Chain {
+    let mut order: Vec<usize> = entries.to_vec();
+    order.sort_by(|&a, &b| {
+        let start = |i: usize| all[i].started_millis().unwrap_or_default();
+        start(a).total_cmp(&start(b)).then(a.cmp(&b))
+    });
+
+    // For each request in `order`: its parent's position in `order` and
+    // when its chain's first request started.
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(order.len());
+    let mut roots: Vec<f64> = Vec::with_capacity(order.len());
+    let mut latest: HashMap<&str, usize> = HashMap::new();
+    let mut best: Option<(f64, usize)> = None;
+    for (position, &index) in order.iter().enumerate() {
+        let entry = &all[index];
+        let started = entry.started_millis().unwrap_or_default();
+        let parent =
+            initiator_url(entry).and_then(|url| latest.get(without_fragment(url)).copied());
+        let root = parent.map_or(started, |p| roots[p]);
+        parents.push(parent);
+        roots.push(root);
+        latest.insert(without_fragment(&entry.request.url), position);
+
+        let time = started + entry.time.max(0.0) - root;
+        if best.is_none_or(|(longest, _)| time > longest) {
+            best = Some((time, position));
+        }
+    }
+
+    let Some((time, mut position)) = best else {
+        return Chain::default();
+    };
+    let mut chain = vec![order[position]];
+    while let Some(parent) = parents[position] {
+        chain.push(order[parent]);
+        position = parent;
+    }
+    chain.reverse();
+    Chain {
+        entries: chain,
+        time,
+    }
+}
+
+/// The URL of the resource that caused `entry`: Chrome's initiator, the
+/// top frame of a script initiator's stack, or the `Referer` header.
+fn initiator_url(entry: &HarEntry) -> Option<&str> {
+    let initiator = entry.initiator.as_ref();
+    initiator
+        .and_then(|i| i.url.as_deref())
+        .or_else(|| {
+            initiator
+                .and_then(|i| i.stack.as_ref())
+                .and_then(|stack| stack.pointer("/callFrames/0/url"))
+                .and_then(|url| url.as_str())
+        })
+        .filter(|url| !url.is_empty())
+        .or_else(|| {
+            entry
+                .request
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("referer"))
+                .map(|h| h.value.as_str())
+        })
+}
+
+fn without_fragment(url: &str) -> &str {
+    url.split_once('#').map_or(url, |(url, _)| url)
+}
+
+fn page_start(page: &HarPage) -> Option<f64> {
+    parse_date_time(&page.started_date_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use serde_json::json;
+
+    fn har() -> HarFile {
+        let page = |id: &str, started: &str| {
+            serde_json::from_value(json!({
+                "startedDateTime": started,
+                "id": id,
+                "title": format!("https://example.com/{id}"),
+                "pageTimings": { "onContentLoad": 300, "onLoad": -1 }
+            }))
+            .unwrap()
+        };
+        let mut har = HarFile::new("test", "1");
+        har.log.pages = Some(vec![
+            page("page_1", "2024-05-14T09:00:00.000Z"),
+            page("page_2", "2024-05-14T09:01:00.000Z"),
+        ]);
+        let root = "https://example.com/";
+        har.log.entries = vec![
+            test_support::entry("GET", root)
+                .pageref("page_1")
+                .started("2024-05-14T09:00:00.010Z")
+                .mime_type("text/html")
+                .time(100.0)
+                .timing("blocked", 5.0)
+                .timing("send", 1.0)
+                .timing("wait", 20.0)
+                .set("/response/content/size", 1000)
+                .set("/response/bodySize", 400)
+                .build(),
+            test_support::entry("GET", "https://example.com/app.css")
+                .pageref("page_1")
+                .started("2024-05-14T09:00:00.120Z")
+                .time(50.0)
+                .initiator(root)
+                .build(),
+            // No pageref, but started while page_1 was loading.
+            test_support::entry("GET", "https://example.com/font.woff2")
+                .started("2024-05-14T09:00:00.200Z")
+                .time(150.0)
+                .initiator("https://example.com/app.css")
+                .build(),
+            test_support::entry("GET", "https://example.com/big.jpg")
+                .pageref("page_1")
+                .started("2024-05-14T09:00:00.130Z")
+                .time(200.0)
+                .initiator(root)
+                .set("/response/content/size", 3000)
+                .set("/response/bodySize", 1200)
+                .build(),
+            test_support::entry("GET", "https://example.com/next")
+                .started("2024-05-14T09:01:00.000Z")
+                .mime_type("text/html")
+                .time(60.0)
+                .timing("blocked", 5.0)
+                .timing("send", 1.0)
+                .timing("wait", 20.0)
+                .build(),
+            test_support::entry("GET", "https://example.com/early")
+                .started("2024-05-14T08:59:00.000Z")
+                .mime_type("text/html")
+                .time(60.0)
+                .build(),
+        ];
+        har
+    }
+
+    #[test]
+    fn assigns_entries_by_pageref_and_start() {
+        let mut har = har();
+        assert_eq!(
+            assign_entries(&har),
+            [Some(0), Some(0), Some(0), Some(0), Some(1), None]
+        );
+        assert_eq!(assign_pages(&mut har), 2);
+        assert_eq!(har.log.entries[4].pageref.as_deref(), Some("page_2"));
+        assert_eq!(har.log.entries[5].pageref, None);
+    }
+
+    #[test]
+    fn measures_pages() {
+        let pages = page_metrics(&har());
+        let first = &pages[0];
+        assert_eq!(first.entries, [0, 1, 2, 3]);
+        assert_eq!(first.requests, 4);
+        assert_eq!((first.content_bytes, first.transfer_bytes), (4000, 1600));
+        assert_eq!((first.on_content_load, first.on_load), (Some(300.0), None));
+        assert_eq!(first.document, Some(0));
+        // 10 ms in, then blocked 5, send 1 and wait 20.
+        assert_eq!(first.document_ttfb, Some(36.0));
+        assert_eq!(first.finished, 350.0);
+        // The font, loaded by the stylesheet, ends 340 ms after the
+        // document started; the image the document loads ends at 320 ms.
+        assert_eq!(first.longest_chain.entries, [0, 1, 2]);
+        assert_eq!(first.longest_chain.time, 340.0);
+
+        assert_eq!(pages[1].entries, [4]);
+        assert_eq!(pages[1].document_ttfb, Some(26.0));
+    }
+}
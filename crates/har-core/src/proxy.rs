@@ -0,0 +1,485 @@
+//! A forward proxy recording the traffic sent through it.
+//!
+//! Requests from any client pointed at the proxy are forwarded with a
+//! replay [`Client`] and recorded as [`HarEntry`] values timed phase by
+//! phase, like replays. HTTPS is intercepted: each `CONNECT` tunnel is
+//! answered with a certificate for its host signed by the local
+//! [`CertificateAuthority`], which clients must trust. Tunnels are expected
+//! to carry HTTP/1.1 over TLS; WebSocket upgrades are not forwarded.
+
+use crate::ca::CertificateAuthority;
+use crate::client::Client;
+use crate::error::{Error, ErrorKind, Result};
+use crate::har::{Extensions, HarEntry, HarPostData, HarRequest};
+use crate::replay;
+use hyper::header::{self, HeaderMap};
+use hyper::http::uri::Authority;
+use hyper::server::conn::Http;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::upgrade::Upgraded;
+use hyper::{Body, Method, Request, Response, Server, StatusCode, Uri};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Headers about the hop between the client and the proxy, not passed on
+/// in either direction.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+type Listener = Box<dyn Fn(&HarEntry) + Send + Sync>;
+
+struct Shared {
+    ca: Arc<CertificateAuthority>,
+    client: Client,
+    entries: Mutex<Vec<HarEntry>>,
+    on_entry: Listener,
+}
+
+/// A running recording proxy. It stops when [`RecordingProxy::stop`] is
+/// called or it is dropped.
+pub struct RecordingProxy {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl RecordingProxy {
+    /// Listens on `addr`, port 0 picking a free one, and forwards requests
+    /// with `client`, whose timeouts, upstream proxy and certificate
+    /// settings apply. `on_entry` is called with each exchange recorded;
+    /// requests that could not be forwarded are answered with 502 and not
+    /// recorded.
+    pub async fn start(
+        addr: SocketAddr,
+        ca: Arc<CertificateAuthority>,
+        client: Client,
+        on_entry: impl Fn(&HarEntry) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let shared = Arc::new(Shared {
+            ca,
+            client,
+            entries: Mutex::default(),
+            on_entry: Box::new(on_entry),
+        });
+
+        let listener = std::net::TcpListener::bind(addr)
+            .and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .map_err(|e| Error::new(ErrorKind::Io, format!("Could not listen on {addr}: {e}")))?;
+        let addr = listener.local_addr().map_err(Error::internal)?;
+        let service = {
+            let shared = Arc::clone(&shared);
+            make_service_fn(move |_| {
+                let shared = Arc::clone(&shared);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        handle(Arc::clone(&shared), request)
+                    }))
+                }
+            })
+        };
+        let (shutdown, stopped) = oneshot::channel();
+        let server = Server::from_tcp(listener)
+            .map_err(|e| Error::new(ErrorKind::Io, format!("Could not listen on {addr}: {e}")))?
+            .serve(service)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        let task = tokio::spawn(async {
+            let _ = server.await;
+        });
+        Ok(RecordingProxy {
+            addr,
+            shared,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+
+    /// The address the proxy listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The exchanges recorded so far, oldest first.
+    pub fn entries(&self) -> Vec<HarEntry> {
+        self.shared.entries.lock().unwrap().clone()
+    }
+
+    /// Stops accepting connections and waits for open ones to finish.
+    /// Intercepted tunnels are left to close on their own.
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for RecordingProxy {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(shared: Arc<Shared>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() == Method::CONNECT {
+        let Some(authority) = request.uri().authority().cloned() else {
+            return Ok(plain(StatusCode::BAD_REQUEST, "CONNECT needs a host:port"));
+        };
+        tokio::spawn(async move {
+            if let Ok(upgraded) = hyper::upgrade::on(request).await {
+                // A client that does not trust the authority hangs up
+                // during the handshake; there is nothing to record.
+                let _ = intercept(shared, authority, upgraded).await;
+            }
+        });
+        return Ok(Response::new(Body::empty()));
+    }
+    if request.uri().scheme().is_none() {
+        return Ok(plain(
+            StatusCode::BAD_REQUEST,
+            "This is a recording proxy: send requests with an absolute URL",
+        ));
+    }
+    let uri = request.uri().clone();
+    Ok(forward(&shared, uri, request).await)
+}
+
+/// Terminates TLS on a `CONNECT` tunnel to `authority` and forwards the
+/// requests sent through it.
+async fn intercept(shared: Arc<Shared>, authority: Authority, upgraded: Upgraded) -> Result<()> {
+    let host = authority
+        .host()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let (chain, key) = shared.ca.leaf(host)?;
+    let tls_error = |e: native_tls::Error| Error::replay(ErrorKind::Tls, &e);
+    let identity =
+        native_tls::Identity::from_pkcs8(chain.as_bytes(), key.as_bytes()).map_err(tls_error)?;
+    let acceptor = native_tls::TlsAcceptor::new(identity).map_err(tls_error)?;
+    let stream = tokio_native_tls::TlsAcceptor::from(acceptor)
+        .accept(upgraded)
+        .await
+        .map_err(tls_error)?;
+
+    let service = service_fn(move |request: Request<Body>| {
+        let shared = Arc::clone(&shared);
+        let authority = authority.clone();
+        async move {
+            let response = match tunnelled_uri(&authority, request.uri()) {
+                Some(uri) => forward(&shared, uri, request).await,
+                None => plain(StatusCode::BAD_REQUEST, "Invalid request target"),
+            };
+            Ok::<_, Infallible>(response)
+        }
+    });
+    Http::new()
+        .http1_only(true)
+        .serve_connection(stream, service)
+        .await
+        .map_err(|e| Error::replay(ErrorKind::Connection, &e))
+}
+
+/// The `https` URL of a request sent through a tunnel to `authority`.
+fn tunnelled_uri(authority: &Authority, target: &Uri) -> Option<Uri> {
+    let path = target.path_and_query().map_or("/", |p| p.as_str());
+    let host = match authority.port_u16() {
+        Some(443) => authority.host(),
+        _ => authority.as_str(),
+    };
+    format!("https://{host}{path}").parse().ok()
+}
+
+/// Sends `request` on to `uri`, records the exchange and returns the
+/// response as received.
+async fn forward(shared: &Shared, uri: Uri, request: Request<Body>) -> Response<Body> {
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => {
+            return plain(
+                StatusCode::BAD_REQUEST,
+                &format!("Could not read the request body: {e}"),
+            )
+        }
+    };
+    let mut headers = parts.headers;
+    strip_hop_by_hop(&mut headers);
+
+    let sent = har_request(&parts.method, &uri, &headers, &body);
+    let exchange = shared.client.send(&parts.method, &uri, &headers, &body);
+    let result = match shared.client.config().timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), exchange)
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::new(
+                    ErrorKind::Timeout,
+                    format!("The server took longer than {ms} ms"),
+                ))
+            }),
+        None => exchange.await,
+    };
+    let exchange = match result {
+        Ok(exchange) => exchange,
+        Err(e) => return plain(StatusCode::BAD_GATEWAY, &format!("{e}")),
+    };
+
+    // The body goes back as it came, still in its content encoding.
+    let mut response = Response::new(Body::from(exchange.body.clone()));
+    *response.status_mut() = exchange.response.status;
+    *response.headers_mut() = exchange.response.headers.clone();
+    strip_hop_by_hop(response.headers_mut());
+
+    let entry = replay::entry(&sent, body.len(), exchange);
+    (shared.on_entry)(&entry);
+    shared.entries.lock().unwrap().push(entry);
+    response
+}
+
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    for name in HOP_BY_HOP {
+        headers.remove(*name);
+    }
+}
+
+/// The request as forwarded; [`replay::entry`] fills in the headers,
+/// cookies and HTTP version actually sent upstream.
+fn har_request(method: &Method, uri: &Uri, headers: &HeaderMap, body: &[u8]) -> HarRequest {
+    let post_data = (!body.is_empty()).then(|| {
+        let mut post = HarPostData {
+            mime_type: headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            text: None,
+            params: None,
+            comment: None,
+            extensions: Extensions::new(),
+        };
+        post.set_bytes(body);
+        post
+    });
+    HarRequest {
+        method: method.to_string(),
+        url: uri.to_string(),
+        http_version: String::new(),
+        cookies: Vec::new(),
+        headers: Vec::new(),
+        query_string: replay::query_string(uri),
+        post_data,
+        headers_size: -1,
+        body_size: body.len() as i64,
+        comment: None,
+        extensions: Extensions::new(),
+    }
+}
+
+fn plain(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(format!("{message}\n")))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, Reply};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Answers every request with a small JSON body, one connection each.
+    async fn serve() -> SocketAddr {
+        test_support::serve(|_| {
+            Reply::new(
+                201,
+                &[
+                    ("Content-Type", "application/json"),
+                    ("Connection", "close"),
+                ],
+                r#"{"ok":true}"#,
+            )
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn forwards_and_records_plain_http() {
+        let upstream = serve().await;
+        let dir = tempfile::tempdir().unwrap();
+        let ca = Arc::new(CertificateAuthority::load_or_create(dir.path()).unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let proxy = {
+            let seen = Arc::clone(&seen);
+            RecordingProxy::start(
+                ([127, 0, 0, 1], 0).into(),
+                ca,
+                Client::new().unwrap(),
+                move |entry| seen.lock().unwrap().push(entry.request.url.clone()),
+            )
+            .await
+            .unwrap()
+        };
+
+        let mut socket = TcpStream::connect(proxy.addr()).await.unwrap();
+        let request = format!(
+            "POST http://{upstream}/items?page=2 HTTP/1.1\r\nHost: {upstream}\r\n\
+             Proxy-Connection: keep-alive\r\nContent-Type: text/plain\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello"
+        );
+        socket.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 201"), "{response}");
+        assert!(response.ends_with(r#"{"ok":true}"#), "{response}");
+
+        let entries = proxy.entries();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        let url = format!("http://{upstream}/items?page=2");
+        assert_eq!(entry.request.url, url);
+        assert_eq!(entry.request.method, "POST");
+        assert_eq!(entry.request.query_string[0].value, "2");
+        assert_eq!(entry.request.body_size, 5);
+        let post = entry.request.post_data.as_ref().unwrap();
+        assert_eq!(
+            (post.mime_type.as_str(), post.text.as_deref()),
+            ("text/plain", Some("hello"))
+        );
+        assert!(!entry
+            .request
+            .headers
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case("proxy-connection")));
+        assert_eq!(entry.response.status, 201);
+        assert_eq!(
+            entry.response.content.text.as_deref(),
+            Some(r#"{"ok":true}"#)
+        );
+        assert!(entry.timings.connect.unwrap() >= 0.0);
+        assert_eq!(*seen.lock().unwrap(), [url]);
+
+        proxy.stop().await;
+    }
+
+    #[tokio::test]
+    async fn records_binary_uploads_as_base64() {
+        let upstream = serve().await;
+        let dir = tempfile::tempdir().unwrap();
+        let ca = Arc::new(CertificateAuthority::load_or_create(dir.path()).unwrap());
+        let proxy = RecordingProxy::start(
+            ([127, 0, 0, 1], 0).into(),
+            ca,
+            Client::new().unwrap(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        let upload = [0x89, b'P', b'N', b'G', 0xff, 0x00];
+        let mut socket = TcpStream::connect(proxy.addr()).await.unwrap();
+        let head = format!(
+            "PUT http://{upstream}/avatar HTTP/1.1\r\nHost: {upstream}\r\n\
+             Content-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            upload.len()
+        );
+        socket.write_all(head.as_bytes()).await.unwrap();
+        socket.write_all(&upload).await.unwrap();
+        let mut response = Vec::new();
+        socket.read_to_end(&mut response).await.unwrap();
+
+        let entries = proxy.entries();
+        let post = entries[0].request.post_data.as_ref().unwrap();
+        assert!(post.is_base64());
+        assert_eq!(post.bytes().unwrap(), upload);
+        assert_eq!(entries[0].request.body_size, upload.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn intercepts_https_tunnels() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = Arc::new(CertificateAuthority::load_or_create(dir.path()).unwrap());
+
+        // An HTTPS server whose certificate the proxy's client trusts.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream = listener.local_addr().unwrap();
+        let (chain, key) = ca.leaf("127.0.0.1").unwrap();
+        let identity = native_tls::Identity::from_pkcs8(chain.as_bytes(), key.as_bytes()).unwrap();
+        let acceptor =
+            tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut tls = acceptor.accept(socket).await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = tls.read(&mut buf).await.unwrap();
+            tls.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nsecret",
+            )
+            .await
+            .unwrap();
+            tls.shutdown().await.unwrap();
+        });
+
+        let client = Client::with_config(crate::client::ClientConfig {
+            ca_bundle: Some(ca.certificate_path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let proxy =
+            RecordingProxy::start(([127, 0, 0, 1], 0).into(), Arc::clone(&ca), client, |_| {})
+                .await
+                .unwrap();
+
+        let mut socket = TcpStream::connect(proxy.addr()).await.unwrap();
+        let connect = format!("CONNECT {upstream} HTTP/1.1\r\nHost: {upstream}\r\n\r\n");
+        socket.write_all(connect.as_bytes()).await.unwrap();
+        let mut buf = [0; 1024];
+        let n = socket.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200"));
+
+        let root = native_tls::Certificate::from_pem(ca.certificate_pem().as_bytes()).unwrap();
+        let connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(root)
+            .build()
+            .unwrap();
+        let mut tls = tokio_native_tls::TlsConnector::from(connector)
+            .connect("127.0.0.1", socket)
+            .await
+            .unwrap();
+        let request =
+            format!("GET /vault HTTP/1.1\r\nHost: {upstream}\r\nConnection: close\r\n\r\n");
+        tls.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        let _ = tls.read_to_end(&mut response).await;
+        let response = String::from_utf8_lossy(&response);
+        assert!(response.ends_with("secret"), "{response}");
+
+        let entries = proxy.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].request.url, format!("https://{upstream}/vault"));
+        assert_eq!(entries[0].response.content.text.as_deref(), Some("secret"));
+        assert!(entries[0].timings.ssl.unwrap() >= 0.0);
+    }
+}
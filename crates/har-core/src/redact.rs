@@ -0,0 +1,327 @@
+//! Removing credentials from an archive before it is shared.
+//!
+//! Header values, cookie values and named parameters in URLs, the query
+//! string and JSON or form bodies are replaced with [`REDACTED`]. Names
+//! match case-insensitively and exactly; the defaults cover the usual
+//! authentication headers and token parameters.
+
+use crate::har::{HarCookie, HarEntry, HarHeader};
+use serde_json::Value;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+const DEFAULT_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+    "x-xsrf-token",
+];
+
+/// Headers holding a URL, whose query is redacted like the request URL's.
+const URL_HEADERS: &[&str] = &["location", "origin", "referer"];
+
+const DEFAULT_PARAMS: &[&str] = &[
+    "access_token",
+    "api_key",
+    "apikey",
+    "auth",
+    "client_secret",
+    "id_token",
+    "passwd",
+    "password",
+    "refresh_token",
+    "secret",
+    "session",
+    "sessionid",
+    "token",
+];
+
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    /// Header names whose values are replaced.
+    pub headers: Vec<String>,
+    /// Parameter names redacted in URLs, query strings, form bodies and
+    /// JSON bodies.
+    pub params: Vec<String>,
+    /// Replace every cookie value, not only the `Cookie` headers.
+    pub cookies: bool,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction {
+            headers: DEFAULT_HEADERS.iter().map(|s| s.to_string()).collect(),
+            params: DEFAULT_PARAMS.iter().map(|s| s.to_string()).collect(),
+            cookies: true,
+        }
+    }
+}
+
+impl Redaction {
+    fn header(&self, name: &str) -> bool {
+        self.headers.iter().any(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    fn param(&self, name: &str) -> bool {
+        self.params.iter().any(|p| p.eq_ignore_ascii_case(name))
+    }
+
+    /// Redacts `entry` in place and returns how many values were replaced.
+    /// A JSON response body is only redacted if it is loaded into
+    /// `response.content.text`.
+    pub fn apply(&self, entry: &mut HarEntry) -> usize {
+        let mut count = 0;
+        let request = &mut entry.request;
+        count += self.headers(&mut request.headers);
+        count += self.query(&mut request.url);
+        for param in &mut request.query_string {
+            if self.param(&param.name) {
+                count += replace(&mut param.value);
+            }
+        }
+        if let Some(post) = &mut request.post_data {
+            for param in post.params.iter_mut().flatten() {
+                if self.param(&param.name) {
+                    if let Some(value) = &mut param.value {
+                        count += replace(value);
+                    }
+                }
+            }
+            if let Some(text) = &mut post.text {
+                count += if post.mime_type.contains("json") {
+                    self.json_text(text)
+                } else if post
+                    .mime_type
+                    .starts_with("application/x-www-form-urlencoded")
+                {
+                    self.pairs(text)
+                } else {
+                    0
+                };
+            }
+        }
+
+        let response = &mut entry.response;
+        count += self.headers(&mut response.headers);
+        if self.cookies {
+            count += cookies(&mut request.cookies) + cookies(&mut response.cookies);
+        }
+        count += self.query(&mut response.redirect_url);
+        let content = &mut response.content;
+        if let Some(text) = &mut content.text {
+            if content.mime_type.contains("json") && content.encoding.is_none() {
+                count += self.json_text(text);
+            }
+        }
+        count
+    }
+
+    fn headers(&self, headers: &mut [HarHeader]) -> usize {
+        headers
+            .iter_mut()
+            .map(|h| {
+                if self.header(&h.name) {
+                    replace(&mut h.value)
+                } else if URL_HEADERS.iter().any(|u| u.eq_ignore_ascii_case(&h.name)) {
+                    self.query(&mut h.value)
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Redacts parameters in the query of a URL, leaving the rest as is.
+    fn query(&self, url: &mut String) -> usize {
+        let Some(start) = url.find('?') else {
+            return 0;
+        };
+        let end = url[start..].find('#').map_or(url.len(), |i| start + i);
+        let mut query = url[start + 1..end].to_string();
+        let count = self.pairs(&mut query);
+        if count > 0 {
+            url.replace_range(start + 1..end, &query);
+        }
+        count
+    }
+
+    /// Redacts `name=value` pairs joined by `&`.
+    fn pairs(&self, text: &mut String) -> usize {
+        let mut count = 0;
+        let pairs: Vec<String> = text
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) if self.param(&decode(name)) && value != REDACTED => {
+                    count += 1;
+                    format!("{name}={REDACTED}")
+                }
+                _ => pair.to_string(),
+            })
+            .collect();
+        if count > 0 {
+            *text = pairs.join("&");
+        }
+        count
+    }
+
+    fn json_text(&self, text: &mut String) -> usize {
+        let Ok(mut value) = serde_json::from_str::<Value>(text) else {
+            return 0;
+        };
+        let count = self.json(&mut value);
+        if count > 0 {
+            *text = value.to_string();
+        }
+        count
+    }
+
+    fn json(&self, value: &mut Value) -> usize {
+        match value {
+            Value::Object(map) => map
+                .iter_mut()
+                .map(|(key, value)| {
+                    if self.param(key) && !value.is_object() && !value.is_array() {
+                        if value.as_str() == Some(REDACTED) {
+                            0
+                        } else {
+                            *value = Value::from(REDACTED);
+                            1
+                        }
+                    } else {
+                        self.json(value)
+                    }
+                })
+                .sum(),
+            Value::Array(items) => items.iter_mut().map(|item| self.json(item)).sum(),
+            _ => 0,
+        }
+    }
+}
+
+fn replace(value: &mut String) -> usize {
+    if value == REDACTED {
+        return 0;
+    }
+    *value = REDACTED.to_string();
+    1
+}
+
+fn cookies(cookies: &mut [HarCookie]) -> usize {
+    cookies.iter_mut().map(|c| replace(&mut c.value)).sum()
+}
+
+/// Percent-decodes a parameter name for matching; invalid escapes are kept.
+fn decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(b) => {
+                    out.push(b);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::har::HarFile;
+    use crate::test_support;
+
+    const FIREFOX: &str = include_str!("../tests/fixtures/firefox.har");
+
+    #[test]
+    fn redacts_credentials_everywhere() {
+        let mut har: HarFile = serde_json::from_str(FIREFOX).unwrap();
+        let entry = &mut har.log.entries[0];
+        entry.request.url = "https://example.com/a?q=1&access_token=abc#top".into();
+        entry
+            .request
+            .headers
+            .push(HarHeader::new("Authorization", "Bearer abc"));
+        let post = entry.request.post_data.get_or_insert_with(|| {
+            serde_json::from_value(serde_json::json!({ "mimeType": "" })).unwrap()
+        });
+        post.mime_type = "application/json".into();
+        post.text = Some(r#"{"user":"ann","auth":{"password":"hunter2"}}"#.into());
+
+        let redaction = Redaction::default();
+        assert!(redaction.apply(entry) >= 3);
+        assert_eq!(
+            entry.request.url,
+            "https://example.com/a?q=1&access_token=[REDACTED]#top"
+        );
+        assert!(entry
+            .request
+            .headers
+            .iter()
+            .any(|h| h.name == "Authorization" && h.value == REDACTED));
+        let body: Value = serde_json::from_str(
+            entry
+                .request
+                .post_data
+                .as_ref()
+                .unwrap()
+                .text
+                .as_ref()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "user": "ann", "auth": { "password": REDACTED } })
+        );
+        assert!(entry.request.cookies.iter().all(|c| c.value == REDACTED));
+
+        // Running it again finds nothing left to replace.
+        assert_eq!(redaction.apply(entry), 0);
+    }
+
+    #[test]
+    fn redacts_urls_in_headers_and_json_responses() {
+        let location = "https://app.example.com/home?access_token=abc";
+        let mut entry = test_support::entry("GET", "https://example.com/callback")
+            .request_header("Referer", "https://example.com/login?token=abc&next=%2F")
+            .status(302)
+            .response_header("Location", location)
+            .set("/response/redirectURL", location)
+            .response_body(
+                "application/json",
+                r#"{"access_token":"abc","expires_in":60}"#,
+            )
+            .build();
+
+        assert_eq!(Redaction::default().apply(&mut entry), 4);
+        let redacted = "https://app.example.com/home?access_token=[REDACTED]";
+        assert_eq!(entry.response.headers[0].value, redacted);
+        assert_eq!(entry.response.redirect_url, redacted);
+        assert_eq!(
+            entry.request.headers[0].value,
+            "https://example.com/login?token=[REDACTED]&next=%2F"
+        );
+        let body: Value =
+            serde_json::from_str(entry.response.content.text.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "access_token": REDACTED, "expires_in": 60 })
+        );
+    }
+}
@@ -0,0 +1,641 @@
+//! Sending a recorded request again.
+//!
+//! A replay produces a complete [`HarEntry`] describing what was actually
+//! sent and received, with measured timings, so it can be appended to an
+//! archive, saved and compared like captured traffic.
+
+use crate::client::{now_millis, Client, Exchange};
+use crate::compression;
+use crate::cookies::{self, CookieJar};
+use crate::error::{Error, ErrorKind, Result};
+use crate::form::{self, Attachments};
+use crate::har::{
+    format_date_time, Extensions, HarCache, HarContent, HarEntry, HarHeader, HarPostData,
+    HarQueryString, HarRequest, HarResponse, HarTimings,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue};
+use hyper::{Method, StatusCode, Uri, Version};
+use std::time::Duration;
+
+/// Accepts any valid HTTP token, so WebDAV verbs like `PROPFIND` and custom
+/// ones like `PURGE` replay as recorded.
+pub fn parse_method(method: &str) -> Result<Method> {
+    if method.eq_ignore_ascii_case("CONNECT") {
+        return Err(Error::new(
+            ErrorKind::UnsupportedMethod,
+            "CONNECT only opens a tunnel through a proxy and cannot be replayed on its own; \
+             replay the requests that were sent through the tunnel instead",
+        ));
+    }
+    Method::from_bytes(method.as_bytes()).map_err(|_| {
+        Error::new(
+            ErrorKind::UnsupportedMethod,
+            format!("{method:?} is not a valid HTTP method token"),
+        )
+    })
+}
+
+/// Sends `request` with `client` and reads the whole response, following
+/// redirects as the client is configured to. Bodies recorded only as
+/// `params` are encoded again, with `attachments` standing in for file
+/// contents that were not captured. The entry describes the last exchange.
+///
+/// With a `jar`, the recorded `Cookie` header is replaced by the cookies the
+/// jar holds for each URL, and every response's `Set-Cookie`, redirects
+/// included, is stored in it.
+pub async fn replay_request(
+    client: &Client,
+    request: &HarRequest,
+    attachments: &Attachments,
+    jar: Option<&mut CookieJar>,
+) -> Result<HarEntry> {
+    let replay = follow(client, request, attachments, jar);
+    let result = match client.config().timeout_ms {
+        Some(ms) => tokio::time::timeout(Duration::from_millis(ms), replay)
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::new(
+                    ErrorKind::Timeout,
+                    format!("The replay took longer than {ms} ms"),
+                ))
+            }),
+        None => replay.await,
+    };
+    result.map_err(|e| e.with_method(&request.method).with_url(&request.url))
+}
+
+async fn follow(
+    client: &Client,
+    recorded: &HarRequest,
+    attachments: &Attachments,
+    mut jar: Option<&mut CookieJar>,
+) -> Result<HarEntry> {
+    let mut method = parse_method(&recorded.method)?;
+    let mut uri = parse_url(&recorded.url)?;
+    let body = match &recorded.post_data {
+        Some(post) => Some(form::body(post, attachments)?),
+        None => None,
+    };
+
+    let mut headers = HeaderMap::new();
+    let mut framed = body.is_some();
+    for header in &recorded.headers {
+        // The body may differ from the recorded one, or be missing from the
+        // capture, so its framing and type come from what is actually sent.
+        if header.name.eq_ignore_ascii_case("content-length")
+            || header.name.eq_ignore_ascii_case("transfer-encoding")
+        {
+            framed = true;
+            continue;
+        }
+        if header.name.eq_ignore_ascii_case("content-type")
+            && body.as_ref().is_some_and(|b| !b.content_type.is_empty())
+        {
+            continue;
+        }
+        // HTTP/2 pseudo-headers such as `:authority` are rebuilt from the URL.
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(header.name.as_bytes()),
+            HeaderValue::from_str(&header.value),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    let mut request = recorded.clone();
+    let mut bytes = Vec::new();
+    if let (Some(body), Some(post)) = (body, &recorded.post_data) {
+        if let Ok(value) = HeaderValue::from_str(&body.content_type) {
+            if !body.content_type.is_empty() {
+                headers.insert(header::CONTENT_TYPE, value);
+            }
+        }
+        let mut sent = HarPostData {
+            mime_type: body.content_type,
+            ..post.clone()
+        };
+        sent.set_bytes(&body.bytes);
+        request.post_data = Some(sent);
+        bytes = body.bytes;
+    }
+    if framed {
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+    }
+
+    let mut redirects = 0;
+    loop {
+        if let Some(jar) = jar.as_deref() {
+            headers.remove(header::COOKIE);
+            let cookie = jar.header(&uri, now_millis());
+            if let Some(value) = cookie.and_then(|c| HeaderValue::from_str(&c).ok()) {
+                headers.insert(header::COOKIE, value);
+            }
+        }
+        let exchange = client.send(&method, &uri, &headers, &bytes).await?;
+        if let Some(jar) = jar.as_deref_mut() {
+            jar.store_response(&uri, &exchange.response.headers, now_millis());
+        }
+        let status = exchange.response.status;
+        let location = exchange
+            .response
+            .headers
+            .get(header::LOCATION)
+            .and_then(|v| v.to_str().ok());
+        let next = match location {
+            Some(location)
+                if status.is_redirection()
+                    && status != StatusCode::NOT_MODIFIED
+                    && redirects < client.config().max_redirects =>
+            {
+                resolve(&uri, location)?
+            }
+            _ => return Ok(entry(&request, bytes.len(), exchange)),
+        };
+        redirects += 1;
+
+        // As in browsers, a 303, or a 301 or 302 answering a POST, is
+        // followed with a GET without the body.
+        let to_get = (status == StatusCode::SEE_OTHER && method != Method::HEAD)
+            || (matches!(status.as_u16(), 301 | 302) && method == Method::POST);
+        if to_get {
+            method = Method::GET;
+            bytes.clear();
+            request.post_data = None;
+            headers.remove(header::CONTENT_TYPE);
+            headers.remove(header::CONTENT_LENGTH);
+        }
+        // Credentials are only meant for the origin they were recorded for.
+        if !same_origin(&uri, &next) {
+            headers.remove(header::AUTHORIZATION);
+            headers.remove(header::COOKIE);
+        }
+        request.method = method.to_string();
+        request.url = next.to_string();
+        request.query_string = query_string(&next);
+        uri = next;
+    }
+}
+
+/// Resolves a `Location` against the URL it answered. Dot segments in
+/// relative references are left for the server to interpret.
+fn resolve(base: &Uri, location: &str) -> Result<Uri> {
+    let location = location.trim();
+    let scheme = base.scheme_str().unwrap_or("http");
+    let authority = base.authority().map_or("", |a| a.as_str());
+    let url = if location.contains("://") {
+        location.to_string()
+    } else if let Some(rest) = location.strip_prefix("//") {
+        format!("{scheme}://{rest}")
+    } else if location.starts_with('/') {
+        format!("{scheme}://{authority}{location}")
+    } else if location.starts_with('?') {
+        format!("{scheme}://{authority}{}{location}", base.path())
+    } else {
+        let path = base.path();
+        let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{scheme}://{authority}{dir}{location}")
+    };
+    parse_url(&url)
+}
+
+fn same_origin(a: &Uri, b: &Uri) -> bool {
+    let port = |uri: &Uri| {
+        uri.port_u16()
+            .unwrap_or(if uri.scheme_str() == Some("https") {
+                443
+            } else {
+                80
+            })
+    };
+    a.scheme() == b.scheme()
+        && a.host()
+            .unwrap_or_default()
+            .eq_ignore_ascii_case(b.host().unwrap_or_default())
+        && port(a) == port(b)
+}
+
+pub(crate) fn query_string(uri: &Uri) -> Vec<HarQueryString> {
+    uri.query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            HarQueryString {
+                name: name.to_string(),
+                value: value.to_string(),
+                comment: None,
+                extensions: Extensions::new(),
+            }
+        })
+        .collect()
+}
+
+/// Parses a recorded URL; the fragment is never sent, so it is dropped.
+pub(crate) fn parse_url(url: &str) -> Result<Uri> {
+    let url = url.split_once('#').map_or(url, |(url, _)| url);
+    url.parse::<Uri>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidRequest,
+            format!("{url:?} is not a valid URL: {e}"),
+        )
+    })
+}
+
+/// The entry for an exchange that sent `sent` with a body of `body_size`
+/// bytes, with measured timings.
+pub(crate) fn entry(sent: &HarRequest, body_size: usize, exchange: Exchange) -> HarEntry {
+    let Exchange {
+        started,
+        headers,
+        version,
+        response,
+        body,
+        server,
+        local,
+        timings,
+    } = exchange;
+    let http_version = format!("{version:?}");
+    let h1 = version < Version::HTTP_2;
+
+    let request_headers = har_headers(&headers);
+    let request = HarRequest {
+        http_version: http_version.clone(),
+        cookies: headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(cookies::parse_cookie_header)
+            .collect(),
+        headers_size: if h1 {
+            let path = parse_url(&sent.url)
+                .ok()
+                .and_then(|uri| uri.path_and_query().map(|p| p.as_str().len()))
+                .unwrap_or(1);
+            headers_size(
+                sent.method.len() + 1 + path + 1 + http_version.len(),
+                &request_headers,
+            )
+        } else {
+            -1
+        },
+        body_size: body_size as i64,
+        headers: request_headers,
+        ..sent.clone()
+    };
+
+    let status = response.status;
+    let status_text = response
+        .extensions
+        .get::<hyper::ext::ReasonPhrase>()
+        .map(|reason| String::from_utf8_lossy(reason.as_bytes()).into_owned())
+        .or_else(|| status.canonical_reason().map(str::to_string))
+        .unwrap_or_default();
+    let header_text = |name| {
+        response
+            .headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let response_headers = har_headers(&response.headers);
+    let received = started
+        + timings.dns.unwrap_or(0.0)
+        + timings.connect.unwrap_or(0.0)
+        + timings.send
+        + timings.wait;
+    let response = HarResponse {
+        status: i64::from(status.as_u16()),
+        status_text: status_text.clone(),
+        http_version: http_version.clone(),
+        cookies: response
+            .headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| cookies::parse_set_cookie(v, received))
+            .collect(),
+        content: content(
+            &body,
+            &header_text(header::CONTENT_ENCODING),
+            header_text(header::CONTENT_TYPE),
+        ),
+        redirect_url: header_text(header::LOCATION),
+        headers_size: if h1 {
+            let status_line = http_version.len() + 1 + 3 + 1 + status_text.len();
+            headers_size(status_line, &response_headers)
+        } else {
+            -1
+        },
+        body_size: body.len() as i64,
+        comment: None,
+        transfer_size: None,
+        headers: response_headers,
+        extensions: Extensions::new(),
+    };
+
+    let timings = HarTimings {
+        blocked: Some(-1.0),
+        dns: Some(timings.dns.unwrap_or(-1.0)),
+        connect: Some(timings.connect.unwrap_or(-1.0)),
+        send: timings.send,
+        wait: timings.wait,
+        receive: timings.receive,
+        ssl: Some(timings.ssl.unwrap_or(-1.0)),
+        comment: None,
+        blocked_queueing: None,
+        extensions: Extensions::new(),
+    };
+    HarEntry {
+        pageref: None,
+        started_date_time: format_date_time(started),
+        time: timings.dns.unwrap_or(-1.0).max(0.0)
+            + timings.connect.unwrap_or(-1.0).max(0.0)
+            + timings.send
+            + timings.wait
+            + timings.receive,
+        request,
+        response,
+        cache: HarCache::default(),
+        timings,
+        server_ip_address: Some(server.ip().to_string()),
+        connection: Some(local.port().to_string()),
+        comment: None,
+        initiator: None,
+        priority: None,
+        resource_type: None,
+        from_cache: None,
+        security_state: None,
+        web_socket_messages: None,
+        extensions: Extensions::new(),
+    }
+}
+
+/// The decoded response body. Text that is not UTF-8 is stored as base64,
+/// and `compression` records how much the wire encoding saved.
+fn content(wire: &[u8], encoding: &str, mime_type: String) -> HarContent {
+    let (bytes, comment) = match compression::decode_content(encoding, wire) {
+        Ok(decoded) => (decoded, None),
+        Err(e) => (wire.to_vec(), Some(format!("Body kept as received: {e}"))),
+    };
+    let size = bytes.len() as i64;
+    let compression = (comment.is_none() && !encoding.is_empty()).then(|| size - wire.len() as i64);
+    let (text, encoding) = match String::from_utf8(bytes) {
+        Ok(text) => (text, None),
+        Err(e) => (BASE64.encode(e.as_bytes()), Some("base64".to_string())),
+    };
+    HarContent {
+        size,
+        compression,
+        mime_type,
+        text: Some(text),
+        encoding,
+        comment,
+        extensions: Extensions::new(),
+    }
+}
+
+fn har_headers(headers: &HeaderMap) -> Vec<HarHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            HarHeader::new(name.as_str(), String::from_utf8_lossy(value.as_bytes()))
+        })
+        .collect()
+}
+
+/// Size of an HTTP/1 head: the start line, `name: value` lines and the blank
+/// line, each ended by CRLF.
+fn headers_size(start_line: usize, headers: &[HarHeader]) -> i64 {
+    let lines: usize = headers
+        .iter()
+        .map(|h| h.name.len() + 2 + h.value.len() + 2)
+        .sum();
+    (start_line + 2 + lines + 2) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientConfig;
+    use crate::test_support::{self, Reply};
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    #[test]
+    fn accepts_any_method_token() {
+        for method in ["GET", "TRACE", "PROPFIND", "MKCOL", "REPORT", "PURGE"] {
+            assert_eq!(parse_method(method).unwrap().as_str(), method);
+        }
+        for method in ["CONNECT", "connect", "", "GET /", "BAD(METHOD)"] {
+            let err = parse_method(method).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::UnsupportedMethod, "{method}");
+        }
+    }
+
+    /// Answers requests in turn with `replies`.
+    async fn serve(replies: Vec<Reply>) -> std::net::SocketAddr {
+        let replies = Mutex::new(VecDeque::from(replies));
+        test_support::serve(move |_| {
+            replies
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("a reply for every request")
+        })
+        .await
+    }
+
+    fn get(url: String) -> HarRequest {
+        test_support::entry("GET", &url).build().request
+    }
+
+    #[tokio::test]
+    async fn replay_records_a_full_entry() {
+        let addr = serve(vec![Reply::raw(
+            "HTTP/1.1 201 Made\r\nContent-Type: text/plain\r\n\
+             Set-Cookie: sid=abc; HttpOnly\r\nLocation: /next\r\n\
+             Content-Length: 5\r\n\r\nhello",
+        )])
+        .await;
+
+        let request = test_support::entry("POST", &format!("http://{addr}/submit#top"))
+            .set("/request/httpVersion", "h2")
+            .request_header(":authority", "example.com")
+            .request_header("Cookie", "a=1; b=2")
+            .request_header("Content-Length", "99")
+            .request_body("application/x-www-form-urlencoded", "q=1")
+            .build()
+            .request;
+        let client = Client::new().unwrap();
+        let entry = replay_request(&client, &request, &Attachments::new(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(entry.request.http_version, "HTTP/1.1");
+        let sent: Vec<_> = entry
+            .request
+            .headers
+            .iter()
+            .map(|h| h.name.as_str())
+            .collect();
+        assert_eq!(sent, ["host", "cookie", "content-type", "content-length"]);
+        assert_eq!(entry.request.cookies.len(), 2);
+        assert_eq!(entry.request.body_size, 3);
+
+        let response = &entry.response;
+        assert_eq!(
+            (response.status, response.status_text.as_str()),
+            (201, "Made")
+        );
+        assert_eq!(response.content.text.as_deref(), Some("hello"));
+        assert_eq!(response.content.mime_type, "text/plain");
+        assert_eq!(response.redirect_url, "/next");
+        assert_eq!(response.cookies[0].http_only, Some(true));
+        assert_eq!(response.body_size, 5);
+
+        let timings = &entry.timings;
+        assert_eq!((timings.dns, timings.ssl), (Some(-1.0), Some(-1.0)));
+        assert!(timings.connect.unwrap() >= 0.0 && timings.wait >= 0.0);
+        assert_eq!(entry.server_ip_address.as_deref(), Some("127.0.0.1"));
+        assert!(entry.started_millis().is_some());
+    }
+
+    #[tokio::test]
+    async fn binary_bodies_are_decoded_and_base64_encoded() {
+        use std::io::Write;
+
+        let png = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0x00];
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gzip.write_all(&png).unwrap();
+        let wire = gzip.finish().unwrap();
+        let addr = serve(vec![Reply::new(
+            200,
+            &[("Content-Type", "image/png"), ("Content-Encoding", "gzip")],
+            &wire,
+        )])
+        .await;
+
+        let client = Client::new().unwrap();
+        let entry = replay_request(
+            &client,
+            &get(format!("http://{addr}/logo.png")),
+            &Attachments::new(),
+            None,
+        )
+        .await
+        .unwrap();
+        let content = &entry.response.content;
+        assert_eq!(content.encoding.as_deref(), Some("base64"));
+        assert_eq!(BASE64.decode(content.text.as_ref().unwrap()).unwrap(), png);
+        assert_eq!(content.size, png.len() as i64);
+        assert_eq!(entry.response.body_size, wire.len() as i64);
+        assert_eq!(
+            content.compression,
+            Some(content.size - entry.response.body_size)
+        );
+    }
+
+    #[tokio::test]
+    async fn binary_attachments_are_recorded_as_sent() {
+        let addr = serve(vec![Reply::new(204, &[], "")]).await;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), [0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10]).unwrap();
+        let attachments = Attachments::from([("photo".to_string(), file.path().to_path_buf())]);
+        let request = test_support::entry("POST", &format!("http://{addr}/upload"))
+            .set(
+                "/request/postData",
+                json!({
+                    "mimeType": "multipart/form-data; boundary=XyZ",
+                    "params": [{ "name": "photo", "fileName": "me.jpg", "contentType": "image/jpeg" }]
+                }),
+            )
+            .set("/request/bodySize", -1)
+            .build()
+            .request;
+        let bytes = form::body(request.post_data.as_ref().unwrap(), &attachments)
+            .unwrap()
+            .bytes;
+
+        let client = Client::new().unwrap();
+        let entry = replay_request(&client, &request, &attachments, None)
+            .await
+            .unwrap();
+        assert_eq!(entry.request.body_size, bytes.len() as i64);
+        let post = entry.request.post_data.as_ref().unwrap();
+        assert!(post.is_base64());
+        assert_eq!(post.bytes().unwrap(), bytes);
+    }
+
+    #[tokio::test]
+    async fn bodiless_posts_are_sent_with_an_empty_body() {
+        let addr = test_support::serve(|request| {
+            let length = request.header("content-length").unwrap_or("none");
+            Reply::new(200, &[], length)
+        })
+        .await;
+        let request = test_support::entry("POST", &format!("http://{addr}/ping"))
+            .request_header("Content-Length", "5")
+            .request_header("Transfer-Encoding", "chunked")
+            .build()
+            .request;
+        let client = Client::with_config(ClientConfig {
+            timeout_ms: Some(2000),
+            ..ClientConfig::default()
+        })
+        .unwrap();
+        let entry = replay_request(&client, &request, &Attachments::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(entry.response.content.text.as_deref(), Some("0"));
+        assert_eq!(entry.request.body_size, 0);
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_on_a_reused_connection() {
+        let found = || {
+            Reply::new(
+                302,
+                &[("Location", "final?n=1"), ("Set-Cookie", "sid=new; Path=/")],
+                "",
+            )
+        };
+        let addr = serve(vec![found(), Reply::new(200, &[], "done")]).await;
+        let client = Client::new().unwrap();
+        let mut jar = CookieJar::new();
+        let entry = replay_request(
+            &client,
+            &get(format!("http://{addr}/a/start")),
+            &Attachments::new(),
+            Some(&mut jar),
+        )
+        .await
+        .unwrap();
+        assert_eq!(entry.response.status, 200);
+        assert_eq!(entry.request.cookies[0].value, "new");
+        assert_eq!(jar.cookies().len(), 1);
+        assert_eq!(entry.request.url, format!("http://{addr}/a/final?n=1"));
+        assert_eq!(entry.request.query_string[0].value, "1");
+        assert_eq!(entry.timings.connect, Some(-1.0));
+
+        let addr = serve(vec![found()]).await;
+        let client = Client::with_config(ClientConfig {
+            max_redirects: 0,
+            ..ClientConfig::default()
+        })
+        .unwrap();
+        let entry = replay_request(
+            &client,
+            &get(format!("http://{addr}/a/start")),
+            &Attachments::new(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(entry.response.status, 302);
+        assert_eq!(entry.response.redirect_url, "final?n=1");
+    }
+}
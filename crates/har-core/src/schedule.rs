@@ -0,0 +1,296 @@
+//! Replaying traffic on its recorded schedule.
+//!
+//! Each entry is sent at its `startedDateTime` offset from the first one,
+//! divided by [`ScheduleOptions::speed`], whether or not earlier requests
+//! have finished, so requests that overlapped in the capture overlap in the
+//! replay. The report compares when each request was meant to start with
+//! when it did, and summarizes how long the responses took.
+
+use crate::client::Client;
+use crate::error::{Error, ErrorKind, Result};
+use crate::form::Attachments;
+use crate::har::HarEntry;
+use crate::replay;
+use crate::summary::Distribution;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// How often a running schedule checks whether it has been cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduleOptions {
+    /// How much faster than recorded to replay: 2 halves every gap, 0.5
+    /// doubles it.
+    pub speed: f64,
+}
+
+impl Default for ScheduleOptions {
+    fn default() -> Self {
+        ScheduleOptions { speed: 1.0 }
+    }
+}
+
+/// When one request was meant to start and what it did.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledItem {
+    /// Position of the entry in the archive.
+    pub index: usize,
+    pub method: String,
+    pub url: String,
+    /// Start offset in the capture, in milliseconds from the first request.
+    pub recorded_offset: f64,
+    /// `recorded_offset` scaled by the speed.
+    pub planned_offset: f64,
+    /// When the request was actually sent, from the start of the replay;
+    /// unset if it was cancelled first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub achieved_offset: Option<f64>,
+    pub recorded_status: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    /// Duration of the replayed exchange in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+/// Sent as each request completes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleProgress<'a> {
+    pub completed: usize,
+    pub total: usize,
+    pub item: &'a ScheduledItem,
+}
+
+/// The outcome of a scheduled replay.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleReport {
+    pub speed: f64,
+    /// One item per entry, in the order they were planned to start.
+    pub items: Vec<ScheduledItem>,
+    /// Requests never sent because the replay was cancelled.
+    pub cancelled: usize,
+    /// Requests that got no response.
+    pub errors: usize,
+    /// How late requests started against the plan.
+    pub lag: Distribution,
+    /// How long the replayed responses took.
+    pub response_time: Distribution,
+    /// The recorded durations of the same requests, for comparison.
+    pub recorded_time: Distribution,
+}
+
+/// Replays `entries`, each given with its position in the archive, on the
+/// schedule they were recorded with, calling `on_progress` as each one
+/// completes. Entries whose `startedDateTime` cannot be read are sent at
+/// the start. Setting `cancel` drops the requests in flight and skips the
+/// rest.
+pub async fn replay_schedule(
+    client: &Client,
+    entries: Vec<(usize, HarEntry)>,
+    options: &ScheduleOptions,
+    attachments: &Attachments,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(&ScheduleProgress),
+) -> Result<ScheduleReport> {
+    let speed = options.speed;
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(Error::new(
+            ErrorKind::InvalidRequest,
+            format!("The replay speed must be above 0, not {speed}"),
+        ));
+    }
+    let first = entries
+        .iter()
+        .filter_map(|(_, entry)| entry.started_millis())
+        .reduce(f64::min)
+        .unwrap_or_default();
+    let mut planned: Vec<(f64, usize, HarEntry)> = entries
+        .into_iter()
+        .map(|(index, entry)| {
+            let offset = entry.started_millis().map_or(0.0, |start| start - first);
+            (offset, index, entry)
+        })
+        .collect();
+    planned.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let mut items: Vec<ScheduledItem> = planned
+        .iter()
+        .map(|(offset, index, entry)| ScheduledItem {
+            index: *index,
+            method: entry.request.method.clone(),
+            url: entry.request.url.clone(),
+            recorded_offset: *offset,
+            planned_offset: offset / speed,
+            achieved_offset: None,
+            recorded_status: entry.response.status,
+            status: None,
+            time: None,
+            error: None,
+        })
+        .collect();
+    let recorded_time = Distribution::of(planned.iter().map(|(_, _, entry)| entry.time));
+
+    let total = items.len();
+    let attachments = Arc::new(attachments.clone());
+    let start = Instant::now();
+    let mut running = JoinSet::new();
+    for (position, (offset, _, entry)) in planned.into_iter().enumerate() {
+        let client = client.clone();
+        let attachments = Arc::clone(&attachments);
+        let at = start + Duration::from_secs_f64(offset / speed / 1000.0);
+        running.spawn(async move {
+            tokio::time::sleep_until(at).await;
+            let sent = Instant::now();
+            let result = replay::replay_request(&client, &entry.request, &attachments, None).await;
+            let achieved = sent.duration_since(start).as_secs_f64() * 1000.0;
+            let time = sent.elapsed().as_secs_f64() * 1000.0;
+            (position, achieved, time, result)
+        });
+    }
+
+    let mut completed = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            running.abort_all();
+            break;
+        }
+        match tokio::time::timeout(CANCEL_POLL, running.join_next()).await {
+            Ok(None) => break,
+            Ok(Some(Ok((position, achieved, time, result)))) => {
+                let item = &mut items[position];
+                item.achieved_offset = Some(achieved);
+                item.time = Some(time);
+                match result {
+                    Ok(replayed) => item.status = Some(replayed.response.status),
+                    Err(err) => item.error = Some(err),
+                }
+                completed += 1;
+                on_progress(&ScheduleProgress {
+                    completed,
+                    total,
+                    item,
+                });
+            }
+            // Either the poll timed out, or a task panicked and its entry
+            // stays cancelled.
+            Ok(Some(Err(_))) | Err(_) => {}
+        }
+    }
+    while running.join_next().await.is_some() {}
+
+    Ok(ScheduleReport {
+        speed,
+        cancelled: items.iter().filter(|i| i.achieved_offset.is_none()).count(),
+        errors: items.iter().filter(|i| i.error.is_some()).count(),
+        lag: Distribution::of(
+            items
+                .iter()
+                .filter_map(|i| Some(i.achieved_offset? - i.planned_offset)),
+        ),
+        response_time: Distribution::of(
+            items
+                .iter()
+                .filter(|i| i.status.is_some())
+                .filter_map(|i| i.time),
+        ),
+        recorded_time,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, serve, Reply};
+
+    #[tokio::test]
+    async fn keeps_recorded_gaps_scaled_by_speed() {
+        let addr = serve(|request| {
+            let reply = Reply::new(204, &[], "");
+            if request.target() == "/slow" {
+                reply.after(Duration::from_millis(200))
+            } else {
+                reply
+            }
+        })
+        .await;
+        // The slow request is still running when the third one is due.
+        let entries = vec![
+            (
+                0,
+                test_support::entry("GET", &format!("http://{addr}/slow"))
+                    .started("2024-05-14T09:00:00.000Z")
+                    .time(10.0)
+                    .build(),
+            ),
+            (
+                2,
+                test_support::entry("GET", &format!("http://{addr}/b"))
+                    .started("2024-05-14T09:00:00.400Z")
+                    .time(10.0)
+                    .build(),
+            ),
+            (
+                1,
+                test_support::entry("GET", &format!("http://{addr}/a"))
+                    .started("2024-05-14T09:00:00.200Z")
+                    .time(10.0)
+                    .build(),
+            ),
+        ];
+        let mut completed = Vec::new();
+        let report = replay_schedule(
+            &Client::new().unwrap(),
+            entries,
+            &ScheduleOptions { speed: 2.0 },
+            &Attachments::new(),
+            &AtomicBool::new(false),
+            |p| completed.push(p.item.index),
+        )
+        .await
+        .unwrap();
+
+        let planned: Vec<_> = report
+            .items
+            .iter()
+            .map(|i| (i.index, i.recorded_offset, i.planned_offset))
+            .collect();
+        assert_eq!(
+            planned,
+            [(0, 0.0, 0.0), (1, 200.0, 100.0), (2, 400.0, 200.0)]
+        );
+        for item in &report.items {
+            let achieved = item.achieved_offset.unwrap();
+            assert!(achieved >= item.planned_offset, "{item:?}");
+            assert!(achieved < item.planned_offset + 150.0, "{item:?}");
+            assert_eq!(item.status, Some(204));
+        }
+        // The slow response overlaps the next one and so finishes after it.
+        assert_eq!(completed[0], 1);
+        assert_eq!(report.response_time.count, 3);
+        assert_eq!(report.recorded_time.p50, 10.0);
+
+        let err = replay_schedule(
+            &Client::new().unwrap(),
+            Vec::new(),
+            &ScheduleOptions { speed: 0.0 },
+            &Attachments::new(),
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidRequest);
+    }
+}
@@ -0,0 +1,257 @@
+//! Totals and distributions over a whole archive.
+//!
+//! Everything is gathered in one pass over the entries, so summaries stay
+//! cheap for archives far larger than the viewer can render at once.
+
+use crate::har::{HarEntry, HarFile};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Order statistics of a set of values, such as durations in milliseconds
+/// or sizes in bytes. All zero for an empty set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Self {
+        let mut values: Vec<f64> = values.into_iter().filter(|v| v.is_finite()).collect();
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        values.sort_by(f64::total_cmp);
+        let count = values.len();
+        Distribution {
+            count,
+            min: values[0],
+            mean: values.iter().sum::<f64>() / count as f64,
+            p50: percentile(&values, 50.0),
+            p90: percentile(&values, 90.0),
+            p99: percentile(&values, 99.0),
+            max: values[count - 1],
+        }
+    }
+}
+
+/// The nearest-rank `p`th percentile of sorted, non-empty `values`.
+pub(crate) fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Durations of each [`HarTimings`](crate::har::HarTimings) phase, over
+/// the entries where it applies; `-1` and missing phases are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseDistributions {
+    pub blocked: Distribution,
+    pub dns: Distribution,
+    pub connect: Distribution,
+    pub ssl: Distribution,
+    pub send: Distribution,
+    pub wait: Distribution,
+    pub receive: Distribution,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub creator: String,
+    pub version: String,
+    pub pages: usize,
+    pub entries: usize,
+    /// The earliest `startedDateTime`, as recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<String>,
+    /// From the first request starting to the last one finishing.
+    pub span_ms: f64,
+    /// Decoded response body bytes.
+    pub content_bytes: i64,
+    /// Response body bytes on the wire, where the exporter recorded them.
+    pub transfer_bytes: i64,
+    /// Entry counts by status class (`2xx`, `4xx`, ...), with `failed` for
+    /// requests that got no response.
+    pub statuses: BTreeMap<String, usize>,
+    /// Entry counts by response MIME type, without parameters.
+    pub mime_types: BTreeMap<String, usize>,
+    pub methods: BTreeMap<String, usize>,
+    /// Total entry times.
+    pub time: Distribution,
+    pub timings: PhaseDistributions,
+}
+
+pub fn summarize(har: &HarFile) -> Summary {
+    let log = &har.log;
+    let mut statuses = BTreeMap::new();
+    let mut mime_types = BTreeMap::new();
+    let mut methods = BTreeMap::new();
+    let mut started: Option<(f64, &str)> = None;
+    let mut end = f64::NEG_INFINITY;
+    let (mut content_bytes, mut transfer_bytes) = (0, 0);
+    let mut phases = Phases::default();
+
+    for entry in &log.entries {
+        let class = match entry.response.status {
+            0 => "failed".to_string(),
+            status => format!("{}xx", status / 100),
+        };
+        *statuses.entry(class).or_insert(0) += 1;
+        *mime_types
+            .entry(mime_type(&entry.response.content.mime_type))
+            .or_insert(0) += 1;
+        *methods
+            .entry(entry.request.method.to_ascii_uppercase())
+            .or_insert(0) += 1;
+        if let Some(millis) = entry.started_millis() {
+            if started.is_none_or(|(first, _)| millis < first) {
+                started = Some((millis, &entry.started_date_time));
+            }
+            end = end.max(millis + entry.time.max(0.0));
+        }
+        content_bytes += entry.response.content.size.max(0);
+        transfer_bytes += entry.response.body_size.max(0);
+        phases.add(entry);
+    }
+
+    Summary {
+        creator: format!("{} {}", log.creator.name, log.creator.version)
+            .trim()
+            .to_string(),
+        version: log.version.clone(),
+        pages: log.pages.as_ref().map_or(0, Vec::len),
+        entries: log.entries.len(),
+        started: started.map(|(_, date)| date.to_string()),
+        span_ms: started.map_or(0.0, |(first, _)| (end - first).max(0.0)),
+        content_bytes,
+        transfer_bytes,
+        statuses,
+        mime_types,
+        methods,
+        time: Distribution::of(phases.time),
+        timings: PhaseDistributions {
+            blocked: Distribution::of(phases.blocked),
+            dns: Distribution::of(phases.dns),
+            connect: Distribution::of(phases.connect),
+            ssl: Distribution::of(phases.ssl),
+            send: Distribution::of(phases.send),
+            wait: Distribution::of(phases.wait),
+            receive: Distribution::of(phases.receive),
+        },
+    }
+}
+
+/// `type/subtype` in lower case, or `unknown` when none was recorded.
+fn mime_type(recorded: &str) -> String {
+    let essence = recorded.split(';').next().unwrap_or_default().trim();
+    if essence.is_empty() {
+        "unknown".to_string()
+    } else {
+        essence.to_ascii_lowercase()
+    }
+}
+
+/// The durations seen so far, phase by phase.
+#[derive(Default)]
+struct Phases {
+    time: Vec<f64>,
+    blocked: Vec<f64>,
+    dns: Vec<f64>,
+    connect: Vec<f64>,
+    ssl: Vec<f64>,
+    send: Vec<f64>,
+    wait: Vec<f64>,
+    receive: Vec<f64>,
+}
+
+impl Phases {
+    fn add(&mut self, entry: &HarEntry) {
+        let timings = &entry.timings;
+        for (values, value) in [
+            (&mut self.time, Some(entry.time)),
+            (&mut self.blocked, timings.blocked),
+            (&mut self.dns, timings.dns),
+            (&mut self.connect, timings.connect),
+            (&mut self.ssl, timings.ssl),
+            (&mut self.send, Some(timings.send)),
+            (&mut self.wait, Some(timings.wait)),
+            (&mut self.receive, Some(timings.receive)),
+        ] {
+            if let Some(value) = value.filter(|v| *v >= 0.0) {
+                values.push(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn summarizes_entries() {
+        let mut har = HarFile::new("test", "1");
+        har.log.entries = vec![
+            test_support::entry("get", "https://example.com/")
+                .started("2024-05-14T09:00:01.000Z")
+                .mime_type("application/json; charset=utf-8")
+                .time(100.0)
+                .timing("dns", 5.0)
+                .set("/response/content/size", 100)
+                .set("/response/bodySize", 40)
+                .build(),
+            test_support::entry("GET", "https://example.com/")
+                .started("2024-05-14T09:00:00.000Z")
+                .status(404)
+                .mime_type("text/html")
+                .time(50.0)
+                .set("/response/content/size", 200)
+                .set("/response/bodySize", 80)
+                .build(),
+            test_support::entry("POST", "https://example.com/")
+                .started("2024-05-14T09:00:02.000Z")
+                .status(0)
+                .time(10.0)
+                .build(),
+        ];
+        let summary = summarize(&har);
+
+        assert_eq!(summary.entries, 3);
+        assert_eq!(summary.started.as_deref(), Some("2024-05-14T09:00:00.000Z"));
+        assert_eq!(summary.span_ms, 2010.0);
+        assert_eq!((summary.content_bytes, summary.transfer_bytes), (300, 120));
+        let counts = |map: &BTreeMap<String, usize>| {
+            map.iter()
+                .map(|(k, v)| format!("{k} {v}"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(counts(&summary.statuses), ["2xx 1", "4xx 1", "failed 1"]);
+        assert_eq!(
+            counts(&summary.mime_types),
+            ["application/json 1", "text/html 1", "unknown 1"]
+        );
+        assert_eq!(counts(&summary.methods), ["GET 2", "POST 1"]);
+        assert_eq!((summary.time.p50, summary.time.max), (50.0, 100.0));
+        assert_eq!(
+            (summary.timings.dns.count, summary.timings.dns.p99),
+            (1, 5.0)
+        );
+        assert_eq!(summary.timings.wait.count, 3);
+        assert_eq!(summary.timings.blocked, Distribution::default());
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let d = Distribution::of((1..=100).map(f64::from));
+        assert_eq!((d.p50, d.p90, d.p99, d.max), (50.0, 90.0, 99.0, 100.0));
+        assert_eq!(Distribution::of([]), Distribution::default());
+    }
+}
@@ -0,0 +1,95 @@
+//! `{{name}}` placeholders in requests.
+//!
+//! Placeholders may appear in the URL, query string, header and cookie
+//! values, and the body text and params of a request. Whitespace inside the
+//! braces is ignored. A placeholder without a value is left as written, so
+//! a missing variable is visible in what was sent.
+
+use crate::har::HarRequest;
+use std::collections::BTreeMap;
+
+/// Values for placeholders, by name.
+pub type Variables = BTreeMap<String, String>;
+
+/// Replaces the placeholders in `text`, adding the names of those without a
+/// value to `missing`.
+pub fn render(text: &str, variables: &Variables, missing: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + len + 2];
+        let name = rest[start + 2..start + 2 + len].trim();
+        out.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                if !name.is_empty() && !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+                out.push_str(placeholder);
+            }
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Replaces the placeholders throughout `request` and returns the names of
+/// those without a value.
+pub fn apply(request: &mut HarRequest, variables: &Variables) -> Vec<String> {
+    let mut missing = Vec::new();
+    edit(request, |text| render(text, variables, &mut missing));
+    missing
+}
+
+/// Rewrites every text of `request` that placeholders may appear in.
+pub(crate) fn edit(request: &mut HarRequest, mut f: impl FnMut(&str) -> String) {
+    let mut edit = |text: &mut String| *text = f(text);
+    edit(&mut request.url);
+    for param in &mut request.query_string {
+        edit(&mut param.value);
+    }
+    for header in &mut request.headers {
+        edit(&mut header.value);
+    }
+    for cookie in &mut request.cookies {
+        edit(&mut cookie.value);
+    }
+    if let Some(post) = &mut request.post_data {
+        // Binary bodies are base64; editing them would corrupt them.
+        if !post.is_base64() {
+            if let Some(text) = &mut post.text {
+                edit(text);
+            }
+        }
+        for param in post.params.iter_mut().flatten() {
+            if let Some(value) = &mut param.value {
+                edit(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_known_placeholders_and_keeps_the_rest() {
+        let variables = Variables::from([("token".to_string(), "abc".to_string())]);
+        let mut missing = Vec::new();
+        assert_eq!(
+            render(
+                "Bearer {{token}} {{ token }} {{user}} {{user}} {{",
+                &variables,
+                &mut missing
+            ),
+            "Bearer abc abc {{user}} {{user}} {{"
+        );
+        assert_eq!(missing, ["user"]);
+    }
+}
@@ -0,0 +1,286 @@
+//! Fixtures for the unit tests: entries built field by field and a local
+//! HTTP/1.1 server with canned answers.
+
+use crate::har::HarEntry;
+use hyper::StatusCode;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// An entry for `method` `url` answered with an empty 200, started at
+/// 2024-05-14T09:00:00Z and taking no time. The builder's setters change
+/// the rest.
+pub(crate) fn entry(method: &str, url: &str) -> EntryBuilder {
+    EntryBuilder(json!({
+        "startedDateTime": "2024-05-14T09:00:00Z",
+        "time": 0,
+        "request": {
+            "method": method,
+            "url": url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "queryString": [],
+            "headersSize": -1,
+            "bodySize": 0
+        },
+        "response": {
+            "status": 200,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": 0
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": 0, "receive": 0 }
+    }))
+}
+
+pub(crate) struct EntryBuilder(Value);
+
+impl EntryBuilder {
+    /// Sets the field at the JSON pointer `pointer`, adding it to its
+    /// object if it is missing.
+    pub fn set(mut self, pointer: &str, value: impl Into<Value>) -> Self {
+        let (parent, key) = pointer.rsplit_once('/').expect("a JSON pointer");
+        self.0
+            .pointer_mut(parent)
+            .and_then(Value::as_object_mut)
+            .unwrap_or_else(|| panic!("{parent:?} is not an object of the entry"))
+            .insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn pageref(self, id: &str) -> Self {
+        self.set("/pageref", id)
+    }
+
+    /// Marks the entry as loaded by a parser reading `url`, as Chrome
+    /// records it in `_initiator`.
+    pub fn initiator(self, url: &str) -> Self {
+        self.set("/_initiator", json!({ "type": "parser", "url": url }))
+    }
+
+    pub fn started(self, date_time: &str) -> Self {
+        self.set("/startedDateTime", date_time)
+    }
+
+    /// Sets `time`, all of it spent waiting for the response.
+    pub fn time(self, ms: f64) -> Self {
+        self.set("/time", ms).set("/timings/wait", ms)
+    }
+
+    pub fn status(self, status: i64) -> Self {
+        self.set("/response/status", status)
+    }
+
+    /// Sets the response's `content.mimeType`.
+    pub fn mime_type(self, mime_type: &str) -> Self {
+        self.set("/response/content/mimeType", mime_type)
+    }
+
+    /// Sets one phase of `timings`, such as `dns`.
+    pub fn timing(self, phase: &str, ms: f64) -> Self {
+        self.set(&format!("/timings/{phase}"), ms)
+    }
+
+    pub fn request_header(mut self, name: &str, value: &str) -> Self {
+        push_header(&mut self.0["request"]["headers"], name, value);
+        self
+    }
+
+    /// Sends `text` as the request body.
+    pub fn request_body(self, mime_type: &str, text: &str) -> Self {
+        self.set(
+            "/request/postData",
+            json!({ "mimeType": mime_type, "text": text }),
+        )
+        .set("/request/bodySize", text.len())
+    }
+
+    pub fn response_header(mut self, name: &str, value: &str) -> Self {
+        push_header(&mut self.0["response"]["headers"], name, value);
+        self
+    }
+
+    /// Answers with `text` as the body.
+    pub fn response_body(self, mime_type: &str, text: &str) -> Self {
+        self.set(
+            "/response/content",
+            json!({ "size": text.len(), "mimeType": mime_type, "text": text }),
+        )
+    }
+
+    pub fn build(self) -> HarEntry {
+        serde_json::from_value(self.0).unwrap()
+    }
+}
+
+fn push_header(headers: &mut Value, name: &str, value: &str) {
+    headers
+        .as_array_mut()
+        .expect("a header list")
+        .push(json!({ "name": name, "value": value }));
+}
+
+/// A request as the test server read it.
+pub(crate) struct Received {
+    /// The request line, like `GET /path HTTP/1.1`.
+    pub line: String,
+    /// How many requests came before this one on its connection.
+    pub sequence: usize,
+    /// The header lines.
+    head: String,
+}
+
+impl Received {
+    /// The request target, like `/path?query`.
+    pub fn target(&self) -> &str {
+        self.line.split(' ').nth(1).unwrap_or_default()
+    }
+
+    /// The value of the first header called `name`, in any case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.head, name)
+    }
+}
+
+/// What the test server answers a request with.
+pub(crate) struct Reply {
+    bytes: Vec<u8>,
+    delay: Duration,
+    hang_up: bool,
+}
+
+impl Reply {
+    /// A response with `status`, `headers` and `body`, and the
+    /// `Content-Length` of the body.
+    pub fn new(status: u16, headers: &[(&str, &str)], body: impl AsRef<[u8]>) -> Self {
+        let body = body.as_ref();
+        let reason = StatusCode::from_u16(status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or_default();
+        let mut head = format!("HTTP/1.1 {status} {reason}\r\n");
+        for (name, value) in headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(body);
+        Reply::raw(bytes)
+    }
+
+    /// A response sent exactly as `bytes`, for status lines and framing
+    /// that [`Reply::new`] does not write.
+    pub fn raw(bytes: impl Into<Vec<u8>>) -> Self {
+        Reply {
+            bytes: bytes.into(),
+            delay: Duration::ZERO,
+            hang_up: false,
+        }
+    }
+
+    /// Closes the connection without answering.
+    pub fn hang_up() -> Self {
+        Reply {
+            hang_up: true,
+            ..Reply::raw([])
+        }
+    }
+
+    /// Sent `delay` after the request was read.
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Serves HTTP/1.1 on a free port of localhost, answering each request
+/// with what `respond` returns for it. Connections are handled
+/// concurrently and kept open until either side sends `Connection: close`.
+pub(crate) async fn serve<F>(respond: F) -> SocketAddr
+where
+    F: Fn(&Received) -> Reply + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let respond = Arc::new(respond);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let respond = Arc::clone(&respond);
+            tokio::spawn(async move {
+                let mut sequence = 0;
+                while let Some(mut request) = read_request(&mut socket).await {
+                    request.sequence = sequence;
+                    sequence += 1;
+                    let reply = respond(&request);
+                    tokio::time::sleep(reply.delay).await;
+                    if reply.hang_up || socket.write_all(&reply.bytes).await.is_err() {
+                        return;
+                    }
+                    let end = reply
+                        .bytes
+                        .windows(4)
+                        .position(|w| w == b"\r\n\r\n")
+                        .unwrap_or(reply.bytes.len());
+                    let reply_head = String::from_utf8_lossy(&reply.bytes[..end]);
+                    if closes(&request.head) || closes(&reply_head) {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    addr
+}
+
+/// Reads one request, with a body of its `Content-Length`; `None` once the
+/// client hangs up.
+async fn read_request(socket: &mut TcpStream) -> Option<Received> {
+    let mut data = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let text = String::from_utf8_lossy(&data[..end]).into_owned();
+            let (line, head) = text.split_once("\r\n").unwrap_or((&text, ""));
+            let length: usize = header(head, "content-length")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+            if data.len() >= end + 4 + length {
+                return Some(Received {
+                    line: line.to_string(),
+                    sequence: 0,
+                    head: head.to_string(),
+                });
+            }
+        }
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// The first header called `name` among the lines of `head`.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+        let (field, value) = line.split_once(':')?;
+        field
+            .trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim())
+    })
+}
+
+fn closes(head: &str) -> bool {
+    header(head, "connection").is_some_and(|v| v.eq_ignore_ascii_case("close"))
+}
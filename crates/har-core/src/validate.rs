@@ -0,0 +1,395 @@
+//! HAR 1.2 validation.
+//!
+//! Runs the strict conformance walk over a document and adds the checks that
+//! need more than one field: timings adding up to `time`, date formats,
+//! `pageref` targets and size bookkeeping. Findings are plain
+//! [`Diagnostic`]s so the UI, the CLI and CI jobs can all consume them.
+
+use crate::compression;
+use crate::conformance::{Diagnostic, ParseMode, Severity};
+use crate::error::Result;
+use crate::har::{parse_date_time, HarEntry, HarFile};
+use crate::loader;
+use std::collections::HashSet;
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::rc::Rc;
+
+/// How far the sum of the phases may drift from `time`, in milliseconds.
+/// Exporters round each phase separately.
+const TIMING_TOLERANCE: f64 = 1.0;
+
+/// Validates a HAR document. Only a document that is not JSON at all, or
+/// whose overall shape cannot be repaired, fails; everything else is
+/// reported as a finding.
+pub fn validate_har<R: Read>(reader: R) -> Result<Vec<Diagnostic>> {
+    let mut findings = Vec::new();
+    let mut checks = EntryChecks::default();
+    let har = loader::stream_har(reader, ParseMode::Strict, &mut findings, |index, entry| {
+        checks.check(index, &entry);
+        ControlFlow::Continue(())
+    })?;
+    let EntryChecks {
+        findings: mut checked,
+        pagerefs,
+        ..
+    } = checks;
+    check_log(&har, &pagerefs, &mut checked);
+    // Cross-field checks see the values the walk filled in, so a field it
+    // already reported would be reported again for its default.
+    let repaired: HashSet<&str> = findings.iter().map(|f| f.json_path.as_str()).collect();
+    checked.retain(|f| !within_repaired(&repaired, &f.json_path));
+    findings.append(&mut checked);
+    findings.sort_by_key(|f| f.entry.map_or(0, |i| i + 1));
+    Ok(findings)
+}
+
+/// Whether `path`, or an object or array containing it, is in `repaired`.
+fn within_repaired(repaired: &HashSet<&str>, path: &str) -> bool {
+    repaired.contains(path)
+        || path
+            .match_indices(['.', '['])
+            .any(|(i, _)| repaired.contains(&path[..i]))
+}
+
+/// Validates a HAR file, decompressing it if needed; `member` picks the
+/// HAR inside a zip archive.
+pub fn validate_file(path: &Path, member: Option<&str>) -> Result<Vec<Diagnostic>> {
+    compression::with_decoded(path, member, Rc::default(), |reader, _| {
+        validate_har(reader).map_err(|e| e.with_path(path))
+    })
+}
+
+fn finding(
+    severity: Severity,
+    entry: Option<usize>,
+    json_path: String,
+    message: impl Into<String>,
+) -> Diagnostic {
+    Diagnostic {
+        severity,
+        entry,
+        json_path,
+        message: message.into(),
+    }
+}
+
+fn check_log(har: &HarFile, pagerefs: &[(usize, String)], findings: &mut Vec<Diagnostic>) {
+    let log = &har.log;
+    if log.version != "1.1" && log.version != "1.2" {
+        findings.push(finding(
+            Severity::Warning,
+            None,
+            "log.version".into(),
+            format!("unknown HAR version {:?}", log.version),
+        ));
+    }
+
+    let mut ids = HashSet::new();
+    for (i, page) in log.pages.iter().flatten().enumerate() {
+        if parse_date_time(&page.started_date_time).is_none() {
+            findings.push(finding(
+                Severity::Error,
+                None,
+                format!("log.pages[{i}].startedDateTime"),
+                format!("{:?} is not an ISO 8601 date-time", page.started_date_time),
+            ));
+        }
+        if !ids.insert(page.id.as_str()) {
+            findings.push(finding(
+                Severity::Error,
+                None,
+                format!("log.pages[{i}].id"),
+                format!("duplicate page id {:?}", page.id),
+            ));
+        }
+    }
+
+    for (index, pageref) in pagerefs {
+        if !ids.contains(pageref.as_str()) {
+            findings.push(finding(
+                Severity::Error,
+                Some(*index),
+                format!("log.entries[{index}].pageref"),
+                format!("no page has id {pageref:?}"),
+            ));
+        }
+    }
+}
+
+/// Per-entry checks, run as entries stream past. Page references are kept
+/// for the end since `pages` may come after `entries` in the document.
+#[derive(Default)]
+struct EntryChecks {
+    findings: Vec<Diagnostic>,
+    pagerefs: Vec<(usize, String)>,
+    last_started: Option<f64>,
+    reported_order: bool,
+}
+
+impl EntryChecks {
+    fn report(&mut self, severity: Severity, index: usize, path: &str, message: String) {
+        let json_path = format!("log.entries[{index}].{path}");
+        self.findings
+            .push(finding(severity, Some(index), json_path, message));
+    }
+
+    fn check(&mut self, index: usize, entry: &HarEntry) {
+        if let Some(pageref) = &entry.pageref {
+            self.pagerefs.push((index, pageref.clone()));
+        }
+
+        match entry.started_millis() {
+            None => self.report(
+                Severity::Error,
+                index,
+                "startedDateTime",
+                format!("{:?} is not an ISO 8601 date-time", entry.started_date_time),
+            ),
+            Some(started) => {
+                if self.last_started.is_some_and(|last| started < last) && !self.reported_order {
+                    self.reported_order = true;
+                    self.report(
+                        Severity::Info,
+                        index,
+                        "startedDateTime",
+                        "entries are not sorted by startedDateTime".into(),
+                    );
+                }
+                self.last_started = Some(started);
+            }
+        }
+
+        self.check_timings(index, entry);
+        self.check_sizes(index, entry);
+    }
+
+    fn check_timings(&mut self, index: usize, entry: &HarEntry) {
+        let timings = &entry.timings;
+        for (name, value) in [
+            ("blocked", timings.blocked),
+            ("dns", timings.dns),
+            ("connect", timings.connect),
+            ("ssl", timings.ssl),
+        ] {
+            if value.is_some_and(|v| v < 0.0 && v != -1.0) {
+                self.report(
+                    Severity::Error,
+                    index,
+                    &format!("timings.{name}"),
+                    format!("must be -1 or non-negative, got {}", value.unwrap()),
+                );
+            }
+        }
+        if let (Some(ssl), Some(connect)) = (timings.ssl, timings.connect) {
+            if ssl > connect && connect >= 0.0 {
+                self.report(
+                    Severity::Warning,
+                    index,
+                    "timings.ssl",
+                    format!("ssl ({ssl}) is included in connect but exceeds it ({connect})"),
+                );
+            }
+        }
+
+        // `ssl` is part of `connect`, so it does not count separately.
+        let phases = [
+            timings.blocked,
+            timings.dns,
+            timings.connect,
+            Some(timings.send),
+            Some(timings.wait),
+            Some(timings.receive),
+        ];
+        let sum: f64 = phases.into_iter().flatten().filter(|v| *v > 0.0).sum();
+        let ssl = timings.ssl.filter(|v| *v > 0.0).unwrap_or(0.0);
+        if (sum - entry.time).abs() <= TIMING_TOLERANCE {
+            return;
+        }
+        if ssl > 0.0 && (sum + ssl - entry.time).abs() <= TIMING_TOLERANCE {
+            // Firefox counts the handshake outside of `connect`.
+            self.report(
+                Severity::Info,
+                index,
+                "timings.ssl",
+                "ssl is counted separately from connect".into(),
+            );
+        } else {
+            self.report(
+                Severity::Warning,
+                index,
+                "time",
+                format!(
+                    "time is {:.3} ms but the timings add up to {sum:.3} ms",
+                    entry.time
+                ),
+            );
+        }
+    }
+
+    fn check_sizes(&mut self, index: usize, entry: &HarEntry) {
+        let request = &entry.request;
+        let response = &entry.response;
+        for (path, value) in [
+            ("request.headersSize", request.headers_size),
+            ("request.bodySize", request.body_size),
+            ("response.headersSize", response.headers_size),
+            ("response.bodySize", response.body_size),
+        ] {
+            if value < -1 {
+                self.report(
+                    Severity::Error,
+                    index,
+                    path,
+                    format!("must be -1 or non-negative, got {value}"),
+                );
+            }
+        }
+
+        let post_text = request
+            .post_data
+            .as_ref()
+            .and_then(|p| p.text.as_deref())
+            .unwrap_or("");
+        if request.body_size > 0 && request.post_data.is_none() {
+            self.report(
+                Severity::Warning,
+                index,
+                "request.bodySize",
+                format!("{} bytes sent but there is no postData", request.body_size),
+            );
+        } else if request.body_size == 0 && !post_text.is_empty() {
+            self.report(
+                Severity::Warning,
+                index,
+                "request.bodySize",
+                "0 but postData has text".into(),
+            );
+        }
+
+        let content = &response.content;
+        if let Some(compression) = content.compression {
+            if response.body_size >= 0 && content.size - compression != response.body_size {
+                self.report(
+                    Severity::Warning,
+                    index,
+                    "response.content.compression",
+                    format!(
+                        "size {} minus compression {compression} does not match bodySize {}",
+                        content.size, response.body_size
+                    ),
+                );
+            }
+        }
+        let bodiless = request.method.eq_ignore_ascii_case("HEAD")
+            || matches!(response.status, 100..=199 | 204 | 304);
+        if bodiless && response.body_size > 0 {
+            self.report(
+                Severity::Warning,
+                index,
+                "response.bodySize",
+                format!(
+                    "{} {} responses have no body but bodySize is {}",
+                    request.method, response.status, response.body_size
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const CHROME: &str = include_str!("../tests/fixtures/chrome.har");
+    const FIREFOX: &str = include_str!("../tests/fixtures/firefox.har");
+
+    fn errors(findings: &[Diagnostic]) -> Vec<&str> {
+        findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| f.json_path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn browser_exports_are_valid() {
+        for source in [CHROME, FIREFOX] {
+            let findings = validate_har(source.as_bytes()).unwrap();
+            assert!(errors(&findings).is_empty(), "{findings:#?}");
+        }
+    }
+
+    #[test]
+    fn reports_cross_field_violations() {
+        let mut doc: Value = serde_json::from_str(FIREFOX).unwrap();
+        let entry = &mut doc["log"]["entries"][1];
+        entry["pageref"] = "page_9".into();
+        entry["startedDateTime"] = "14/05/2024 11:40".into();
+        entry["time"] = 500.into();
+        entry["request"]["headersSize"] = (-7).into();
+        doc["log"]["entries"][2]["request"]["bodySize"] = 0.into();
+
+        let findings = validate_har(doc.to_string().as_bytes()).unwrap();
+        assert_eq!(
+            errors(&findings),
+            [
+                "log.entries[1].startedDateTime",
+                "log.entries[1].request.headersSize",
+                "log.entries[1].pageref",
+            ]
+        );
+        let warnings: Vec<&str> = findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning)
+            .map(|f| f.json_path.as_str())
+            .collect();
+        assert!(warnings.contains(&"log.entries[1].time"));
+        assert!(warnings.contains(&"log.entries[2].request.bodySize"));
+    }
+
+    #[test]
+    fn findings_point_past_skipped_entries() {
+        let mut doc: Value = serde_json::from_str(FIREFOX).unwrap();
+        let mut entry = doc["log"]["entries"][0].clone();
+        entry["time"] = 99_999.into();
+        doc["log"]["entries"] = Value::Array(vec!["garbage".into(), entry]);
+
+        let findings = validate_har(doc.to_string().as_bytes()).unwrap();
+        let paths: Vec<(Option<usize>, &str)> = findings
+            .iter()
+            .map(|f| (f.entry, f.json_path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (Some(0), "log.entries[0]"),
+                (Some(1), "log.entries[1].time")
+            ]
+        );
+    }
+
+    #[test]
+    fn repaired_fields_are_reported_once() {
+        let mut doc: Value = serde_json::from_str(FIREFOX).unwrap();
+        for pointer in ["/log/entries/0", "/log/pages/0"] {
+            let object = doc.pointer_mut(pointer).unwrap().as_object_mut().unwrap();
+            object.remove("startedDateTime");
+        }
+
+        let findings = validate_har(doc.to_string().as_bytes()).unwrap();
+        for path in [
+            "log.pages[0].startedDateTime",
+            "log.entries[0].startedDateTime",
+        ] {
+            let found: Vec<&str> = findings
+                .iter()
+                .filter(|f| f.json_path == path)
+                .map(|f| f.message.as_str())
+                .collect();
+            assert_eq!(found, [r#"required field is missing; using """#], "{path}");
+        }
+    }
+}
@@ -3,210 +3,612 @@
     windows_subsystem = "windows"
 )]
 
-use serde::{Deserialize, Serialize};
-use std::fs;
+//! Tauri commands over the `har-core` engine.
 
-#[derive(Debug, Serialize, Deserialize)]
-struct HarFile {
-    log: HarLog,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarLog {
-    version: String,
-    creator: HarCreator,
-    #[serde(default)]
-    browser: Option<HarBrowser>,
-    pages: Option<Vec<HarPage>>,
-    entries: Vec<HarEntry>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarCreator {
-    name: String,
-    version: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarBrowser {
-    name: String,
-    version: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarPage {
-    id: String,
-    title: String,
-    started_date_time: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarEntry {
-    #[serde(rename = "startedDateTime")]
-    started_date_time: String,
-    time: f64,
-    request: HarRequest,
-    response: HarResponse,
-    cache: HarCache,
-    timings: HarTimings,
-    #[serde(default)]
-    server_ip_address: Option<String>,
-    #[serde(default)]
-    connection: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarRequest {
-    method: String,
-    url: String,
-    http_version: String,
-    cookies: Vec<HarCookie>,
-    headers: Vec<HarHeader>,
-    query_string: Vec<HarQueryString>,
-    post_data: Option<HarPostData>,
-    headers_size: i64,
-    body_size: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarResponse {
-    status: i64,
-    status_text: String,
-    http_version: String,
-    cookies: Vec<HarCookie>,
-    headers: Vec<HarHeader>,
-    content: HarContent,
-    redirect_url: String,
-    headers_size: i64,
-    body_size: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarCookie {
-    name: String,
-    value: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarHeader {
-    name: String,
-    value: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarQueryString {
-    name: String,
-    value: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarPostData {
-    mime_type: String,
-    text: Option<String>,
-    params: Option<Vec<HarParam>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarParam {
-    name: String,
-    value: Option<String>,
-    file_name: Option<String>,
-    content_type: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarContent {
-    size: i64,
-    mime_type: String,
-    text: Option<String>,
-    encoding: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarCache {
-    #[serde(default)]
-    before_request: Option<HarCacheState>,
-    #[serde(default)]
-    after_request: Option<HarCacheState>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarCacheState {
-    expires: Option<String>,
-    last_access: String,
-    etag: String,
-    hit_count: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct HarTimings {
-    blocked: f64,
-    dns: f64,
-    connect: f64,
-    send: f64,
-    wait: f64,
-    receive: f64,
-    ssl: f64,
-}
-
-#[tauri::command]
-async fn load_har_file(path: String) -> Result<HarFile, String> {
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let har_file: HarFile = serde_json::from_str(&content).map_err(|e| e.to_string())?;
-    Ok(har_file)
+use har_core::batch::{self, BatchOptions, BatchSummary};
+use har_core::ca::CertificateAuthority;
+use har_core::client::{Client, ClientConfig};
+use har_core::compression::{self, Compression};
+use har_core::cookies::CookieJar;
+use har_core::diff::{self, DiffOptions, ResponseDiff};
+use har_core::environment::Environment;
+use har_core::flow::{self, FlowOptions, StepLog};
+use har_core::form::Attachments;
+use har_core::group::{self, Group, GroupBy};
+use har_core::har::HarRequest;
+use har_core::mock::{MockOptions, MockRequest, MockServer};
+use har_core::pages::{self, PageMetrics};
+use har_core::proxy::RecordingProxy;
+use har_core::replay;
+use har_core::schedule::{self, ScheduleOptions, ScheduleReport};
+use har_core::summary::{self, Summary};
+use har_core::{
+    loader, validate, Diagnostic, Error, ErrorKind, HarEntry, HarFile, HarSession, LoadOptions,
+    ParseMode, Result,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[derive(Default)]
+struct AppState {
+    session: RwLock<Option<HarSession>>,
+    cancel_load: Arc<AtomicBool>,
+    /// Stops the running batch or scheduled replay.
+    cancel_replay: Arc<AtomicBool>,
+    /// Shared by replays so connections are reused; rebuilt when the replay
+    /// options change.
+    client: Mutex<Option<Client>>,
+    /// Cookies carried between replays that opt in to the jar.
+    cookie_jar: Mutex<CookieJar>,
+    mock: Mutex<Option<MockServer>>,
+    recording: Mutex<Option<RecordingProxy>>,
+}
+
+impl AppState {
+    fn client(&self, config: ClientConfig) -> Result<Client> {
+        let mut client = self.client.lock().unwrap();
+        match client.as_ref() {
+            Some(existing) if *existing.config() == config => Ok(existing.clone()),
+            _ => Ok(client.insert(Client::with_config(config)?).clone()),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct EntryPage<'a> {
+    offset: usize,
+    entries: &'a [HarEntry],
+}
+
+/// A fully parsed archive; serializes as the HAR document plus a
+/// `diagnostics` list.
+#[derive(Serialize)]
+struct LoadedHar {
+    #[serde(flatten)]
+    har: HarFile,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[tauri::command]
+async fn load_har_file(
+    path: String,
+    member: Option<String>,
+    mode: Option<ParseMode>,
+) -> Result<LoadedHar> {
+    let (har, diagnostics) = tauri::async_runtime::spawn_blocking(move || {
+        loader::read_har_file(
+            Path::new(&path),
+            member.as_deref(),
+            mode.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(Error::internal)??;
+    Ok(LoadedHar { har, diagnostics })
+}
+
+/// Checks a HAR file against the HAR 1.2 rules without loading it into the
+/// session.
+#[tauri::command]
+async fn validate_har(path: String, member: Option<String>) -> Result<Vec<Diagnostic>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        validate::validate_file(Path::new(&path), member.as_deref())
+    })
+    .await
+    .map_err(Error::internal)?
 }
 
-#[tauri::command]
-async fn replay_request(request: HarRequest) -> Result<String, String> {
-    let client = reqwest::Client::new();
-
-    let method = match request.method.as_str() {
-        "GET" => reqwest::Method::GET,
-        "POST" => reqwest::Method::POST,
-        "PUT" => reqwest::Method::PUT,
-        "DELETE" => reqwest::Method::DELETE,
-        "HEAD" => reqwest::Method::HEAD,
-        "OPTIONS" => reqwest::Method::OPTIONS,
-        "PATCH" => reqwest::Method::PATCH,
-        _ => return Err(format!("Unsupported method: {}", request.method)),
+/// Lists the HAR files inside a zip archive, or nothing for other files.
+#[tauri::command]
+fn list_har_archive_members(path: String) -> Result<Vec<String>> {
+    compression::list_archive_members(Path::new(&path))
+}
+
+/// Loads a HAR file incrementally, emitting `har-load-page` and
+/// `har-load-progress` events as entries arrive. Resolves with the archive
+/// minus its entries once the whole file has been read; in lenient and
+/// strict `mode` the findings are available from `get_har_diagnostics`.
+/// The replay cookie jar starts over from the new archive's cookies.
+#[tauri::command]
+async fn open_har_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    member: Option<String>,
+    mode: Option<ParseMode>,
+) -> Result<HarFile> {
+    let cancel = Arc::clone(&state.cancel_load);
+    cancel.store(false, Ordering::Relaxed);
+
+    let mut session = tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let options = LoadOptions {
+            member,
+            mode: mode.unwrap_or_default(),
+            ..LoadOptions::default()
+        };
+        HarSession::open(path, &options, &cancel, |offset, entries, progress| {
+            let _ = app.emit("har-load-page", EntryPage { offset, entries });
+            let _ = app.emit("har-load-progress", progress);
+        })
+        .map_err(|e| match e.context.path {
+            Some(_) => e,
+            None => e.with_path(path),
+        })
+    })
+    .await
+    .map_err(Error::internal)??;
+
+    let entries = std::mem::take(&mut session.har.log.entries);
+    let header = session.har.clone();
+    session.har.log.entries = entries;
+    *state.cookie_jar.lock().unwrap() = CookieJar::from_entries(&session.har.log.entries);
+    *state.session.write().unwrap() = Some(session);
+    Ok(header)
+}
+
+/// Saves the loaded archive. Without an explicit `compression` the format
+/// follows the file extension (`.gz`, `.zst`, `.br`, `.zip`).
+#[tauri::command]
+async fn save_har_file(
+    state: State<'_, AppState>,
+    path: String,
+    compression: Option<Compression>,
+) -> Result<()> {
+    let path = PathBuf::from(path);
+    let compression = compression.unwrap_or_else(|| Compression::from_extension(&path));
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    session.save(&path, compression)
+}
+
+#[tauri::command]
+fn cancel_har_load(state: State<'_, AppState>) {
+    state.cancel_load.store(true, Ordering::Relaxed);
+}
+
+#[tauri::command]
+fn get_har_entries(
+    state: State<'_, AppState>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<HarEntry>> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    Ok(session.entries(offset, limit).to_vec())
+}
+
+/// Totals and timing percentiles over the whole open archive. Like the
+/// other commands that walk every entry, it runs on the async runtime so a
+/// large archive does not freeze the window.
+#[tauri::command(async)]
+fn summarize_har(state: State<'_, AppState>) -> Result<Summary> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    Ok(summary::summarize(&session.har))
+}
+
+/// Statistics per host or per endpoint over the entries at `indices`, or
+/// the whole open archive, slowest group first.
+#[tauri::command(async)]
+fn group_har_entries(
+    state: State<'_, AppState>,
+    by: Option<GroupBy>,
+    indices: Option<Vec<usize>>,
+) -> Result<Vec<Group>> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    let entries = &session.har.log.entries;
+    let by = by.unwrap_or_default();
+    Ok(match indices {
+        Some(indices) => group::group(
+            indices
+                .into_iter()
+                .filter_map(|index| Some((index, entries.get(index)?))),
+            by,
+        ),
+        None => group::group(entries.iter().enumerate(), by),
+    })
+}
+
+/// Load metrics for each page of the open archive.
+#[tauri::command(async)]
+fn get_page_metrics(state: State<'_, AppState>) -> Result<Vec<PageMetrics>> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    Ok(pages::page_metrics(&session.har))
+}
+
+/// Sets the `pageref` of entries without a page to the page that was
+/// loading when they started, and returns how many changed.
+#[tauri::command(async)]
+fn assign_pages(state: State<'_, AppState>) -> Result<usize> {
+    let mut session = state.session.write().unwrap();
+    let session = session.as_mut().ok_or_else(Error::not_loaded)?;
+    Ok(pages::assign_pages(&mut session.har))
+}
+
+#[tauri::command]
+fn get_har_diagnostics(state: State<'_, AppState>) -> Result<Vec<Diagnostic>> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    Ok(session.diagnostics.clone())
+}
+
+#[tauri::command]
+fn get_response_body(state: State<'_, AppState>, index: usize) -> Result<Option<String>> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    session.response_body(index)
+}
+
+/// Replays `request` and returns the resulting entry. `attachments` maps the
+/// names of file params to local files to upload in place of contents the
+/// archive did not capture. Replays share one client, and so its
+/// connections, until `options` change. An `environment` fills in
+/// placeholders and retargets the request first; a placeholder it has no
+/// value for fails the replay before anything is sent.
+#[tauri::command]
+async fn replay_request(
+    state: State<'_, AppState>,
+    mut request: HarRequest,
+    attachments: Option<Attachments>,
+    options: Option<ClientConfig>,
+    use_cookie_jar: Option<bool>,
+    environment: Option<Environment>,
+) -> Result<HarEntry> {
+    let client = state.client(options.unwrap_or_default())?;
+    let attachments = attachments.unwrap_or_default();
+    if let Some(environment) = &environment {
+        environment.apply_strict(&mut request)?;
+    }
+    if !use_cookie_jar.unwrap_or(false) {
+        return replay::replay_request(&client, &request, &attachments, None).await;
+    }
+    // The jar is copied out for the replay and merged back after it, so a
+    // lock is not held across it.
+    let mut jar = state.cookie_jar.lock().unwrap().clone();
+    let entry = replay::replay_request(&client, &request, &attachments, Some(&mut jar)).await?;
+    *state.cookie_jar.lock().unwrap() = jar;
+    Ok(entry)
+}
+
+/// Compares `replayed` with the recorded entry at `index`.
+#[tauri::command]
+fn diff_replay(
+    state: State<'_, AppState>,
+    index: usize,
+    replayed: HarEntry,
+    options: Option<DiffOptions>,
+) -> Result<ResponseDiff> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    let mut recorded = session
+        .entries(index, 1)
+        .first()
+        .cloned()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidRequest, format!("No entry {index}")))?;
+    recorded.response.content.text = session.response_body(index)?;
+    diff::diff_responses(&recorded, &replayed, &options.unwrap_or_default())
+}
+
+/// Replays entries `first` to `last` of the open archive in order, emitting
+/// a `flow-step` event with each step's log as it completes.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn replay_flow(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    first: usize,
+    last: usize,
+    flow: FlowOptions,
+    attachments: Option<Attachments>,
+    options: Option<ClientConfig>,
+    use_cookie_jar: Option<bool>,
+) -> Result<Vec<StepLog>> {
+    let steps = {
+        let session = state.session.read().unwrap();
+        let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+        let entries = session.entries(first, (last + 1).saturating_sub(first));
+        let mut steps = entries.to_vec();
+        for (index, step) in (first..).zip(&mut steps) {
+            step.response.content.text = session.response_body(index)?;
+        }
+        steps
     };
+    let client = state.client(options.unwrap_or_default())?;
+    let attachments = attachments.unwrap_or_default();
+    let mut jar = use_cookie_jar
+        .unwrap_or(false)
+        .then(|| state.cookie_jar.lock().unwrap().clone());
+    let logs = flow::replay_flow(&client, &steps, &flow, &attachments, jar.as_mut(), |log| {
+        let _ = app.emit("flow-step", log);
+    })
+    .await?;
+    if let Some(jar) = jar {
+        *state.cookie_jar.lock().unwrap() = jar;
+    }
+    Ok(logs)
+}
 
-    let mut req_builder = client.request(method, &request.url);
+/// Replays the entries at `indices` independently, emitting a
+/// `batch-progress` event as each completes, until done or cancelled with
+/// `cancel_replay`. An `environment` fills in placeholders and retargets
+/// every request first; a placeholder it has no value for fails the batch
+/// before anything is sent.
+#[tauri::command]
+async fn replay_batch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    indices: Vec<usize>,
+    batch: BatchOptions,
+    attachments: Option<Attachments>,
+    options: Option<ClientConfig>,
+    environment: Option<Environment>,
+) -> Result<BatchSummary> {
+    let entries = {
+        let session = state.session.read().unwrap();
+        let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+        let count = session.har.log.entries.len();
+        indices
+            .into_iter()
+            .map(|index| {
+                let mut entry = session.entries(index, 1).first().cloned().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidRequest,
+                        format!("No entry {index}; the archive has {count}"),
+                    )
+                })?;
+                if let Some(environment) = &environment {
+                    environment.apply_strict(&mut entry.request)?;
+                }
+                Ok((index, entry))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    let client = state.client(options.unwrap_or_default())?;
+    let cancel = Arc::clone(&state.cancel_replay);
+    cancel.store(false, Ordering::Relaxed);
+    let summary = batch::replay_batch(
+        &client,
+        entries,
+        &batch,
+        &attachments.unwrap_or_default(),
+        &cancel,
+        |progress| {
+            let _ = app.emit("batch-progress", progress);
+        },
+    )
+    .await;
+    Ok(summary)
+}
 
-    for header in request.headers {
-        if let Ok(header_name) = reqwest::header::HeaderName::from_bytes(header.name.as_bytes()) {
-            if let Ok(header_value) = reqwest::header::HeaderValue::from_str(&header.value) {
-                req_builder = req_builder.header(header_name, header_value);
+/// Replays the whole archive with the recorded gaps between requests,
+/// divided by `schedule.speed`, emitting a `schedule-progress` event as each
+/// request completes, until done or cancelled with `cancel_replay`. An
+/// `environment` applies as in `replay_batch`.
+#[tauri::command]
+async fn replay_schedule(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    schedule: ScheduleOptions,
+    attachments: Option<Attachments>,
+    options: Option<ClientConfig>,
+    environment: Option<Environment>,
+) -> Result<ScheduleReport> {
+    let entries = {
+        let session = state.session.read().unwrap();
+        let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+        let mut entries: Vec<_> = session
+            .har
+            .log
+            .entries
+            .iter()
+            .cloned()
+            .enumerate()
+            .collect();
+        if let Some(environment) = &environment {
+            for (_, entry) in &mut entries {
+                environment.apply_strict(&mut entry.request)?;
             }
         }
-    }
+        entries
+    };
+    let client = state.client(options.unwrap_or_default())?;
+    let cancel = Arc::clone(&state.cancel_replay);
+    cancel.store(false, Ordering::Relaxed);
+    schedule::replay_schedule(
+        &client,
+        entries,
+        &schedule,
+        &attachments.unwrap_or_default(),
+        &cancel,
+        |progress| {
+            let _ = app.emit("schedule-progress", progress);
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+fn cancel_replay(state: State<'_, AppState>) {
+    state.cancel_replay.store(true, Ordering::Relaxed);
+}
 
-    if let Some(post_data) = request.post_data {
-        if let Some(text) = post_data.text {
-            req_builder = req_builder.body(text);
+/// Serves the open archive's responses on `port` of localhost, or a free
+/// port, emitting a `mock-request` event for each request received, and
+/// returns the address. A server already running is stopped first.
+#[tauri::command]
+async fn start_mock_server(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    port: Option<u16>,
+    options: Option<MockOptions>,
+) -> Result<String> {
+    let running = state.mock.lock().unwrap().take();
+    if let Some(server) = running {
+        server.stop().await;
+    }
+    let entries = {
+        let session = state.session.read().unwrap();
+        let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+        let mut entries: Vec<_> = session
+            .har
+            .log
+            .entries
+            .iter()
+            .cloned()
+            .enumerate()
+            .collect();
+        for (index, entry) in &mut entries {
+            entry.response.content.text = session.response_body(*index)?;
         }
+        entries
+    };
+    let server = MockServer::start(
+        ([127, 0, 0, 1], port.unwrap_or(0)).into(),
+        entries,
+        options.unwrap_or_default(),
+        move |request| {
+            let _ = app.emit("mock-request", request);
+        },
+    )
+    .await?;
+    let addr = server.addr();
+    *state.mock.lock().unwrap() = Some(server);
+    Ok(format!("http://{addr}"))
+}
+
+/// Stops the mock server and returns the requests it had no response for.
+#[tauri::command]
+async fn stop_mock_server(state: State<'_, AppState>) -> Result<Vec<MockRequest>> {
+    let running = state.mock.lock().unwrap().take();
+    let Some(server) = running else {
+        return Ok(Vec::new());
+    };
+    let unmatched = server.unmatched();
+    server.stop().await;
+    Ok(unmatched)
+}
+
+/// A recording proxy that has started.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Recording {
+    address: String,
+    /// The certificate clients must trust for HTTPS to be recorded.
+    ca_certificate: PathBuf,
+    /// The new, empty archive recorded into when none was open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    har: Option<HarFile>,
+}
+
+#[derive(Clone, Serialize)]
+struct RecordedEntry<'a> {
+    index: usize,
+    entry: &'a HarEntry,
+}
+
+/// Starts a recording proxy on `port` of localhost, or a free port, that
+/// appends each exchange to the open archive, or to a new one, emitting a
+/// `proxy-entry` event for each. The certificate authority is created in
+/// the app's data directory on first use. A proxy already running is
+/// stopped first.
+#[tauri::command]
+async fn start_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    port: Option<u16>,
+    options: Option<ClientConfig>,
+) -> Result<Recording> {
+    let running = state.recording.lock().unwrap().take();
+    if let Some(proxy) = running {
+        proxy.stop().await;
     }
+    let dir = app.path().app_data_dir().map_err(Error::internal)?;
+    let ca = CertificateAuthority::load_or_create(&dir)?;
+    let ca_certificate = ca.certificate_path().to_path_buf();
+    let client = state.client(options.unwrap_or_default())?;
+    let har = {
+        let mut session = state.session.write().unwrap();
+        match session.as_ref() {
+            Some(_) => None,
+            None => {
+                let har = HarFile::new("HAR Analyser", env!("CARGO_PKG_VERSION"));
+                *session = Some(HarSession::new(har.clone())?);
+                Some(har)
+            }
+        }
+    };
+    let proxy = RecordingProxy::start(
+        ([127, 0, 0, 1], port.unwrap_or(0)).into(),
+        Arc::new(ca),
+        client,
+        move |entry| {
+            let state = app.state::<AppState>();
+            let mut session = state.session.write().unwrap();
+            let Some(session) = session.as_mut() else {
+                return;
+            };
+            if let Ok(index) = session.push_entry(entry.clone()) {
+                let entry = &session.entries(index, 1)[0];
+                let _ = app.emit("proxy-entry", RecordedEntry { index, entry });
+            }
+        },
+    )
+    .await?;
+    let address = format!("http://{}", proxy.addr());
+    *state.recording.lock().unwrap() = Some(proxy);
+    Ok(Recording {
+        address,
+        ca_certificate,
+        har,
+    })
+}
 
-    let response = req_builder.send().await.map_err(|e| e.to_string())?;
-    let status = response.status().as_u16();
-    let headers = response.headers().clone();
-    let body = response.text().await.map_err(|e| e.to_string())?;
+/// Stops the recording proxy and returns how many exchanges it recorded.
+#[tauri::command]
+async fn stop_recording(state: State<'_, AppState>) -> Result<usize> {
+    let running = state.recording.lock().unwrap().take();
+    let Some(proxy) = running else {
+        return Ok(0);
+    };
+    let recorded = proxy.entries().len();
+    proxy.stop().await;
+    Ok(recorded)
+}
 
-    let header_vec: Vec<HarHeader> = headers
-        .iter()
-        .map(|(name, value)| HarHeader {
-            name: name.to_string(),
-            value: value.to_str().unwrap_or("").to_string(),
-        })
-        .collect();
+/// The cookies replays that use the jar will send.
+#[tauri::command]
+fn get_cookie_jar(state: State<'_, AppState>) -> CookieJar {
+    state.cookie_jar.lock().unwrap().clone()
+}
 
-    let response_data = serde_json::json!({
-        "status": status,
-        "headers": header_vec,
-        "body": body,
-    });
+/// Replaces the jar with the user's edited copy.
+#[tauri::command]
+fn set_cookie_jar(state: State<'_, AppState>, jar: CookieJar) {
+    *state.cookie_jar.lock().unwrap() = jar;
+}
+
+/// Refills the jar with the cookies the open archive ends with.
+#[tauri::command]
+fn reset_cookie_jar(state: State<'_, AppState>) -> Result<CookieJar> {
+    let session = state.session.read().unwrap();
+    let session = session.as_ref().ok_or_else(Error::not_loaded)?;
+    let jar = CookieJar::from_entries(&session.har.log.entries);
+    *state.cookie_jar.lock().unwrap() = jar.clone();
+    Ok(jar)
+}
 
-    Ok(response_data.to_string())
+/// Adds `entry` to the open archive and returns its index.
+#[tauri::command]
+fn append_har_entry(state: State<'_, AppState>, entry: HarEntry) -> Result<usize> {
+    let mut session = state.session.write().unwrap();
+    let session = session.as_mut().ok_or_else(Error::not_loaded)?;
+    session.push_entry(entry)
 }
 
 fn main() {
@@ -215,7 +617,36 @@ fn main() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![load_har_file, replay_request])
+        .manage(AppState::default())
+        .invoke_handler(tauri::generate_handler![
+            load_har_file,
+            validate_har,
+            list_har_archive_members,
+            open_har_file,
+            save_har_file,
+            cancel_har_load,
+            get_har_entries,
+            get_har_diagnostics,
+            summarize_har,
+            group_har_entries,
+            get_page_metrics,
+            assign_pages,
+            get_response_body,
+            replay_request,
+            replay_flow,
+            replay_batch,
+            replay_schedule,
+            cancel_replay,
+            start_mock_server,
+            stop_mock_server,
+            start_recording,
+            stop_recording,
+            diff_replay,
+            append_har_entry,
+            get_cookie_jar,
+            set_cookie_jar,
+            reset_cookie_jar
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
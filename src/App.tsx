@@ -1,7 +1,7 @@
 import React, { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
-import { readTextFile } from "@tauri-apps/plugin-fs";
+import { listen } from "@tauri-apps/api/event";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "./components/ui/tabs";
 import { Button } from "./components/ui/button";
 import { ScrollArea } from "./components/ui/scroll-area";
@@ -40,9 +40,16 @@ interface HarPage {
   id: string;
   title: string;
   startedDateTime: string;
+  pageTimings: HarPageTimings;
+}
+
+interface HarPageTimings {
+  onContentLoad?: number;
+  onLoad?: number;
 }
 
 interface HarEntry {
+  pageref?: string;
   startedDateTime: string;
   time: number;
   request: HarRequest;
@@ -80,6 +87,11 @@ interface HarResponse {
 interface HarCookie {
   name: string;
   value: string;
+  path?: string;
+  domain?: string;
+  expires?: string;
+  httpOnly?: boolean;
+  secure?: boolean;
 }
 
 interface HarHeader {
@@ -107,6 +119,7 @@ interface HarParam {
 
 interface HarContent {
   size: number;
+  compression?: number;
   mimeType: string;
   text?: string;
   encoding?: string;
@@ -124,21 +137,308 @@ interface HarCacheState {
   hitCount: number;
 }
 
+interface AppError {
+  kind: string;
+  message: string;
+  context: {
+    path?: string;
+    line?: number;
+    column?: number;
+    jsonPath?: string;
+    url?: string;
+    method?: string;
+  };
+}
+
+interface LoadProgress {
+  bytesRead: number;
+  totalBytes: number;
+  entries: number;
+}
+
+type ParseMode = "standard" | "lenient" | "strict";
+
+interface Diagnostic {
+  severity: "info" | "warning" | "error";
+  entry?: number;
+  jsonPath: string;
+  message: string;
+}
+
+interface EntryPage {
+  offset: number;
+  entries: HarEntry[];
+}
+
 interface HarTimings {
-  blocked: number;
-  dns: number;
-  connect: number;
+  blocked?: number;
+  dns?: number;
+  connect?: number;
   send: number;
   wait: number;
   receive: number;
-  ssl: number;
+  ssl?: number;
+}
+
+interface StoredCookie {
+  name: string;
+  value: string;
+  domain: string;
+  hostOnly: boolean;
+  path: string;
+  expires?: string;
+  secure: boolean;
+  httpOnly: boolean;
+}
+
+interface Extraction {
+  name: string;
+  step: number;
+  source: { kind: "jsonPath" | "regex" | "header"; expression: string };
+  replaceRecorded: boolean;
+}
+
+interface StepLog {
+  step: number;
+  method: string;
+  url: string;
+  entry?: HarEntry;
+  error?: AppError;
+  unresolved: string[];
+  extracted: { name: string; value: string | null }[];
+}
+
+interface BatchOptions {
+  concurrency: number;
+  requestsPerSecond: number | null;
+  retries: number;
+  retryDelayMs: number;
+}
+
+interface BatchItem {
+  index: number;
+  method: string;
+  url: string;
+  outcome: "passed" | "failed" | "error" | "cancelled";
+  recordedStatus: number;
+  status?: number;
+  attempts: number;
+  time: number;
+  error?: AppError;
+}
+
+interface BatchSummary {
+  items: BatchItem[];
+  passed: number;
+  failed: number;
+  errors: number;
+  cancelled: number;
+  elapsed: number;
+}
+
+interface ScheduledItem {
+  index: number;
+  method: string;
+  url: string;
+  recordedOffset: number;
+  plannedOffset: number;
+  achievedOffset?: number;
+  recordedStatus: number;
+  status?: number;
+  time?: number;
+  error?: AppError;
+}
+
+interface Distribution {
+  count: number;
+  min: number;
+  mean: number;
+  p50: number;
+  p90: number;
+  p99: number;
+  max: number;
+}
+
+interface Summary {
+  creator: string;
+  version: string;
+  pages: number;
+  entries: number;
+  started?: string;
+  spanMs: number;
+  contentBytes: number;
+  transferBytes: number;
+  statuses: Record<string, number>;
+  mimeTypes: Record<string, number>;
+  methods: Record<string, number>;
+  time: Distribution;
+  timings: Record<"blocked" | "dns" | "connect" | "ssl" | "send" | "wait" | "receive", Distribution>;
+}
+
+interface PageMetrics {
+  id: string;
+  title: string;
+  startedDateTime: string;
+  onContentLoad?: number;
+  onLoad?: number;
+  requests: number;
+  contentBytes: number;
+  transferBytes: number;
+  document?: number;
+  documentTtfb?: number;
+  finished: number;
+  longestChain: { entries: number[]; time: number };
+  entries: number[];
+}
+
+interface Group {
+  key: string;
+  host: string;
+  method?: string;
+  path?: string;
+  count: number;
+  errors: number;
+  errorRate: number;
+  time: Distribution;
+  requestBytes: number;
+  responseBytes: number;
+  responseSize: Distribution;
+  entries: number[];
+}
+
+interface ScheduleReport {
+  speed: number;
+  items: ScheduledItem[];
+  cancelled: number;
+  errors: number;
+  lag: Distribution;
+  responseTime: Distribution;
+  recordedTime: Distribution;
+}
+
+interface MockOptions {
+  strategy: "sequential" | "first" | "last";
+  matchBody: boolean;
+  cors: boolean;
+}
+
+interface MockRequest {
+  method: string;
+  url: string;
+  entry?: number;
+  status: number;
+}
+
+interface Recording {
+  address: string;
+  caCertificate: string;
+  har?: HarFile;
+}
+
+interface RecordedEntry {
+  index: number;
+  entry: HarEntry;
+}
+
+interface ResponseDiff {
+  same: boolean;
+  status?: { recorded: number; replayed: number };
+  headers: { name: string; recorded: string | null; replayed: string | null }[];
+  body:
+    | { kind: "same" }
+    | { kind: "unavailable" }
+    | { kind: "json"; changes: { path: string; recorded?: unknown; replayed?: unknown }[] }
+    | { kind: "text"; lines: { op: "equal" | "removed" | "added"; text: string }[] }
+    | { kind: "binary"; recordedSize: number; replayedSize: number };
+  timing: {
+    recorded: number;
+    replayed: number;
+    delta: number;
+    phases: { name: string; recorded: number; replayed: number; delta: number }[];
+  };
+}
+
+interface Retarget {
+  scheme?: string;
+  host?: string;
+  port?: number;
+  stripPrefix?: string;
+  addPrefix?: string;
+  hosts: string[];
+}
+
+interface Environment {
+  name: string;
+  variables: Record<string, string>;
+  retarget: Retarget;
+}
+
+interface ClientConfig {
+  connectTimeoutMs?: number;
+  timeoutMs?: number;
+  maxRedirects: number;
+  acceptInvalidCerts: boolean;
+  caBundle?: string;
+  clientCert?: string;
+  clientKey?: string;
+  proxy?: string;
+  httpVersion: "auto" | "http1" | "http2";
 }
 
 function App() {
   const [harFile, setHarFile] = useState<HarFile | null>(null);
   const [selectedEntry, setSelectedEntry] = useState<HarEntry | null>(null);
   const [editedRequest, setEditedRequest] = useState<HarRequest | null>(null);
-  const [replayResponse, setReplayResponse] = useState<any>(null);
+  const [replayResponse, setReplayResponse] = useState<HarEntry | null>(null);
+  const [attachments, setAttachments] = useState<Record<string, string>>({});
+  const [replayDiff, setReplayDiff] = useState<ResponseDiff | null>(null);
+  const [ignorePaths, setIgnorePaths] = useState<string>("");
+  const [clientConfig, setClientConfig] = useState<ClientConfig>({
+    maxRedirects: 10,
+    acceptInvalidCerts: false,
+    httpVersion: "auto",
+  });
+  const [useCookieJar, setUseCookieJar] = useState<boolean>(false);
+  const [cookieJar, setCookieJar] = useState<StoredCookie[]>([]);
+  const [showFlow, setShowFlow] = useState<boolean>(false);
+  const [flowRange, setFlowRange] = useState<{ first: number; last: number }>({ first: 0, last: 0 });
+  const [extractions, setExtractions] = useState<Extraction[]>([]);
+  const [continueOnError, setContinueOnError] = useState<boolean>(false);
+  const [flowLogs, setFlowLogs] = useState<StepLog[]>([]);
+  const [flowRunning, setFlowRunning] = useState<boolean>(false);
+  const [showBatch, setShowBatch] = useState<boolean>(false);
+  const [batchOptions, setBatchOptions] = useState<BatchOptions>({ concurrency: 4, requestsPerSecond: null, retries: 0, retryDelayMs: 500 });
+  const [batchItems, setBatchItems] = useState<BatchItem[]>([]);
+  const [batchTotal, setBatchTotal] = useState<number>(0);
+  const [batchSummary, setBatchSummary] = useState<BatchSummary | null>(null);
+  const [batchRunning, setBatchRunning] = useState<boolean>(false);
+  const [showSchedule, setShowSchedule] = useState<boolean>(false);
+  const [scheduleSpeed, setScheduleSpeed] = useState<number>(1);
+  const [scheduleItems, setScheduleItems] = useState<ScheduledItem[]>([]);
+  const [scheduleReport, setScheduleReport] = useState<ScheduleReport | null>(null);
+  const [scheduleRunning, setScheduleRunning] = useState<boolean>(false);
+  const [showMock, setShowMock] = useState<boolean>(false);
+  const [mockPort, setMockPort] = useState<number>(8080);
+  const [mockOptions, setMockOptions] = useState<MockOptions>({ strategy: "sequential", matchBody: false, cors: true });
+  const [mockAddress, setMockAddress] = useState<string | null>(null);
+  const [mockRequests, setMockRequests] = useState<MockRequest[]>([]);
+  const [showRecord, setShowRecord] = useState<boolean>(false);
+  const [recordPort, setRecordPort] = useState<number>(8888);
+  const [recording, setRecording] = useState<Recording | null>(null);
+  const [recordedCount, setRecordedCount] = useState<number>(0);
+  const [loadProgress, setLoadProgress] = useState<LoadProgress | null>(null);
+  const [summary, setSummary] = useState<Summary | null>(null);
+  const [showEndpoints, setShowEndpoints] = useState<boolean>(false);
+  const [groupBy, setGroupBy] = useState<"endpoint" | "host">("endpoint");
+  const [groups, setGroups] = useState<Group[]>([]);
+  const [showPages, setShowPages] = useState<boolean>(false);
+  const [pageMetrics, setPageMetrics] = useState<PageMetrics[]>([]);
+  const [appError, setAppError] = useState<AppError | null>(null);
+  const [parseMode, setParseMode] = useState<ParseMode>("lenient");
+  const [diagnostics, setDiagnostics] = useState<Diagnostic[]>([]);
+  const [showDiagnostics, setShowDiagnostics] = useState<boolean>(false);
+  const [currentFile, setCurrentFile] = useState<{ path: string; member?: string } | null>(null);
+  const [archiveChoice, setArchiveChoice] = useState<{ path: string; members: string[] } | null>(null);
   const [searchTerm, setSearchTerm] = useState<string>("");
   const [filterMethod, setFilterMethod] = useState<string>("all");
   const [filterStatus, setFilterStatus] = useState<string>("all");
@@ -150,6 +450,18 @@ function App() {
     return window.matchMedia("(prefers-color-scheme: dark)").matches;
   });
 
+  const [environments, setEnvironments] = useState<Environment[]>(() => {
+    const saved = localStorage.getItem("environments");
+    return saved ? JSON.parse(saved) : [];
+  });
+  const [environmentName, setEnvironmentName] = useState<string>("");
+  const [showEnvironments, setShowEnvironments] = useState<boolean>(false);
+  const environment = environments.find(e => e.name === environmentName) ?? null;
+
+  React.useEffect(() => {
+    localStorage.setItem("environments", JSON.stringify(environments));
+  }, [environments]);
+
   React.useEffect(() => {
     if (darkMode) {
       document.documentElement.classList.add('dark');
@@ -167,63 +479,462 @@ function App() {
     try {
       const selected = await open({
         multiple: false,
-        filters: [{ name: 'HAR Files', extensions: ['har'] }]
+        filters: [{ name: 'HAR Files', extensions: ['har', 'gz', 'zst', 'br', 'zip'] }]
       });
 
       if (selected && !Array.isArray(selected)) {
-        const content = await readTextFile(selected);
-        const harData = JSON.parse(content) as HarFile;
-        setHarFile(harData);
-
-        setSelectedEntry(null);
-        setEditedRequest(null);
-        setReplayResponse(null);
+        const members = await invoke<string[]>("list_har_archive_members", { path: selected });
+        if (members.length > 1) {
+          setArchiveChoice({ path: selected, members });
+        } else {
+          await loadHarFile(selected, members[0]);
+        }
       }
     } catch (error) {
       console.error("Error opening HAR file:", error);
+      reportError(error);
     }
   }
 
-  async function replayRequest(request: HarRequest) {
+  async function loadHarFile(path: string, member?: string) {
+    setArchiveChoice(null);
+    setSelectedEntry(null);
+    setEditedRequest(null);
+    setReplayResponse(null);
+    setDiagnostics([]);
+    setCurrentFile({ path, member });
+
+    let entries: HarEntry[] = [];
+    const unlistenPage = await listen<EntryPage>("har-load-page", event => {
+      entries = entries.concat(event.payload.entries);
+      setHarFile(prev => prev ? { log: { ...prev.log, entries } } : prev);
+    });
+    const unlistenProgress = await listen<LoadProgress>("har-load-progress", event => {
+      setLoadProgress(event.payload);
+    });
+    setHarFile({ log: { version: "", creator: { name: "", version: "" }, entries } });
+
+    try {
+      const header = await invoke<HarFile>("open_har_file", { path, member, mode: parseMode });
+      setHarFile({ log: { ...header.log, entries } });
+      setDiagnostics(await invoke<Diagnostic[]>("get_har_diagnostics"));
+      setCookieJar(await invoke<StoredCookie[]>("get_cookie_jar"));
+    } catch (error) {
+      console.error("Error opening HAR file:", error);
+      reportError(error);
+    } finally {
+      unlistenPage();
+      unlistenProgress();
+      setLoadProgress(null);
+    }
+  }
+
+  async function validateHarFile() {
+    if (!currentFile) {
+      return;
+    }
+    try {
+      const findings = await invoke<Diagnostic[]>("validate_har", currentFile);
+      setDiagnostics(findings);
+      setShowDiagnostics(true);
+    } catch (error) {
+      console.error("Error validating HAR file:", error);
+      reportError(error);
+    }
+  }
+
+  async function saveHarFile() {
     try {
-      const rustRequest = {
-        ...request,
-        http_version: request.httpVersion,
-        query_string: request.queryString,
-        post_data: request.postData ? {
-          ...request.postData,
-          mime_type: request.postData.mimeType,
-          params: request.postData.params ? request.postData.params.map(param => ({
-            ...param,
-            file_name: param.fileName,
-            content_type: param.contentType
-          })) : undefined
-        } : undefined,
-        headers_size: request.headersSize,
-        body_size: request.bodySize
-      };
-
-      delete (rustRequest as any).httpVersion;
-      delete (rustRequest as any).queryString;
-      if (rustRequest.post_data) {
-        delete (rustRequest.post_data as any).mimeType;
-        if (rustRequest.post_data.params) {
-          rustRequest.post_data.params.forEach(param => {
-            delete (param as any).fileName;
-            delete (param as any).contentType;
-          });
+      const path = await save({
+        filters: [{ name: 'HAR Files', extensions: ['har', 'gz', 'zst', 'br', 'zip'] }]
+      });
+      if (path) {
+        await invoke("save_har_file", { path });
+      }
+    } catch (error) {
+      console.error("Error saving HAR file:", error);
+      reportError(error);
+    }
+  }
+
+  function reportError(error: unknown) {
+    if (error && typeof error === "object" && "kind" in error) {
+      const appError = error as AppError;
+      if (appError.kind !== "cancelled") {
+        setAppError(appError);
+      }
+    } else {
+      setAppError({ kind: "internal", message: String(error), context: {} });
+    }
+  }
+
+  async function cancelLoad() {
+    await invoke("cancel_har_load");
+  }
+
+  async function selectEntry(entry: HarEntry) {
+    setSelectedEntry(entry);
+    setEditedRequest(null);
+    setReplayResponse(null);
+    setReplayDiff(null);
+    setAttachments({});
+
+    if (harFile && entry.response.content.text === undefined) {
+      try {
+        const index = harFile.log.entries.indexOf(entry);
+        const text = await invoke<string | null>("get_response_body", { index });
+        if (text !== null) {
+          const withBody = { ...entry, response: { ...entry.response, content: { ...entry.response.content, text } } };
+          harFile.log.entries[index] = withBody;
+          setSelectedEntry(current => current === entry ? withBody : current);
         }
+      } catch (error) {
+        console.error("Error loading response body:", error);
+        reportError(error);
       }
-      delete (rustRequest as any).headersSize;
-      delete (rustRequest as any).bodySize;
+    }
+  }
 
-      const response = await invoke("replay_request", { request: rustRequest });
-      setReplayResponse(JSON.parse(response as string));
+  async function replayRequest(request: HarRequest) {
+    try {
+      const replayed = await invoke<HarEntry>("replay_request", {
+        request,
+        attachments,
+        options: clientConfig,
+        useCookieJar,
+        environment,
+      });
+      setReplayResponse(replayed);
+      setReplayDiff(null);
+      if (useCookieJar) {
+        setCookieJar(await invoke<StoredCookie[]>("get_cookie_jar"));
+      }
     } catch (error) {
       console.error("Error replaying request:", error);
+      reportError(error);
+    }
+  }
+
+  async function compareReplay(entry: HarEntry) {
+    if (!harFile || !selectedEntry) return;
+    try {
+      const index = harFile.log.entries.indexOf(selectedEntry);
+      const paths = ignorePaths.split("\n").map(p => p.trim()).filter(p => p);
+      setReplayDiff(await invoke<ResponseDiff>("diff_replay", {
+        index,
+        replayed: entry,
+        options: paths.length > 0 ? { ignorePaths: paths } : null,
+      }));
+    } catch (error) {
+      console.error("Error comparing responses:", error);
+      reportError(error);
+    }
+  }
+
+  async function appendReplay(entry: HarEntry) {
+    try {
+      await invoke<number>("append_har_entry", { entry });
+      setHarFile(prev => prev ? { log: { ...prev.log, entries: [...prev.log.entries, entry] } } : prev);
+    } catch (error) {
+      console.error("Error adding replayed entry:", error);
+      reportError(error);
+    }
+  }
+
+  async function attachFile(param: string) {
+    const selected = await open({ multiple: false });
+    if (selected && !Array.isArray(selected)) {
+      setAttachments(prev => ({ ...prev, [param]: selected }));
+    }
+  }
+
+  async function runFlow() {
+    setFlowLogs([]);
+    setFlowRunning(true);
+    const unlisten = await listen<StepLog>("flow-step", event => {
+      setFlowLogs(prev => [...prev, event.payload]);
+    });
+    try {
+      await invoke<StepLog[]>("replay_flow", {
+        first: flowRange.first,
+        last: flowRange.last,
+        flow: {
+          extractions,
+          continueOnError,
+          variables: environment?.variables ?? {},
+          retarget: environment?.retarget ?? { hosts: [] },
+        },
+        attachments,
+        options: clientConfig,
+        useCookieJar,
+      });
+      if (useCookieJar) {
+        setCookieJar(await invoke<StoredCookie[]>("get_cookie_jar"));
+      }
+    } catch (error) {
+      console.error("Error replaying flow:", error);
+      reportError(error);
+    } finally {
+      unlisten();
+      setFlowRunning(false);
+    }
+  }
+
+  function matchesFilter(entry: HarEntry) {
+    if (searchTerm && !entry.request.url.toLowerCase().includes(searchTerm.toLowerCase()) &&
+        !entry.request.method.toLowerCase().includes(searchTerm.toLowerCase())) {
+      return false;
+    }
+
+    if (filterMethod !== "all" && entry.request.method !== filterMethod) {
+      return false;
+    }
+
+    if (filterStatus !== "all") {
+      const statusCode = entry.response.status;
+      if (filterStatus === "2xx" && (statusCode < 200 || statusCode >= 300)) return false;
+      if (filterStatus === "3xx" && (statusCode < 300 || statusCode >= 400)) return false;
+      if (filterStatus === "4xx" && (statusCode < 400 || statusCode >= 500)) return false;
+      if (filterStatus === "5xx" && (statusCode < 500 || statusCode >= 600)) return false;
+    }
+
+    return true;
+  }
+
+  async function runBatch() {
+    if (!harFile) return;
+    const indices = harFile.log.entries.flatMap((entry, i) => matchesFilter(entry) ? [i] : []);
+    setBatchItems([]);
+    setBatchTotal(indices.length);
+    setBatchSummary(null);
+    setBatchRunning(true);
+    const unlisten = await listen<{ completed: number; total: number; item: BatchItem }>("batch-progress", event => {
+      setBatchItems(prev => [...prev, event.payload.item]);
+    });
+    try {
+      setBatchSummary(await invoke<BatchSummary>("replay_batch", {
+        indices,
+        batch: batchOptions,
+        attachments,
+        options: clientConfig,
+        environment,
+      }));
+    } catch (error) {
+      console.error("Error replaying batch:", error);
+      reportError(error);
+    } finally {
+      unlisten();
+      setBatchRunning(false);
+    }
+  }
+
+  async function cancelReplay() {
+    await invoke("cancel_replay");
+  }
+
+  async function runSchedule() {
+    if (!harFile) return;
+    setScheduleItems([]);
+    setScheduleReport(null);
+    setScheduleRunning(true);
+    const unlisten = await listen<{ completed: number; total: number; item: ScheduledItem }>("schedule-progress", event => {
+      setScheduleItems(prev => [...prev, event.payload.item]);
+    });
+    try {
+      setScheduleReport(await invoke<ScheduleReport>("replay_schedule", {
+        schedule: { speed: scheduleSpeed },
+        attachments,
+        options: clientConfig,
+        environment,
+      }));
+    } catch (error) {
+      console.error("Error replaying schedule:", error);
+      reportError(error);
+    } finally {
+      unlisten();
+      setScheduleRunning(false);
+    }
+  }
+
+  React.useEffect(() => {
+    const unlisten = listen<MockRequest>("mock-request", event => {
+      setMockRequests(prev => [...prev.slice(-499), event.payload]);
+    });
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, []);
+
+  async function startMock() {
+    try {
+      setMockRequests([]);
+      setMockAddress(await invoke<string>("start_mock_server", { port: mockPort, options: mockOptions }));
+    } catch (error) {
+      console.error("Error starting mock server:", error);
+      reportError(error);
+    }
+  }
+
+  async function stopMock() {
+    try {
+      await invoke<MockRequest[]>("stop_mock_server");
+      setMockAddress(null);
+    } catch (error) {
+      console.error("Error stopping mock server:", error);
+      reportError(error);
     }
   }
 
+  const entryCount = harFile?.log.entries.length ?? 0;
+  const loading = loadProgress !== null;
+  React.useEffect(() => {
+    if (!harFile || loading) {
+      setSummary(null);
+      return;
+    }
+    invoke<Summary>("summarize_har")
+      .then(setSummary)
+      .catch(() => setSummary(null));
+  }, [harFile !== null, entryCount, loading]);
+
+  React.useEffect(() => {
+    if (!showEndpoints || !harFile || loading) {
+      return;
+    }
+    const indices = harFile.log.entries.flatMap((entry, i) => matchesFilter(entry) ? [i] : []);
+    invoke<Group[]>("group_har_entries", { by: groupBy, indices })
+      .then(setGroups)
+      .catch(error => {
+        console.error("Error grouping entries:", error);
+        reportError(error);
+      });
+  }, [showEndpoints, groupBy, entryCount, loading, searchTerm, filterMethod, filterStatus]);
+
+  React.useEffect(() => {
+    if (!showPages || !harFile || loading) {
+      return;
+    }
+    invoke<PageMetrics[]>("get_page_metrics")
+      .then(setPageMetrics)
+      .catch(error => {
+        console.error("Error measuring pages:", error);
+        reportError(error);
+      });
+  }, [showPages, entryCount, loading]);
+
+  async function assignPages() {
+    if (!harFile) return;
+    try {
+      await invoke<number>("assign_pages");
+      const entries = await invoke<HarEntry[]>("get_har_entries", { offset: 0, limit: harFile.log.entries.length });
+      setHarFile(prev => prev ? { log: { ...prev.log, entries } } : prev);
+      setPageMetrics(await invoke<PageMetrics[]>("get_page_metrics"));
+    } catch (error) {
+      console.error("Error assigning entries to pages:", error);
+      reportError(error);
+    }
+  }
+
+  React.useEffect(() => {
+    const unlisten = listen<RecordedEntry>("proxy-entry", event => {
+      const { index, entry } = event.payload;
+      setRecordedCount(prev => prev + 1);
+      setHarFile(prev => {
+        if (!prev) return prev;
+        const entries = [...prev.log.entries];
+        entries[index] = entry;
+        return { log: { ...prev.log, entries } };
+      });
+    });
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, []);
+
+  async function startRecording() {
+    try {
+      const started = await invoke<Recording>("start_recording", { port: recordPort, options: clientConfig });
+      if (started.har) {
+        setHarFile({ log: { ...started.har.log, entries: [] } });
+        setCurrentFile(null);
+        setDiagnostics([]);
+        setSelectedEntry(null);
+      }
+      setRecordedCount(0);
+      setRecording(started);
+    } catch (error) {
+      console.error("Error starting recording proxy:", error);
+      reportError(error);
+    }
+  }
+
+  async function stopRecording() {
+    try {
+      await invoke<number>("stop_recording");
+      setRecording(null);
+    } catch (error) {
+      console.error("Error stopping recording proxy:", error);
+      reportError(error);
+    }
+  }
+
+  function updateEnvironment(index: number, change: Partial<Environment>) {
+    const previous = environments[index];
+    setEnvironments(prev => prev.map((env, i) => i === index ? { ...env, ...change } : env));
+    if (change.name !== undefined && previous.name === environmentName) {
+      setEnvironmentName(change.name);
+    }
+  }
+
+  function updateRetarget(index: number, change: Partial<Retarget>) {
+    updateEnvironment(index, { retarget: { ...environments[index].retarget, ...change } });
+  }
+
+  function parseVariables(text: string): Record<string, string> {
+    const variables: Record<string, string> = {};
+    for (const line of text.split("\n")) {
+      const at = line.indexOf("=");
+      if (at > 0) {
+        variables[line.slice(0, at).trim()] = line.slice(at + 1);
+      }
+    }
+    return variables;
+  }
+
+  function updateExtraction(index: number, change: Partial<Extraction>) {
+    setExtractions(prev => prev.map((rule, i) => i === index ? { ...rule, ...change } : rule));
+  }
+
+  async function updateCookieJar(jar: StoredCookie[]) {
+    try {
+      await invoke("set_cookie_jar", { jar });
+      setCookieJar(jar);
+    } catch (error) {
+      reportError(error);
+    }
+  }
+
+  async function resetCookieJar() {
+    try {
+      setCookieJar(await invoke<StoredCookie[]>("reset_cookie_jar"));
+    } catch (error) {
+      console.error("Error resetting cookie jar:", error);
+      reportError(error);
+    }
+  }
+
+  async function pickClientFile(field: "caBundle" | "clientCert" | "clientKey") {
+    const selected = await open({ multiple: false, filters: [{ name: "PEM", extensions: ["pem", "crt", "key"] }] });
+    if (selected && !Array.isArray(selected)) {
+      setClientConfig(prev => ({ ...prev, [field]: selected }));
+    }
+  }
+
+  function parseMillis(value: string): number | undefined {
+    const millis = parseInt(value, 10);
+    return Number.isNaN(millis) || millis <= 0 ? undefined : millis;
+  }
+
   function formatHeaders(headers: HarHeader[]): string {
     return headers.map(h => `${h.name}: ${h.value}`).join('\n');
   }
@@ -278,6 +989,13 @@ function App() {
       return <div>No content</div>;
     }
 
+    if (content.encoding === "base64") {
+      if (content.mimeType.startsWith("image/")) {
+        return <img src={`data:${content.mimeType};base64,${content.text}`} alt="Response body" className="max-w-full" />;
+      }
+      return <div>{content.size} bytes of binary {content.mimeType || "content"} (base64)</div>;
+    }
+
     let language = 'text';
     let text = content.text;
 
@@ -314,6 +1032,12 @@ function App() {
     );
   }
 
+  // The common verbs first, then any others (WebDAV, PURGE, ...) in the archive.
+  const methodOptions = Array.from(new Set([
+    "GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD",
+    ...(harFile?.log.entries.map(entry => entry.request.method) ?? []),
+  ]));
+
   const maxTime = harFile?.log.entries.reduce((max, entry) =>
     Math.max(max, entry.time), 0) || 0;
 
@@ -346,10 +1070,732 @@ function App() {
               </svg>
             )}
           </Button>
-          <Button onClick={openHarFile}>Open HAR File</Button>
+          {loadProgress ? (
+            <>
+              <span className="text-sm">
+                Loading {loadProgress.entries} entries ({Math.round((loadProgress.bytesRead / Math.max(loadProgress.totalBytes, 1)) * 100)}%)
+              </span>
+              <Button onClick={cancelLoad}>Cancel</Button>
+            </>
+          ) : (
+            <>
+              <select
+                className="p-2 rounded border border-input bg-background text-sm"
+                value={parseMode}
+                onChange={(e) => setParseMode(e.target.value as ParseMode)}
+                title="Parsing mode"
+              >
+                <option value="standard">Standard</option>
+                <option value="lenient">Lenient</option>
+                <option value="strict">Strict</option>
+              </select>
+              {currentFile && <Button onClick={validateHarFile}>Validate</Button>}
+              <select
+                className="p-2 rounded border border-input bg-background text-sm"
+                value={environmentName}
+                onChange={(e) => setEnvironmentName(e.target.value)}
+                title="Environment to replay against"
+              >
+                <option value="">As recorded</option>
+                {environments.map(env => (
+                  <option key={env.name} value={env.name}>{env.name}</option>
+                ))}
+              </select>
+              <Button variant="outline" onClick={() => setShowEnvironments(prev => !prev)}>Environments</Button>
+              {harFile && <Button variant="outline" onClick={() => setShowFlow(prev => !prev)}>Replay Flow</Button>}
+              {harFile && <Button variant="outline" onClick={() => setShowBatch(prev => !prev)}>Replay Batch</Button>}
+              {harFile && <Button variant="outline" onClick={() => setShowSchedule(prev => !prev)}>Replay Schedule</Button>}
+              {harFile && <Button variant="outline" onClick={() => setShowMock(prev => !prev)}>Mock Server</Button>}
+              {harFile && <Button variant="outline" onClick={() => setShowEndpoints(prev => !prev)}>Endpoints</Button>}
+              {harFile?.log.pages?.length ? <Button variant="outline" onClick={() => setShowPages(prev => !prev)}>Pages</Button> : null}
+              <Button variant="outline" onClick={() => setShowRecord(prev => !prev)}>Record</Button>
+              {harFile && <Button onClick={saveHarFile}>Save HAR File</Button>}
+              <Button onClick={openHarFile}>Open HAR File</Button>
+            </>
+          )}
         </div>
       </header>
 
+      {appError && (
+        <Card className="mb-4 border-destructive">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>{appError.message}</span>
+              <Button variant="ghost" onClick={() => setAppError(null)}>Dismiss</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="text-sm space-y-1">
+            {appError.context.path && <div>File: {appError.context.path}</div>}
+            {appError.context.jsonPath && <div>Location: {appError.context.jsonPath}</div>}
+            {appError.context.line !== undefined && (
+              <div>Line {appError.context.line}, column {appError.context.column}</div>
+            )}
+            {appError.context.method && <div>Method: {appError.context.method}</div>}
+            {appError.context.url && <div>URL: {appError.context.url}</div>}
+          </CardContent>
+        </Card>
+      )}
+
+      {diagnostics.length > 0 && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>{diagnostics.length} findings</span>
+              <Button variant="ghost" onClick={() => setShowDiagnostics(prev => !prev)}>
+                {showDiagnostics ? "Hide" : "Show"}
+              </Button>
+            </CardTitle>
+          </CardHeader>
+          {showDiagnostics && (
+            <CardContent className="text-sm space-y-1 max-h-64 overflow-auto">
+              {diagnostics.map((d, i) => (
+                <div key={i} className={d.severity === "error" ? "text-destructive" : undefined}>
+                  <span className="font-mono">{d.jsonPath}</span>: {d.message}
+                </div>
+              ))}
+            </CardContent>
+          )}
+        </Card>
+      )}
+
+      {archiveChoice && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle>Choose a HAR file from the archive</CardTitle>
+          </CardHeader>
+          <CardContent className="flex flex-col gap-2">
+            {archiveChoice.members.map(member => (
+              <Button key={member} onClick={() => loadHarFile(archiveChoice.path, member)}>
+                {member}
+              </Button>
+            ))}
+          </CardContent>
+        </Card>
+      )}
+
+      {showEnvironments && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Environments</span>
+              <Button variant="ghost" onClick={() => setShowEnvironments(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Replays use the environment chosen in the header. Empty fields keep the recorded value;
+              {" {{name}} "}placeholders in URLs, headers and bodies are filled from the variables.
+            </p>
+            {environments.map((env, i) => (
+              <div key={i} className="p-2 border rounded grid grid-cols-6 gap-2 items-center">
+                <Label className="col-span-1">Name</Label>
+                <Input
+                  className="col-span-4"
+                  value={env.name}
+                  onChange={(e) => updateEnvironment(i, { name: e.target.value })}
+                />
+                <Button variant="ghost" onClick={() => setEnvironments(prev => prev.filter((_, j) => j !== i))}>
+                  Remove
+                </Button>
+                <Label className="col-span-1">Target</Label>
+                <select
+                  className="col-span-1 p-2 rounded border border-input bg-background text-sm"
+                  value={env.retarget.scheme ?? ""}
+                  onChange={(e) => updateRetarget(i, { scheme: e.target.value || undefined })}
+                >
+                  <option value="">scheme</option>
+                  <option value="https">https</option>
+                  <option value="http">http</option>
+                </select>
+                <Input
+                  className="col-span-3"
+                  placeholder="host"
+                  value={env.retarget.host ?? ""}
+                  onChange={(e) => updateRetarget(i, { host: e.target.value || undefined })}
+                />
+                <Input
+                  className="col-span-1"
+                  type="number"
+                  placeholder="port"
+                  value={env.retarget.port ?? ""}
+                  onChange={(e) => updateRetarget(i, { port: parseInt(e.target.value, 10) || undefined })}
+                />
+                <Label className="col-span-1">Path prefix</Label>
+                <Input
+                  className="col-span-2"
+                  placeholder="remove, e.g. /api"
+                  value={env.retarget.stripPrefix ?? ""}
+                  onChange={(e) => updateRetarget(i, { stripPrefix: e.target.value || undefined })}
+                />
+                <Input
+                  className="col-span-3"
+                  placeholder="add, e.g. /staging/api"
+                  value={env.retarget.addPrefix ?? ""}
+                  onChange={(e) => updateRetarget(i, { addPrefix: e.target.value || undefined })}
+                />
+                <Label className="col-span-1">Only hosts</Label>
+                <Input
+                  className="col-span-5"
+                  placeholder="all hosts; or a comma-separated list"
+                  value={env.retarget.hosts.join(", ")}
+                  onChange={(e) => updateRetarget(i, { hosts: e.target.value.split(",").map(h => h.trim()).filter(h => h) })}
+                />
+                <Label className="col-span-1">Variables</Label>
+                <textarea
+                  className="col-span-5 min-h-[60px] p-2 border rounded bg-card font-mono"
+                  placeholder={"baseUrl=https://staging.example.com\ntoken=..."}
+                  defaultValue={Object.entries(env.variables).map(([name, value]) => `${name}=${value}`).join("\n")}
+                  onBlur={(e) => updateEnvironment(i, { variables: parseVariables(e.target.value) })}
+                />
+              </div>
+            ))}
+            <Button
+              variant="outline"
+              onClick={() => setEnvironments(prev => [...prev, { name: `Environment ${prev.length + 1}`, variables: {}, retarget: { hosts: [] } }])}
+            >
+              Add environment
+            </Button>
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showFlow && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Replay flow</span>
+              <Button variant="ghost" onClick={() => setShowFlow(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <div className="flex flex-wrap items-center gap-4">
+              <Label htmlFor="flow-first">Entries</Label>
+              <Input
+                id="flow-first"
+                type="number"
+                min={0}
+                className="w-24"
+                value={flowRange.first}
+                onChange={(e) => setFlowRange({ ...flowRange, first: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+              />
+              <span>to</span>
+              <Input
+                type="number"
+                min={flowRange.first}
+                className="w-24"
+                value={flowRange.last}
+                onChange={(e) => setFlowRange({ ...flowRange, last: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+              />
+              <label className="flex items-center gap-2">
+                <input type="checkbox" checked={useCookieJar} onChange={(e) => setUseCookieJar(e.target.checked)} />
+                Use cookie jar
+              </label>
+              <label className="flex items-center gap-2">
+                <input type="checkbox" checked={continueOnError} onChange={(e) => setContinueOnError(e.target.checked)} />
+                Continue after errors
+              </label>
+            </div>
+            <p className="text-muted-foreground">
+              Steps count from 0. Use {"{{name}}"} in a request to insert a value extracted from an earlier response.
+            </p>
+            {extractions.map((rule, i) => (
+              <div key={i} className="grid grid-cols-12 items-center gap-2">
+                <Input
+                  className="col-span-2"
+                  placeholder="name"
+                  value={rule.name}
+                  onChange={(e) => updateExtraction(i, { name: e.target.value })}
+                />
+                <Input
+                  className="col-span-1"
+                  type="number"
+                  min={0}
+                  title="Step"
+                  value={rule.step}
+                  onChange={(e) => updateExtraction(i, { step: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+                />
+                <select
+                  className="col-span-2 p-2 rounded border border-input bg-background text-sm"
+                  value={rule.source.kind}
+                  onChange={(e) => updateExtraction(i, { source: { ...rule.source, kind: e.target.value as Extraction["source"]["kind"] } })}
+                >
+                  <option value="jsonPath">JSONPath</option>
+                  <option value="regex">Regex</option>
+                  <option value="header">Header</option>
+                </select>
+                <Input
+                  className="col-span-4 font-mono"
+                  placeholder={rule.source.kind === "jsonPath" ? "$.token" : rule.source.kind === "regex" ? "csrf\" value=\"([^\"]+)" : "X-CSRF-Token"}
+                  value={rule.source.expression}
+                  onChange={(e) => updateExtraction(i, { source: { ...rule.source, expression: e.target.value } })}
+                />
+                <label className="col-span-2 flex items-center gap-2" title="Also replace the recorded value in later requests">
+                  <input
+                    type="checkbox"
+                    checked={rule.replaceRecorded}
+                    onChange={(e) => updateExtraction(i, { replaceRecorded: e.target.checked })}
+                  />
+                  Replace recorded
+                </label>
+                <Button variant="ghost" onClick={() => setExtractions(prev => prev.filter((_, j) => j !== i))}>
+                  Remove
+                </Button>
+              </div>
+            ))}
+            <div className="flex gap-2">
+              <Button
+                variant="outline"
+                onClick={() => setExtractions(prev => [...prev, { name: "", step: 0, source: { kind: "jsonPath", expression: "" }, replaceRecorded: true }])}
+              >
+                Add extraction
+              </Button>
+              <Button disabled={flowRunning || flowRange.last < flowRange.first} onClick={runFlow}>
+                {flowRunning ? "Running..." : "Run flow"}
+              </Button>
+            </div>
+            {flowLogs.length > 0 && (
+              <div className="space-y-2 max-h-96 overflow-auto">
+                {flowLogs.map(log => (
+                  <div key={log.step} className={`p-2 border rounded ${log.error ? "border-destructive" : ""}`}>
+                    <div className="font-mono truncate">
+                      #{flowRange.first + log.step} {log.method} {log.url}
+                    </div>
+                    <div>
+                      {log.entry
+                        ? `${log.entry.response.status} ${log.entry.response.statusText}, ${log.entry.time.toFixed(1)} ms`
+                        : log.error?.message}
+                    </div>
+                    {log.extracted.map(value => (
+                      <div key={value.name} className="font-mono text-xs">
+                        {value.name} = {value.value ?? "not found"}
+                      </div>
+                    ))}
+                    {log.unresolved.length > 0 && (
+                      <div className="text-xs text-destructive">Unresolved: {log.unresolved.join(", ")}</div>
+                    )}
+                    {log.entry && (
+                      <Button variant="outline" className="mt-2" onClick={() => appendReplay(log.entry!)}>
+                        Add to archive
+                      </Button>
+                    )}
+                  </div>
+                ))}
+              </div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showBatch && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Replay batch</span>
+              <Button variant="ghost" onClick={() => setShowBatch(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Replays the {harFile.log.entries.filter(matchesFilter).length} requests matching the search and filters
+              of the request list. Each passes if its status matches the recorded one.
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+              <Label htmlFor="batch-concurrency">Concurrency</Label>
+              <Input
+                id="batch-concurrency"
+                type="number"
+                min={1}
+                className="w-20"
+                value={batchOptions.concurrency}
+                onChange={(e) => setBatchOptions({ ...batchOptions, concurrency: Math.max(1, parseInt(e.target.value, 10) || 1) })}
+              />
+              <Label htmlFor="batch-rps">Requests per second</Label>
+              <Input
+                id="batch-rps"
+                type="number"
+                min={0}
+                className="w-24"
+                placeholder="unlimited"
+                value={batchOptions.requestsPerSecond ?? ""}
+                onChange={(e) => setBatchOptions({ ...batchOptions, requestsPerSecond: e.target.value ? parseFloat(e.target.value) : null })}
+              />
+              <Label htmlFor="batch-retries">Retries</Label>
+              <Input
+                id="batch-retries"
+                type="number"
+                min={0}
+                className="w-20"
+                value={batchOptions.retries}
+                onChange={(e) => setBatchOptions({ ...batchOptions, retries: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+              />
+              <Label htmlFor="batch-delay">Retry delay (ms)</Label>
+              <Input
+                id="batch-delay"
+                type="number"
+                min={0}
+                className="w-24"
+                value={batchOptions.retryDelayMs}
+                onChange={(e) => setBatchOptions({ ...batchOptions, retryDelayMs: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+              />
+            </div>
+            <div className="flex items-center gap-2">
+              {batchRunning ? (
+                <>
+                  <Button variant="outline" onClick={cancelReplay}>Cancel</Button>
+                  <span>{batchItems.length} of {batchTotal} done</span>
+                </>
+              ) : (
+                <Button onClick={runBatch}>Run batch</Button>
+              )}
+              {batchSummary && (
+                <span>
+                  {batchSummary.passed} passed, {batchSummary.failed} failed, {batchSummary.errors} errors
+                  {batchSummary.cancelled > 0 && `, ${batchSummary.cancelled} cancelled`} in {(batchSummary.elapsed / 1000).toFixed(1)} s
+                </span>
+              )}
+            </div>
+            {(batchSummary?.items ?? batchItems).length > 0 && (
+              <div className="space-y-1 max-h-96 overflow-auto">
+                {(batchSummary?.items ?? batchItems).map(item => (
+                  <div
+                    key={item.index}
+                    className={`flex gap-2 p-1 border rounded font-mono text-xs ${item.outcome === "passed" ? "" : "border-destructive"}`}
+                  >
+                    <span className="w-12">#{item.index}</span>
+                    <span className="w-20">{item.outcome}</span>
+                    <span className="w-24">
+                      {item.status ?? "-"} / {item.recordedStatus}
+                    </span>
+                    <span className="flex-1 truncate" title={item.error?.message ?? item.url}>
+                      {item.method} {item.url}
+                    </span>
+                    {item.attempts > 1 && <span>{item.attempts} attempts</span>}
+                  </div>
+                ))}
+              </div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showSchedule && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Replay on recorded schedule</span>
+              <Button variant="ghost" onClick={() => setShowSchedule(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Sends all {harFile.log.entries.length} requests with the gaps between them as recorded, divided by the speed.
+              Requests that overlapped in the capture overlap again.
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+              <Label htmlFor="schedule-speed">Speed</Label>
+              <Input
+                id="schedule-speed"
+                type="number"
+                min={0.1}
+                step={0.5}
+                className="w-24"
+                value={scheduleSpeed}
+                onChange={(e) => setScheduleSpeed(parseFloat(e.target.value) || 1)}
+              />
+              {[0.5, 1, 2, 10].map(speed => (
+                <Button key={speed} variant={scheduleSpeed === speed ? "default" : "outline"} onClick={() => setScheduleSpeed(speed)}>
+                  {speed}x
+                </Button>
+              ))}
+              {scheduleRunning ? (
+                <>
+                  <Button variant="outline" onClick={cancelReplay}>Cancel</Button>
+                  <span>{scheduleItems.length} of {harFile.log.entries.length} done</span>
+                </>
+              ) : (
+                <Button onClick={runSchedule}>Run</Button>
+              )}
+            </div>
+            {scheduleReport && (
+              <div className="grid grid-cols-4 gap-2 font-mono text-xs">
+                <span />
+                <span>p50</span>
+                <span>p90</span>
+                <span>p99</span>
+                {([["Start lag", scheduleReport.lag], ["Replayed", scheduleReport.responseTime], ["Recorded", scheduleReport.recordedTime]] as const).map(([label, d]) => (
+                  <React.Fragment key={label}>
+                    <span>{label}</span>
+                    <span>{d.p50.toFixed(1)} ms</span>
+                    <span>{d.p90.toFixed(1)} ms</span>
+                    <span>{d.p99.toFixed(1)} ms</span>
+                  </React.Fragment>
+                ))}
+              </div>
+            )}
+            {(scheduleReport?.items ?? scheduleItems).length > 0 && (
+              <div className="space-y-1 max-h-96 overflow-auto">
+                {(scheduleReport?.items ?? scheduleItems).map(item => (
+                  <div
+                    key={item.index}
+                    className={`flex gap-2 p-1 border rounded font-mono text-xs ${item.error ? "border-destructive" : ""}`}
+                  >
+                    <span className="w-12">#{item.index}</span>
+                    <span className="w-40" title="Planned and achieved start">
+                      +{item.plannedOffset.toFixed(0)} / {item.achievedOffset !== undefined ? `+${item.achievedOffset.toFixed(0)}` : "-"} ms
+                    </span>
+                    <span className="w-16">{item.status ?? "-"}</span>
+                    <span className="w-20">{item.time !== undefined ? `${item.time.toFixed(1)} ms` : ""}</span>
+                    <span className="flex-1 truncate" title={item.error?.message ?? item.url}>
+                      {item.method} {item.url}
+                    </span>
+                  </div>
+                ))}
+              </div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showMock && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Mock server</span>
+              <Button variant="ghost" onClick={() => setShowMock(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Answers requests with the recorded responses, matched on method, path and query. Point your front end at
+              the server's address in place of its backend.
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+              <Label htmlFor="mock-port">Port</Label>
+              <Input
+                id="mock-port"
+                type="number"
+                min={0}
+                max={65535}
+                className="w-24"
+                disabled={mockAddress !== null}
+                value={mockPort}
+                onChange={(e) => setMockPort(Math.min(65535, Math.max(0, parseInt(e.target.value, 10) || 0)))}
+              />
+              <Label htmlFor="mock-strategy">Repeated requests</Label>
+              <select
+                id="mock-strategy"
+                className="p-2 rounded border border-input bg-background text-sm"
+                disabled={mockAddress !== null}
+                value={mockOptions.strategy}
+                onChange={(e) => setMockOptions({ ...mockOptions, strategy: e.target.value as MockOptions["strategy"] })}
+              >
+                <option value="sequential">Each response in turn</option>
+                <option value="first">Always the first</option>
+                <option value="last">Always the last</option>
+              </select>
+              <label className="flex items-center gap-2">
+                <input
+                  type="checkbox"
+                  disabled={mockAddress !== null}
+                  checked={mockOptions.matchBody}
+                  onChange={(e) => setMockOptions({ ...mockOptions, matchBody: e.target.checked })}
+                />
+                Match body
+              </label>
+              <label className="flex items-center gap-2">
+                <input
+                  type="checkbox"
+                  disabled={mockAddress !== null}
+                  checked={mockOptions.cors}
+                  onChange={(e) => setMockOptions({ ...mockOptions, cors: e.target.checked })}
+                />
+                Allow cross-origin calls
+              </label>
+              {mockAddress ? (
+                <>
+                  <Button variant="outline" onClick={stopMock}>Stop</Button>
+                  <span className="font-mono">{mockAddress}</span>
+                </>
+              ) : (
+                <Button onClick={startMock}>Start</Button>
+              )}
+            </div>
+            {mockRequests.some(r => r.entry === undefined && r.status === 404) && (
+              <div className="text-destructive">
+                {new Set(mockRequests.filter(r => r.entry === undefined && r.status === 404).map(r => `${r.method} ${r.url}`)).size} unmatched requests
+              </div>
+            )}
+            {mockRequests.length > 0 && (
+              <div className="space-y-1 max-h-96 overflow-auto">
+                {mockRequests.map((request, i) => (
+                  <div
+                    key={i}
+                    className={`flex gap-2 p-1 border rounded font-mono text-xs ${request.entry === undefined && request.status === 404 ? "border-destructive" : ""}`}
+                  >
+                    <span className="w-12">{request.status}</span>
+                    <span className="w-16">{request.entry !== undefined ? `#${request.entry}` : "-"}</span>
+                    <span className="flex-1 truncate">{request.method} {request.url}</span>
+                  </div>
+                ))}
+              </div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showEndpoints && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Endpoints</span>
+              <Button variant="ghost" onClick={() => setShowEndpoints(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              The requests matching the search and filters, grouped with numbers, UUIDs and hashes in paths shown as {"{id}"},
+              slowest first.
+            </p>
+            <div className="flex items-center gap-4">
+              <Label htmlFor="group-by">Group by</Label>
+              <select
+                id="group-by"
+                className="p-2 rounded border border-input bg-background text-sm"
+                value={groupBy}
+                onChange={(e) => setGroupBy(e.target.value as "endpoint" | "host")}
+              >
+                <option value="endpoint">Endpoint</option>
+                <option value="host">Host</option>
+              </select>
+            </div>
+            <div className="max-h-96 overflow-auto">
+              <table className="w-full font-mono text-xs">
+                <thead>
+                  <tr className="text-left">
+                    <th className="p-1">{groupBy === "host" ? "Host" : "Endpoint"}</th>
+                    <th className="p-1">Count</th>
+                    <th className="p-1">Errors</th>
+                    <th className="p-1">p50</th>
+                    <th className="p-1">p90</th>
+                    <th className="p-1">p99</th>
+                    <th className="p-1">Avg size</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {groups.map(group => (
+                    <tr key={group.key} className="border-t">
+                      <td className="p-1 break-all">{group.key}</td>
+                      <td className="p-1">{group.count}</td>
+                      <td className={`p-1 ${group.errors > 0 ? "text-destructive" : ""}`}>{(group.errorRate * 100).toFixed(1)}%</td>
+                      <td className="p-1">{group.time.p50.toFixed(1)} ms</td>
+                      <td className="p-1">{group.time.p90.toFixed(1)} ms</td>
+                      <td className="p-1">{group.time.p99.toFixed(1)} ms</td>
+                      <td className="p-1">{(group.responseSize.mean / 1024).toFixed(1)} KB</td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </div>
+          </CardContent>
+        </Card>
+      )}
+
+      {harFile && showPages && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Pages</span>
+              <Button variant="ghost" onClick={() => setShowPages(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Times are from each page starting. Requests without a page count towards the page that was loading when
+              they started; assigning writes that into the archive.
+            </p>
+            <Button variant="outline" onClick={assignPages}>Assign entries to pages</Button>
+            <div className="max-h-96 overflow-auto">
+              <table className="w-full font-mono text-xs">
+                <thead>
+                  <tr className="text-left">
+                    <th className="p-1">Page</th>
+                    <th className="p-1">Requests</th>
+                    <th className="p-1">Transferred</th>
+                    <th className="p-1">Document TTFB</th>
+                    <th className="p-1">DOMContentLoaded</th>
+                    <th className="p-1">Load</th>
+                    <th className="p-1">Longest chain</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {pageMetrics.map(page => (
+                    <tr key={page.id} className="border-t">
+                      <td className="p-1 break-all" title={page.startedDateTime}>{page.title || page.id}</td>
+                      <td className="p-1">{page.requests}</td>
+                      <td className="p-1">{(page.transferBytes / 1024).toFixed(1)} KB</td>
+                      <td className="p-1">{page.documentTtfb !== undefined ? `${page.documentTtfb.toFixed(1)} ms` : "-"}</td>
+                      <td className="p-1">{page.onContentLoad !== undefined ? `${page.onContentLoad.toFixed(1)} ms` : "-"}</td>
+                      <td className="p-1">{page.onLoad !== undefined ? `${page.onLoad.toFixed(1)} ms` : "-"}</td>
+                      <td
+                        className="p-1"
+                        title={page.longestChain.entries.map(i => harFile.log.entries[i]?.request.url).join("\n")}
+                      >
+                        {page.longestChain.entries.length} requests, {page.longestChain.time.toFixed(1)} ms
+                      </td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </div>
+          </CardContent>
+        </Card>
+      )}
+
+      {showRecord && (
+        <Card className="mb-4">
+          <CardHeader>
+            <CardTitle className="flex justify-between items-center">
+              <span>Record traffic</span>
+              <Button variant="ghost" onClick={() => setShowRecord(false)}>Close</Button>
+            </CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4 text-sm">
+            <p className="text-muted-foreground">
+              Runs a proxy that adds every request sent through it to the open archive, or to a new one if none is open.
+              Set it as the HTTP and HTTPS proxy of a browser, simulator or service; HTTPS is recorded once the client
+              trusts the proxy's certificate authority. Save the archive when done.
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+              <Label htmlFor="record-port">Port</Label>
+              <Input
+                id="record-port"
+                type="number"
+                min={0}
+                max={65535}
+                className="w-24"
+                disabled={recording !== null}
+                value={recordPort}
+                onChange={(e) => setRecordPort(Math.min(65535, Math.max(0, parseInt(e.target.value, 10) || 0)))}
+              />
+              {recording ? (
+                <>
+                  <Button variant="outline" onClick={stopRecording}>Stop</Button>
+                  <span className="font-mono">{recording.address}</span>
+                  <span>{recordedCount} requests recorded</span>
+                </>
+              ) : (
+                <Button onClick={startRecording}>Start</Button>
+              )}
+            </div>
+            {recording && (
+              <div>
+                Certificate authority to trust: <span className="font-mono">{recording.caCertificate}</span>
+              </div>
+            )}
+          </CardContent>
+        </Card>
+      )}
+
       {harFile ? (
         <div className="grid grid-cols-1 md:grid-cols-3 gap-4">
           {/* Left sidebar - Request list */}
@@ -375,13 +1821,9 @@ function App() {
                         onChange={(e) => setFilterMethod(e.target.value)}
                       >
                         <option value="all">All Methods</option>
-                        <option value="GET">GET</option>
-                        <option value="POST">POST</option>
-                        <option value="PUT">PUT</option>
-                        <option value="DELETE">DELETE</option>
-                        <option value="PATCH">PATCH</option>
-                        <option value="OPTIONS">OPTIONS</option>
-                        <option value="HEAD">HEAD</option>
+                        {methodOptions.map(method => (
+                          <option key={method} value={method}>{method}</option>
+                        ))}
                       </select>
                     </div>
                     <div>
@@ -403,35 +1845,12 @@ function App() {
                 </div>
                 <ScrollArea className="h-[calc(100vh-280px)]">
                   {harFile.log.entries
-                    .filter(entry => {
-                      if (searchTerm && !entry.request.url.toLowerCase().includes(searchTerm.toLowerCase()) &&
-                          !entry.request.method.toLowerCase().includes(searchTerm.toLowerCase())) {
-                        return false;
-                      }
-
-                      if (filterMethod !== "all" && entry.request.method !== filterMethod) {
-                        return false;
-                      }
-
-                      if (filterStatus !== "all") {
-                        const statusCode = entry.response.status;
-                        if (filterStatus === "2xx" && (statusCode < 200 || statusCode >= 300)) return false;
-                        if (filterStatus === "3xx" && (statusCode < 300 || statusCode >= 400)) return false;
-                        if (filterStatus === "4xx" && (statusCode < 400 || statusCode >= 500)) return false;
-                        if (filterStatus === "5xx" && (statusCode < 500 || statusCode >= 600)) return false;
-                      }
-
-                      return true;
-                    })
+                    .filter(matchesFilter)
                     .map((entry, index) => (
                     <div 
                       key={index}
                       className={`p-2 mb-2 cursor-pointer rounded ${selectedEntry === entry ? 'bg-primary/10' : 'hover:bg-secondary/10'}`}
-                      onClick={() => {
-                        setSelectedEntry(entry);
-                        setEditedRequest(null);
-                        setReplayResponse(null);
-                      }}
+                      onClick={() => selectEntry(entry)}
                     >
                       <div className="font-medium">{entry.request.method} {(() => {
                         try {
@@ -577,7 +1996,166 @@ function App() {
                                     className="col-span-3 min-h-[150px] p-2 border rounded bg-card"
                                   />
                                 </div>
+                                {(editedRequest || selectedEntry.request).postData?.params
+                                  ?.filter(param => param.fileName !== undefined)
+                                  .map(param => (
+                                    <div key={param.name} className="grid grid-cols-4 items-center gap-4">
+                                      <Label className="text-right">File: {param.name}</Label>
+                                      <span className="col-span-2 text-sm truncate">
+                                        {attachments[param.name] || param.fileName || "not captured"}
+                                      </span>
+                                      <Button variant="outline" onClick={() => attachFile(param.name)}>
+                                        Attach file
+                                      </Button>
+                                    </div>
+                                  ))}
                               </div>
+                              <Accordion type="single" collapsible>
+                                <AccordionItem value="cookies">
+                                  <AccordionTrigger>Cookie jar ({cookieJar.length})</AccordionTrigger>
+                                  <AccordionContent>
+                                    <div className="flex items-center justify-between mb-2">
+                                      <label className="flex items-center gap-2 text-sm">
+                                        <input
+                                          type="checkbox"
+                                          checked={useCookieJar}
+                                          onChange={(e) => setUseCookieJar(e.target.checked)}
+                                        />
+                                        Send cookies from the jar instead of the recorded Cookie header
+                                      </label>
+                                      <Button variant="outline" onClick={resetCookieJar}>
+                                        Reset from archive
+                                      </Button>
+                                    </div>
+                                    <div className="grid gap-2">
+                                      {cookieJar.map((cookie, i) => (
+                                        <div key={`${cookie.domain}${cookie.path}${cookie.name}`} className="grid grid-cols-12 items-center gap-2 text-sm">
+                                          <span className="col-span-3 truncate" title={`${cookie.hostOnly ? "" : "."}${cookie.domain}${cookie.path}`}>
+                                            {cookie.hostOnly ? "" : "."}{cookie.domain}{cookie.path}
+                                          </span>
+                                          <span className="col-span-2 truncate font-mono">{cookie.name}</span>
+                                          <Input
+                                            className="col-span-4"
+                                            value={cookie.value}
+                                            onChange={(e) => setCookieJar(cookieJar.map((c, j) => j === i ? { ...c, value: e.target.value } : c))}
+                                            onBlur={() => updateCookieJar(cookieJar)}
+                                          />
+                                          <span className="col-span-2 text-xs text-muted-foreground truncate">
+                                            {[cookie.secure && "Secure", cookie.httpOnly && "HttpOnly", cookie.expires ?? "session"]
+                                              .filter(Boolean)
+                                              .join(", ")}
+                                          </span>
+                                          <Button variant="ghost" onClick={() => updateCookieJar(cookieJar.filter((_, j) => j !== i))}>
+                                            Remove
+                                          </Button>
+                                        </div>
+                                      ))}
+                                      {cookieJar.length === 0 && (
+                                        <p className="text-sm text-muted-foreground">The jar is empty.</p>
+                                      )}
+                                    </div>
+                                  </AccordionContent>
+                                </AccordionItem>
+                                <AccordionItem value="connection">
+                                  <AccordionTrigger>Connection</AccordionTrigger>
+                                  <AccordionContent>
+                                    <div className="grid gap-3">
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="connect-timeout" className="text-right">Connect timeout (ms)</Label>
+                                        <Input
+                                          id="connect-timeout"
+                                          type="number"
+                                          min={0}
+                                          placeholder="none"
+                                          value={clientConfig.connectTimeoutMs ?? ""}
+                                          onChange={(e) => setClientConfig({ ...clientConfig, connectTimeoutMs: parseMillis(e.target.value) })}
+                                          className="col-span-3"
+                                        />
+                                      </div>
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="timeout" className="text-right">Total timeout (ms)</Label>
+                                        <Input
+                                          id="timeout"
+                                          type="number"
+                                          min={0}
+                                          placeholder="none"
+                                          value={clientConfig.timeoutMs ?? ""}
+                                          onChange={(e) => setClientConfig({ ...clientConfig, timeoutMs: parseMillis(e.target.value) })}
+                                          className="col-span-3"
+                                        />
+                                      </div>
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="max-redirects" className="text-right">Max redirects</Label>
+                                        <Input
+                                          id="max-redirects"
+                                          type="number"
+                                          min={0}
+                                          value={clientConfig.maxRedirects}
+                                          onChange={(e) => setClientConfig({ ...clientConfig, maxRedirects: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+                                          className="col-span-3"
+                                        />
+                                      </div>
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="http-version" className="text-right">HTTP version</Label>
+                                        <select
+                                          id="http-version"
+                                          className="col-span-3 p-2 rounded border border-input bg-background text-sm"
+                                          value={clientConfig.httpVersion}
+                                          onChange={(e) => setClientConfig({ ...clientConfig, httpVersion: e.target.value as ClientConfig["httpVersion"] })}
+                                        >
+                                          <option value="auto">Negotiate</option>
+                                          <option value="http1">HTTP/1.1 only</option>
+                                          <option value="http2">HTTP/2 only</option>
+                                        </select>
+                                      </div>
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="proxy" className="text-right">Proxy</Label>
+                                        <Input
+                                          id="proxy"
+                                          placeholder="http://host:port or socks5://host:port"
+                                          value={clientConfig.proxy ?? ""}
+                                          onChange={(e) => setClientConfig({ ...clientConfig, proxy: e.target.value || undefined })}
+                                          className="col-span-3"
+                                        />
+                                      </div>
+                                      <div className="grid grid-cols-4 items-center gap-4">
+                                        <Label htmlFor="insecure" className="text-right">Invalid certificates</Label>
+                                        <label className="col-span-3 flex items-center gap-2 text-sm">
+                                          <input
+                                            id="insecure"
+                                            type="checkbox"
+                                            checked={clientConfig.acceptInvalidCerts}
+                                            onChange={(e) => setClientConfig({ ...clientConfig, acceptInvalidCerts: e.target.checked })}
+                                          />
+                                          Accept (self-signed or expired)
+                                        </label>
+                                      </div>
+                                      {([
+                                        ["caBundle", "CA bundle"],
+                                        ["clientCert", "Client certificate"],
+                                        ["clientKey", "Client key"],
+                                      ] as const).map(([field, label]) => (
+                                        <div key={field} className="grid grid-cols-4 items-center gap-4">
+                                          <Label className="text-right">{label}</Label>
+                                          <span className="col-span-2 text-sm truncate">
+                                            {clientConfig[field] || "none"}
+                                          </span>
+                                          <div className="flex gap-2">
+                                            <Button variant="outline" onClick={() => pickClientFile(field)}>
+                                              Choose
+                                            </Button>
+                                            {clientConfig[field] && (
+                                              <Button variant="ghost" onClick={() => setClientConfig({ ...clientConfig, [field]: undefined })}>
+                                                Clear
+                                              </Button>
+                                            )}
+                                          </div>
+                                        </div>
+                                      ))}
+                                    </div>
+                                  </AccordionContent>
+                                </AccordionItem>
+                              </Accordion>
                               <div className="flex justify-end">
                                 <Button onClick={() => replayRequest(editedRequest || selectedEntry.request)}>
                                   Send Request
@@ -588,29 +2166,105 @@ function App() {
                                 <div className="mt-4">
                                   <h3 className="font-bold mb-2">Response</h3>
                                   <div className="p-2 bg-card border rounded">
-                                    <div>Status: {replayResponse.status}</div>
+                                    <div className="flex items-center justify-between">
+                                      <div>
+                                        Status: {replayResponse.response.status} {replayResponse.response.statusText}
+                                        {" "}({replayResponse.response.httpVersion}, {replayResponse.time.toFixed(1)} ms)
+                                      </div>
+                                      <div className="flex gap-2">
+                                        {currentFile && (
+                                          <Button variant="outline" onClick={() => compareReplay(replayResponse)}>
+                                            Compare with recorded
+                                          </Button>
+                                        )}
+                                        {currentFile && (
+                                          <Button variant="outline" onClick={() => appendReplay(replayResponse)}>
+                                            Add to archive
+                                          </Button>
+                                        )}
+                                      </div>
+                                    </div>
                                     <Accordion type="single" collapsible>
+                                      <AccordionItem value="diff">
+                                        <AccordionTrigger>
+                                          Differences{replayDiff && (replayDiff.same ? " (none)" : "")}
+                                        </AccordionTrigger>
+                                        <AccordionContent>
+                                          <div className="grid gap-2 text-xs">
+                                            <Label htmlFor="ignore-paths">JSON paths to ignore, one per line</Label>
+                                            <textarea
+                                              id="ignore-paths"
+                                              value={ignorePaths}
+                                              placeholder="$.meta.requestId"
+                                              onChange={(e) => setIgnorePaths(e.target.value)}
+                                              className="min-h-[50px] p-2 border rounded bg-card font-mono"
+                                            />
+                                            {replayDiff && (
+                                              <div className="font-mono space-y-1">
+                                                {replayDiff.status && (
+                                                  <div>Status {replayDiff.status.recorded} → {replayDiff.status.replayed}</div>
+                                                )}
+                                                {replayDiff.headers.map(h => (
+                                                  <div key={h.name}>
+                                                    {h.recorded !== null && <div className="text-red-600">- {h.name}: {h.recorded}</div>}
+                                                    {h.replayed !== null && <div className="text-green-600">+ {h.name}: {h.replayed}</div>}
+                                                  </div>
+                                                ))}
+                                                {replayDiff.body.kind === "unavailable" && <div>Body not captured; not compared</div>}
+                                                {replayDiff.body.kind === "binary" && (
+                                                  <div>Binary body differs ({replayDiff.body.recordedSize} → {replayDiff.body.replayedSize} bytes)</div>
+                                                )}
+                                                {replayDiff.body.kind === "json" && replayDiff.body.changes.map(c => (
+                                                  <div key={c.path}>
+                                                    {c.recorded !== undefined && <div className="text-red-600">- {c.path} = {JSON.stringify(c.recorded)}</div>}
+                                                    {c.replayed !== undefined && <div className="text-green-600">+ {c.path} = {JSON.stringify(c.replayed)}</div>}
+                                                  </div>
+                                                ))}
+                                                {replayDiff.body.kind === "text" && (
+                                                  <pre className="max-h-64 overflow-auto">
+                                                    {replayDiff.body.lines.map((line, i) => (
+                                                      <div
+                                                        key={i}
+                                                        className={line.op === "removed" ? "text-red-600" : line.op === "added" ? "text-green-600" : "text-muted-foreground"}
+                                                      >
+                                                        {line.op === "removed" ? "- " : line.op === "added" ? "+ " : "  "}{line.text}
+                                                      </div>
+                                                    ))}
+                                                  </pre>
+                                                )}
+                                                <div>
+                                                  Time {replayDiff.timing.recorded.toFixed(1)} → {replayDiff.timing.replayed.toFixed(1)} ms
+                                                  ({replayDiff.timing.delta >= 0 ? "+" : ""}{replayDiff.timing.delta.toFixed(1)} ms)
+                                                </div>
+                                                {replayDiff.timing.phases.map(p => (
+                                                  <div key={p.name} className="text-muted-foreground">
+                                                    {p.name}: {p.recorded.toFixed(1)} → {p.replayed.toFixed(1)} ms
+                                                  </div>
+                                                ))}
+                                              </div>
+                                            )}
+                                          </div>
+                                        </AccordionContent>
+                                      </AccordionItem>
+                                      <AccordionItem value="timings">
+                                        <AccordionTrigger>Timings</AccordionTrigger>
+                                        <AccordionContent>
+                                          <pre className="text-xs">{JSON.stringify(replayResponse.timings, null, 2)}</pre>
+                                        </AccordionContent>
+                                      </AccordionItem>
                                       <AccordionItem value="headers">
                                         <AccordionTrigger>Headers</AccordionTrigger>
                                         <AccordionContent>
-                                          <pre className="text-xs">{JSON.stringify(replayResponse.headers, null, 2)}</pre>
+                                          <pre className="text-xs">{JSON.stringify(replayResponse.response.headers, null, 2)}</pre>
                                         </AccordionContent>
                                       </AccordionItem>
                                       <AccordionItem value="body">
                                         <AccordionTrigger>Body</AccordionTrigger>
                                         <AccordionContent>
-                                          <SyntaxHighlighter 
-                                            language="json" 
-                                            style={vscDarkPlus}
-                                            customStyle={{
-                                              backgroundColor: 'var(--background)',
-                                              borderRadius: '0.25rem',
-                                              padding: '1rem',
-                                              border: '1px solid var(--border)'
-                                            }}
-                                          >
-                                            {replayResponse.body}
-                                          </SyntaxHighlighter>
+                                          <div className="text-xs mb-2">
+                                            {replayResponse.response.content.size} bytes decoded, {replayResponse.response.bodySize} bytes on the wire
+                                          </div>
+                                          {formatContent(replayResponse.response.content)}
                                         </AccordionContent>
                                       </AccordionItem>
                                     </Accordion>
@@ -893,23 +2547,27 @@ function App() {
                       <CardTitle>HAR File Summary</CardTitle>
                     </CardHeader>
                     <CardContent>
+                      {summary ? (
                       <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
                         {/* Basic Statistics */}
                         <div className="space-y-4">
                           <h3 className="text-lg font-semibold">Basic Statistics</h3>
                           <div className="grid grid-cols-2 gap-2">
                             <div className="font-medium">Total Requests:</div>
-                            <div>{harFile.log.entries.length}</div>
+                            <div>{summary.entries}</div>
+
+                            <div className="font-medium">Transferred:</div>
+                            <div>{(summary.transferBytes / 1024).toFixed(2)} KB</div>
 
-                            <div className="font-medium">Total Size:</div>
-                            <div>{(harFile.log.entries.reduce((sum, entry) => sum + entry.response.bodySize, 0) / 1024).toFixed(2)} KB</div>
+                            <div className="font-medium">Content:</div>
+                            <div>{(summary.contentBytes / 1024).toFixed(2)} KB</div>
 
-                            <div className="font-medium">Average Response Time:</div>
-                            <div>{(harFile.log.entries.reduce((sum, entry) => sum + entry.time, 0) / harFile.log.entries.length).toFixed(2)} ms</div>
+                            <div className="font-medium">Time Span:</div>
+                            <div>{summary.spanMs.toFixed(2)} ms</div>
 
                             <div className="font-medium">Slowest Request:</div>
                             <div>
-                              {(() => {
+                              {harFile.log.entries.length > 0 && (() => {
                                 const slowest = harFile.log.entries.reduce((prev, current) => 
                                   (prev.time > current.time) ? prev : current);
                                 try {
@@ -922,111 +2580,104 @@ function App() {
                           </div>
                         </div>
 
+                        {/* Timing Percentiles */}
+                        <div className="space-y-4">
+                          <h3 className="text-lg font-semibold">Timings</h3>
+                          <div className="grid grid-cols-4 gap-2 text-sm">
+                            <div className="font-medium">Phase</div>
+                            <div className="font-medium">p50</div>
+                            <div className="font-medium">p90</div>
+                            <div className="font-medium">p99</div>
+                            {([
+                              ["Total", summary.time],
+                              ["Blocked", summary.timings.blocked],
+                              ["DNS", summary.timings.dns],
+                              ["Connect", summary.timings.connect],
+                              ["TLS", summary.timings.ssl],
+                              ["Send", summary.timings.send],
+                              ["Wait", summary.timings.wait],
+                              ["Receive", summary.timings.receive],
+                            ] as [string, Distribution][])
+                              .filter(([_, d]) => d.count > 0)
+                              .map(([phase, d]) => (
+                                <React.Fragment key={phase}>
+                                  <div>{phase}</div>
+                                  <div>{d.p50.toFixed(2)} ms</div>
+                                  <div>{d.p90.toFixed(2)} ms</div>
+                                  <div>{d.p99.toFixed(2)} ms</div>
+                                </React.Fragment>
+                              ))}
+                          </div>
+                        </div>
+
                         {/* Method Distribution */}
                         <div className="space-y-4">
                           <h3 className="text-lg font-semibold">HTTP Methods</h3>
-                          {(() => {
-                            const methodCounts = harFile.log.entries.reduce((acc, entry) => {
-                              acc[entry.request.method] = (acc[entry.request.method] || 0) + 1;
-                              return acc;
-                            }, {} as Record<string, number>);
-
-                            return Object.entries(methodCounts).map(([method, count]) => (
-                              <div key={method} className="space-y-1">
-                                <div className="flex justify-between">
-                                  <span className="font-medium">{method}</span>
-                                  <span>{count} ({((count / harFile.log.entries.length) * 100).toFixed(1)}%)</span>
-                                </div>
-                                <div className="h-2 bg-secondary/20 rounded-full">
-                                  <div 
-                                    className="h-full bg-primary rounded-full" 
-                                    style={{ width: `${(count / harFile.log.entries.length) * 100}%` }}
-                                  />
-                                </div>
+                          {Object.entries(summary.methods).map(([method, count]) => (
+                            <div key={method} className="space-y-1">
+                              <div className="flex justify-between">
+                                <span className="font-medium">{method}</span>
+                                <span>{count} ({((count / summary.entries) * 100).toFixed(1)}%)</span>
+                              </div>
+                              <div className="h-2 bg-secondary/20 rounded-full">
+                                <div 
+                                  className="h-full bg-primary rounded-full" 
+                                  style={{ width: `${(count / summary.entries) * 100}%` }}
+                                />
                               </div>
-                            ));
-                          })()}
+                            </div>
+                          ))}
                         </div>
 
                         {/* Status Code Distribution */}
                         <div className="space-y-4">
                           <h3 className="text-lg font-semibold">Status Codes</h3>
-                          {(() => {
-                            const statusGroups = {
-                              "2xx Success": 0,
-                              "3xx Redirection": 0,
-                              "4xx Client Error": 0,
-                              "5xx Server Error": 0,
-                              "Other": 0
-                            };
-
-                            harFile.log.entries.forEach(entry => {
-                              const status = entry.response.status;
-                              if (status >= 200 && status < 300) statusGroups["2xx Success"]++;
-                              else if (status >= 300 && status < 400) statusGroups["3xx Redirection"]++;
-                              else if (status >= 400 && status < 500) statusGroups["4xx Client Error"]++;
-                              else if (status >= 500 && status < 600) statusGroups["5xx Server Error"]++;
-                              else statusGroups["Other"]++;
-                            });
-
-                            return Object.entries(statusGroups)
-                              .filter(([_, count]) => count > 0)
-                              .map(([group, count]) => (
-                                <div key={group} className="space-y-1">
-                                  <div className="flex justify-between">
-                                    <span className="font-medium">{group}</span>
-                                    <span>{count} ({((count / harFile.log.entries.length) * 100).toFixed(1)}%)</span>
-                                  </div>
-                                  <div className="h-2 bg-secondary/20 rounded-full">
-                                    <div 
-                                      className={`h-full rounded-full ${
-                                        group === "2xx Success" ? "bg-green-500" :
-                                        group === "3xx Redirection" ? "bg-blue-500" :
-                                        group === "4xx Client Error" ? "bg-yellow-500" :
-                                        group === "5xx Server Error" ? "bg-red-500" : "bg-gray-500"
-                                      }`}
-                                      style={{ width: `${(count / harFile.log.entries.length) * 100}%` }}
-                                    />
-                                  </div>
-                                </div>
-                            ));
-                          })()}
+                          {Object.entries(summary.statuses).map(([group, count]) => (
+                            <div key={group} className="space-y-1">
+                              <div className="flex justify-between">
+                                <span className="font-medium">{group}</span>
+                                <span>{count} ({((count / summary.entries) * 100).toFixed(1)}%)</span>
+                              </div>
+                              <div className="h-2 bg-secondary/20 rounded-full">
+                                <div 
+                                  className={`h-full rounded-full ${
+                                    group === "2xx" ? "bg-green-500" :
+                                    group === "3xx" ? "bg-blue-500" :
+                                    group === "4xx" ? "bg-yellow-500" :
+                                    group === "5xx" ? "bg-red-500" : "bg-gray-500"
+                                  }`}
+                                  style={{ width: `${(count / summary.entries) * 100}%` }}
+                                />
+                              </div>
+                            </div>
+                          ))}
                         </div>
 
                         {/* Content Type Distribution */}
                         <div className="space-y-4">
                           <h3 className="text-lg font-semibold">Content Types</h3>
-                          {(() => {
-                            const contentTypes = harFile.log.entries.reduce((acc, entry) => {
-                              const mimeType = entry.response.content.mimeType.split(';')[0].trim();
-                              const type = mimeType.includes('/') 
-                                ? mimeType.split('/')[1] 
-                                : mimeType;
-
-                              acc[type] = (acc[type] || 0) + 1;
-                              return acc;
-                            }, {} as Record<string, number>);
-
-                            return Object.entries(contentTypes)
-                              .sort((a, b) => b[1] - a[1])
-                              .slice(0, 5)
-                              .map(([type, count]) => (
-                                <div key={type} className="space-y-1">
-                                  <div className="flex justify-between">
-                                    <span className="font-medium">{type || 'unknown'}</span>
-                                    <span>{count} ({((count / harFile.log.entries.length) * 100).toFixed(1)}%)</span>
-                                  </div>
-                                  <div className="h-2 bg-secondary/20 rounded-full">
-                                    <div 
-                                      className="h-full bg-purple-500 rounded-full" 
-                                      style={{ width: `${(count / harFile.log.entries.length) * 100}%` }}
-                                    />
-                                  </div>
+                          {Object.entries(summary.mimeTypes)
+                            .sort((a, b) => b[1] - a[1])
+                            .slice(0, 5)
+                            .map(([type, count]) => (
+                              <div key={type} className="space-y-1">
+                                <div className="flex justify-between">
+                                  <span className="font-medium">{type}</span>
+                                  <span>{count} ({((count / summary.entries) * 100).toFixed(1)}%)</span>
                                 </div>
-                            ));
-                          })()}
+                                <div className="h-2 bg-secondary/20 rounded-full">
+                                  <div 
+                                    className="h-full bg-purple-500 rounded-full" 
+                                    style={{ width: `${(count / summary.entries) * 100}%` }}
+                                  />
+                                </div>
+                              </div>
+                          ))}
                         </div>
                       </div>
+                      ) : (
+                        <p className="text-muted-foreground">Summarizing…</p>
+                      )}
                     </CardContent>
                   </Card>
                 </TabsContent>
@@ -1051,7 +2702,30 @@ function App() {
             <p className="text-center text-muted-foreground mb-4">
               Open a HAR file to analyze network requests and responses
             </p>
-            <Button onClick={openHarFile}>Open HAR File</Button>
+            {loadProgress ? (
+            <>
+              <span className="text-sm">
+                Loading {loadProgress.entries} entries ({Math.round((loadProgress.bytesRead / Math.max(loadProgress.totalBytes, 1)) * 100)}%)
+              </span>
+              <Button onClick={cancelLoad}>Cancel</Button>
+            </>
+          ) : (
+            <>
+              <select
+                className="p-2 rounded border border-input bg-background text-sm"
+                value={parseMode}
+                onChange={(e) => setParseMode(e.target.value as ParseMode)}
+                title="Parsing mode"
+              >
+                <option value="standard">Standard</option>
+                <option value="lenient">Lenient</option>
+                <option value="strict">Strict</option>
+              </select>
+              {currentFile && <Button onClick={validateHarFile}>Validate</Button>}
+              {harFile && <Button onClick={saveHarFile}>Save HAR File</Button>}
+              <Button onClick={openHarFile}>Open HAR File</Button>
+            </>
+          )}
           </CardContent>
         </Card>
       )}